@@ -0,0 +1,157 @@
+use core::{alloc::Layout, ptr::NonNull};
+
+use crate::{
+    common::{AllocInit, BAllocator},
+    ops::AllocOp,
+};
+
+/// Outcome of `run_workload`.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct WorkloadResult {
+    /// Allocs and reallocs that succeeded.
+    pub allocations: usize,
+    /// Allocs, reallocs and deallocs the allocator returned an error for.
+    pub failures: usize,
+    /// Largest single block that could still be allocated once the workload
+    /// finished.
+    pub largest_free: usize,
+    /// Free bytes left once the workload finished, as far as they could be
+    /// found by allocating them.
+    pub free_bytes: usize,
+    /// `1 - largest_free / free_bytes`, 0 when nothing is free.
+    pub fragmentation: f32,
+}
+
+/// Runs `ops` against `alloc`, keeping up to `N` live blocks, and reports
+/// how it went so different allocators can be compared on the same
+/// workload. Ops that do not make sense, such as freeing an empty slot, are
+/// ignored.
+///
+/// Free memory is measured by repeatedly allocating the largest block
+/// `can_allocate` reports into the unused slots, so it is an underestimate
+/// when the slots run out first. Every block is freed again before
+/// returning.
+///
+/// # Safety
+/// `alloc` must not be used by anything else during the workload.
+///
+/// # Panics
+/// If `alloc` has not been initialized.
+pub unsafe fn run_workload<A: BAllocator + AllocInit, const N: usize>(
+    alloc: &A,
+    ops: &[AllocOp],
+) -> WorkloadResult {
+    assert!(
+        alloc.is_initialized(),
+        "run_workload: uninitialized allocator"
+    );
+    let mut live: [Option<(NonNull<u8>, Layout)>; N] = [None; N];
+    let mut allocations = 0;
+    let mut failures = 0;
+
+    for &op in ops {
+        unsafe {
+            match op {
+                AllocOp::Alloc { size, align } => {
+                    let Ok(layout) = Layout::from_size_align(size, align) else {
+                        continue;
+                    };
+                    let Some(slot) = live.iter_mut().find(|slot| slot.is_none()) else {
+                        continue;
+                    };
+                    match alloc.try_allocate(layout) {
+                        Ok(ptr) => {
+                            *slot = Some((ptr, layout));
+                            allocations += 1;
+                        }
+                        Err(_) => failures += 1,
+                    }
+                }
+                AllocOp::Dealloc { index } => {
+                    let Some((ptr, layout)) = live.get_mut(index).and_then(Option::take) else {
+                        continue;
+                    };
+                    if alloc.try_deallocate(ptr, layout).is_err() {
+                        failures += 1;
+                    }
+                }
+                AllocOp::Realloc { index, new_size } => {
+                    let Some(Some((ptr, layout))) = live.get(index).copied() else {
+                        continue;
+                    };
+                    let Ok(new_layout) = Layout::from_size_align(new_size, layout.align()) else {
+                        continue;
+                    };
+                    match alloc.try_reallocate(ptr, layout, new_size) {
+                        Ok(ptr) => {
+                            live[index] = Some((ptr, new_layout));
+                            allocations += 1;
+                        }
+                        Err(_) => failures += 1,
+                    }
+                }
+            }
+        }
+    }
+
+    let largest_free = largest_allocatable(alloc);
+    let mut free_bytes = 0;
+    for slot in live.iter_mut().filter(|slot| slot.is_none()) {
+        let size = largest_allocatable(alloc);
+        if size == 0 {
+            break;
+        }
+        let layout = Layout::from_size_align(size, 1).unwrap();
+        let Ok(ptr) = (unsafe { alloc.try_allocate(layout) }) else {
+            break;
+        };
+        *slot = Some((ptr, layout));
+        free_bytes += size;
+    }
+
+    for (ptr, layout) in live.iter_mut().filter_map(Option::take) {
+        let _ = unsafe { alloc.try_deallocate(ptr, layout) };
+    }
+
+    let fragmentation = if free_bytes == 0 {
+        0.0
+    } else {
+        1.0 - largest_free as f32 / free_bytes as f32
+    };
+    return WorkloadResult {
+        allocations,
+        failures,
+        largest_free,
+        free_bytes,
+        fragmentation,
+    };
+}
+
+/// Largest byte aligned size `alloc` reports it can allocate.
+fn largest_allocatable<A: BAllocator>(alloc: &A) -> usize {
+    let fits = |size: usize| {
+        Layout::from_size_align(size, 1).is_ok_and(|layout| alloc.can_allocate(layout))
+    };
+    if !fits(1) {
+        return 0;
+    }
+
+    let mut low = 1;
+    let mut high = 2;
+    while fits(high) {
+        low = high;
+        high = match high.checked_mul(2) {
+            Some(high) => high,
+            None => return low,
+        };
+    }
+    while high - low > 1 {
+        let mid = low + (high - low) / 2;
+        if fits(mid) {
+            low = mid;
+        } else {
+            high = mid;
+        }
+    }
+    return low;
+}
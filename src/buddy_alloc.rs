@@ -1,11 +1,18 @@
-use spin::Mutex;
-
-use crate::common::Alloc;
+use crate::{
+    common::{Alloc, RwLocked},
+    lock::Mutex,
+};
 
 mod bconst;
 mod locked;
 mod lockless;
 
-pub use crate::buddy_alloc::locked::LockedBuddy;
+#[cfg(feature = "buddy_track_live")]
+pub use crate::buddy_alloc::locked::LIVE_CAPACITY;
+pub use crate::buddy_alloc::locked::{
+    BuddyBuilder, LockedBuddy, MAX_ORDER, MIN_ORDER, NR_MAX_ORDER, PAGE_SIZE, TINY_BLOCKS,
+    TINY_ORDER, TINY_SLOT_SIZE,
+};
 
 pub type LockedBuddyAlloc = Alloc<Mutex<LockedBuddy>>;
+pub type RwLockedBuddyAlloc = RwLocked<LockedBuddy>;
@@ -2,15 +2,25 @@ use core::{
     alloc::Layout,
     fmt::{Debug, Formatter, Result as FmtResult},
     mem::{align_of, size_of},
+    num::NonZeroUsize,
     ptr::{NonNull, null_mut},
 };
 
 #[cfg(debug_assertions)]
 use log::{debug, error, trace};
-use spin::Mutex;
+use spin::RwLock;
 
-use crate::common::{
-    Alloc, AllocInit, BAllocator, BAllocatorError, HEAP_SIZE_ZERO, HEAP_START_NULL, OOM, align_up,
+#[cfg(feature = "fill_on_free")]
+use crate::common::fill_freed;
+#[cfg(feature = "buddy_track_live")]
+use crate::common::meta_word;
+use crate::{
+    common::{
+        ALLOCATOR_UNINITIALIZED, Alloc, AllocBounds, AllocInit, BAllocatorError, BlockMap,
+        HEAP_END_OVERFLOWED, HEAP_SIZE_ZERO, HEAP_START_NULL, LockedInner, MetaWord, OOM, align_up,
+        check_alloc, check_free, check_write, dangling, fits_meta_word, from_meta_word, prefault,
+    },
+    lock::Mutex,
 };
 
 #[derive(Debug)]
@@ -28,12 +38,18 @@ impl FreeList {
     }
 }
 
+// Packed with `compact_meta`, otherwise `nr_free` pads back out to the
+// alignment of `head`. Fields are only ever copied out, never borrowed.
 #[derive(Debug)]
+#[cfg_attr(feature = "compact_meta", repr(C, packed(4)))]
 pub struct FreeArea {
     pub head: Option<NonNull<FreeList>>,
-    pub nr_free: usize,
+    pub nr_free: MetaWord,
 }
 
+#[cfg(feature = "compact_meta")]
+const _: () = assert!(size_of::<FreeArea>() == size_of::<usize>() + size_of::<MetaWord>());
+
 impl FreeArea {
     const fn new() -> FreeArea {
         FreeArea {
@@ -42,6 +58,10 @@ impl FreeArea {
         }
     }
 
+    fn len(&self) -> usize {
+        return from_meta_word(self.nr_free);
+    }
+
     fn push(&mut self, mut value: NonNull<FreeList>) {
         unsafe {
             value.as_mut().next = self.head;
@@ -62,22 +82,133 @@ impl FreeArea {
             None
         }
     }
+
+    /// Walks the list to check `nr_free` matches its actual length.
+    #[cfg(debug_assertions)]
+    fn debug_assert_consistent(&self, order: usize) {
+        let mut len = 0;
+        let mut current = self.head;
+        while let Some(node) = current {
+            len += 1;
+            current = unsafe { node.as_ref().next };
+        }
+        debug_assert_eq!(
+            len, self.nr_free,
+            "Free list of order {order} holds {len} blocks but nr_free is {}",
+            self.nr_free
+        );
+    }
+
+    fn contains(&self, addr: usize) -> bool {
+        let mut current = self.head;
+        while let Some(node) = current {
+            if node.as_ptr() as usize == addr {
+                return true;
+            }
+            current = unsafe { node.as_ref().next };
+        }
+        return false;
+    }
+
+    fn remove(&mut self, addr: usize) -> bool {
+        let mut prev: Option<NonNull<FreeList>> = None;
+        let mut current = self.head;
+
+        while let Some(mut node) = current {
+            unsafe {
+                let next = node.as_ref().next;
+                if node.as_ref().start_addr() == addr {
+                    match prev {
+                        Some(mut prev) => prev.as_mut().next = next,
+                        None => self.head = next,
+                    }
+                    node.as_mut().next = None;
+                    self.nr_free -= 1;
+                    return true;
+                }
+                prev = current;
+                current = next;
+            }
+        }
+        return false;
+    }
 }
 
 pub const PAGE_SIZE: usize = 8;
+/// Smallest order blocks are handed out at. Orders below it are never
+/// populated, so a block of `MIN_ORDER` is never split further.
 pub const MIN_ORDER: usize = 0;
 pub const MAX_ORDER: usize = 32;
 pub const NR_MAX_ORDER: usize = MAX_ORDER + 1;
+/// Slot size of the tiny slab. Only requests smaller than this, and aligned
+/// to at most this, are served from it.
+pub const TINY_SLOT_SIZE: usize = PAGE_SIZE / 2;
+/// Slots carved out of each tiny slab block, one per bit of its bitmap.
+const TINY_SLOTS: usize = u32::BITS as usize;
+/// Order of the buddy blocks the tiny slab carves into slots.
+pub const TINY_ORDER: usize = (TINY_SLOTS * TINY_SLOT_SIZE / PAGE_SIZE).ilog2() as usize;
+/// Number of buddy blocks the tiny slab can hold at once. Tiny requests
+/// past what they fit are served with a whole block as usual.
+pub const TINY_BLOCKS: usize = 4;
+/// Number of live blocks `buddy_track_live` can keep track of at once.
+#[cfg(feature = "buddy_track_live")]
+pub const LIVE_CAPACITY: usize = 64;
+
+/// Live blocks, plus a count of those that did not fit in the table.
+#[cfg(feature = "buddy_track_live")]
+#[derive(Debug)]
+struct LiveBlocks {
+    /// Order of each block, by offset from the heap base.
+    blocks: BlockMap<MetaWord, MetaWord, LIVE_CAPACITY>,
+    untracked: usize,
+}
+
+#[cfg(feature = "buddy_track_live")]
+impl LiveBlocks {
+    const fn new() -> LiveBlocks {
+        LiveBlocks {
+            blocks: BlockMap::new((0, 0)),
+            untracked: 0,
+        }
+    }
+
+    fn insert(&mut self, offset: usize, order: usize) {
+        if !self.blocks.insert(meta_word(offset), meta_word(order)) {
+            #[cfg(debug_assertions)]
+            error!("Live block table full, not tracking offset {offset:#X}");
+            self.untracked += 1;
+        }
+    }
+
+    fn remove(&mut self, offset: usize) {
+        if self.blocks.remove(meta_word(offset)).is_none() {
+            self.untracked = self.untracked.saturating_sub(1);
+        }
+    }
+}
 
 pub struct LockedBuddy {
     base: *mut u8,
     size: usize,
     list_areas: [FreeArea; NR_MAX_ORDER],
+    merge_threshold: usize,
+    merges: usize,
+    splits: usize,
+    /// Set by `new_with_static_heap`, the heap is only put on the free lists
+    /// on first use as that cannot be done in a const fn.
+    pending_heap: bool,
+    tiny_enabled: bool,
+    /// Bitmap of the used slots of each tiny slab block, by address. Kept
+    /// here rather than in the block so every slot is usable.
+    tiny: BlockMap<usize, u32, TINY_BLOCKS>,
+    #[cfg(feature = "buddy_track_live")]
+    live: LiveBlocks,
+    on_top_free: Option<fn(usize)>,
 }
 
 impl Debug for Alloc<Mutex<LockedBuddy>> {
     fn fmt(&self, f: &mut Formatter<'_>) -> FmtResult {
-        let alloc = self.alloc.lock();
+        let alloc = self.lock();
         writeln!(f, "LockedBuddy {{")?;
         writeln!(f, "    base: {:?}", alloc.base)?;
         writeln!(f, "    size: {}", alloc.size)?;
@@ -96,26 +227,90 @@ impl Default for LockedBuddy {
 }
 
 impl LockedBuddy {
+    /// Size of a `MAX_ORDER` block, the largest allocation the free lists can
+    /// describe, saturating where it does not fit in a `usize`. The heap
+    /// itself may well be smaller.
+    pub const MAX_ALLOC_SIZE: usize = if (PAGE_SIZE as u128) << MAX_ORDER > usize::MAX as u128 {
+        usize::MAX
+    } else {
+        PAGE_SIZE << MAX_ORDER
+    };
+
     const fn new() -> LockedBuddy {
         LockedBuddy {
             base: null_mut(),
             size: 0,
             list_areas: [const { FreeArea::new() }; NR_MAX_ORDER],
+            merge_threshold: 0,
+            merges: 0,
+            splits: 0,
+            pending_heap: false,
+            tiny_enabled: false,
+            tiny: BlockMap::new((0, 0)),
+            #[cfg(feature = "buddy_track_live")]
+            live: LiveBlocks::new(),
+            on_top_free: None,
+        }
+    }
+
+    const fn new_with_static_heap(start: usize, size: usize) -> LockedBuddy {
+        NonZeroUsize::new(start).expect(HEAP_START_NULL);
+        NonZeroUsize::new(size).expect(HEAP_SIZE_ZERO);
+        start.checked_add(size).expect(HEAP_END_OVERFLOWED);
+        assert!(
+            size.is_power_of_two(),
+            "Buddy Allocator heap not a power of two"
+        );
+        assert!(
+            size <= LockedBuddy::MAX_ALLOC_SIZE,
+            "Buddy Allocator heap larger than a MAX_ORDER block"
+        );
+        assert!(
+            start.is_multiple_of(align_of::<FreeList>()),
+            "Given start is not 8 byte aligned"
+        );
+        assert!(fits_meta_word(size), "Heap too large for compact_meta");
+
+        let mut buddy = LockedBuddy::new();
+        buddy.base = start as *mut u8;
+        buddy.size = size;
+        buddy.pending_heap = true;
+        return buddy;
+    }
+
+    #[cfg(debug_assertions)]
+    fn debug_assert_list_consistent(&self) {
+        for (order, area) in self.list_areas.iter().enumerate() {
+            area.debug_assert_consistent(order);
+        }
+    }
+
+    fn claim_pending_heap(&mut self) {
+        if self.pending_heap {
+            self.pending_heap = false;
+            unsafe { self.add_free_area(self.base as usize, self.top_order()) };
         }
     }
 
     unsafe fn init(&mut self, start: usize, size: usize) {
         debug_assert!(start != 0, "{}", HEAP_START_NULL);
         debug_assert!(size > 0, "{}", HEAP_SIZE_ZERO);
+        start.checked_add(size).expect(HEAP_END_OVERFLOWED);
         debug_assert!(
             size.is_power_of_two(),
             "Buddy Allocator heap not a power of two"
         );
+        // Blocks above MAX_ORDER have no free list to go on.
+        assert!(
+            size <= LockedBuddy::MAX_ALLOC_SIZE,
+            "Buddy Allocator heap larger than a MAX_ORDER block"
+        );
         debug_assert_eq!(
             align_up(start, align_of::<FreeList>()),
             start,
             "Given start is not 8 byte aligned"
         );
+        assert!(fits_meta_word(size), "Heap too large for compact_meta");
 
         self.base = start as *mut u8;
         self.size = size;
@@ -125,6 +320,38 @@ impl LockedBuddy {
         }
     }
 
+    unsafe fn init_aligned(
+        &mut self,
+        start: usize,
+        size: usize,
+    ) -> Result<(usize, usize), BAllocatorError> {
+        let aligned = align_up(start, align_of::<FreeList>());
+        let size = size
+            .checked_sub(aligned - start)
+            .filter(|&size| size >= PAGE_SIZE)
+            .ok_or(BAllocatorError::Oom(None))?;
+        // The heap has to stay a power of two no larger than a MAX_ORDER
+        // block, so the tail past the largest one that fits is left unused.
+        let size = (1 << size.ilog2()).min(LockedBuddy::MAX_ALLOC_SIZE);
+
+        unsafe { self.init(aligned, size) };
+        return Ok((aligned, size));
+    }
+
+    fn is_initialized(&self) -> bool {
+        return !self.base.is_null();
+    }
+
+    fn deinit(&mut self) {
+        // Every block is back on the free lists once nothing is live, merged
+        // or not.
+        debug_assert!(
+            self.pending_heap || self.free_bytes() == self.size,
+            "Deinitialized with live allocations"
+        );
+        *self = LockedBuddy::new();
+    }
+
     unsafe fn add_free_area(&mut self, addr: usize, order: usize) {
         debug_assert!(
             addr != 0,
@@ -136,6 +363,12 @@ impl LockedBuddy {
         new_item.next = self.list_areas[order].head;
 
         let item_ptr = addr as *mut FreeList;
+        check_write(
+            addr,
+            size_of::<FreeList>(),
+            self.base as usize,
+            self.base as usize + self.size,
+        );
 
         unsafe {
             #[cfg(debug_assertions)]
@@ -155,9 +388,15 @@ impl LockedBuddy {
      */
     #[allow(clippy::result_unit_err)]
     fn split_area_to(&mut self, target_order: usize) -> Result<(), BAllocatorError> {
-        let source_order = (target_order..NR_MAX_ORDER)
-            .find(|&order| self.list_areas[order].nr_free > 0)
-            .ok_or(BAllocatorError::Oom(None))?;
+        let mut source_order =
+            (target_order..NR_MAX_ORDER).find(|&order| self.list_areas[order].nr_free > 0);
+
+        // Lazily coalesced blocks may still add up to a large enough block.
+        if source_order.is_none() && self.merge_threshold > 0 && self.merge_all(false).0 > 0 {
+            source_order =
+                (target_order..NR_MAX_ORDER).find(|&order| self.list_areas[order].nr_free > 0);
+        }
+        let source_order = source_order.ok_or(BAllocatorError::Oom(None))?;
 
         for current_order in (target_order..=source_order).rev() {
             if self.list_areas[current_order].nr_free > 0 {
@@ -179,6 +418,7 @@ impl LockedBuddy {
 
                     self.push_to_order(buddy_order, start_addr);
                     self.push_to_order(buddy_order, buddy_addr);
+                    self.splits += 1;
                     #[cfg(debug_assertions)]
                     trace!(
                         "Pushed to order: {}, start_addr: {:#X}, buddy_addr: {:#X}",
@@ -190,30 +430,232 @@ impl LockedBuddy {
         return Err(BAllocatorError::Oom(None));
     }
 
-    fn combine_free_buddies(&mut self, addr: usize) {
+    fn top_order(&self) -> usize {
+        // A fully trimmed heap has no blocks at all.
+        return self.size.div_ceil(PAGE_SIZE).checked_ilog2().unwrap_or(0) as usize;
+    }
+
+    fn buddy_of(&self, addr: usize, order: usize) -> usize {
+        let base = self.base as usize;
+        return base + ((addr - base) ^ (PAGE_SIZE << order));
+    }
+
+    /// Whether the block at `addr` can become one of `new_order` by absorbing
+    /// the free buddy above it at every order from `order` up.
+    fn can_grow_in_place(&self, addr: usize, order: usize, new_order: usize) -> bool {
+        let base = self.base as usize;
+        if new_order > self.top_order() || !(addr - base).is_multiple_of(PAGE_SIZE << new_order) {
+            return false;
+        }
+        return (order..new_order)
+            .all(|order| self.list_areas[order].contains(addr + (PAGE_SIZE << order)));
+    }
+
+    /// Merges the block at `addr` with its free buddies for as long as they
+    /// are free, returning the address and order of the resulting block.
+    fn combine_free_buddies(&mut self, mut addr: usize, mut order: usize) -> (usize, usize) {
         debug_assert!(addr != 0, "combine_free_buddies: Given address is NULL");
-        for current_order in MIN_ORDER..=MAX_ORDER {
-            let buddy_addr = addr ^ (PAGE_SIZE << current_order);
+        if self.list_areas[order].len() < self.merge_threshold {
+            return (addr, order);
+        }
 
-            if (buddy_addr ^ addr) == (PAGE_SIZE << current_order)
-                && self.list_areas[current_order].nr_free >= 2
-            {
-                let new_addr = addr.min(buddy_addr);
-                self.list_areas[current_order].head = None;
-                self.list_areas[current_order].nr_free = 0;
+        while order < self.top_order() {
+            let buddy_addr = self.buddy_of(addr, order);
+            if !self.list_areas[order].remove(buddy_addr) {
+                break;
+            }
+            addr = addr.min(buddy_addr);
+            order += 1;
+            self.merges += 1;
+        }
+        return (addr, order);
+    }
 
-                let node_ptr = new_addr as *mut FreeList;
-                unsafe {
-                    node_ptr.write_volatile(FreeList::new());
-                    self.list_areas[current_order + 1].push(NonNull::new_unchecked(node_ptr));
+    /// Merges every pair of free buddies, catching up on merges deferred by
+    /// `merge_threshold`. Returns the number of merges done and the highest
+    /// order a merge reached, 0 when nothing merged, so a maintenance task
+    /// can tell whether another pass would help.
+    pub fn coalesce_all(&mut self) -> (usize, usize) {
+        return self.merge_all(true);
+    }
+
+    /// `coalesce_all`, only calling `on_top_free` when `notify` is set. A
+    /// catch-up merge for an allocation leaves it unset, as the block is
+    /// about to be split and handed out.
+    fn merge_all(&mut self, notify: bool) -> (usize, usize) {
+        self.claim_pending_heap();
+        let merges = self.merges;
+        let mut highest = 0;
+
+        for order in MIN_ORDER..self.top_order() {
+            let mut current = self.list_areas[order].head;
+            while let Some(node) = current {
+                let addr = node.as_ptr() as usize;
+                let buddy_addr = self.buddy_of(addr, order);
+                if self.list_areas[order].remove(buddy_addr) {
+                    self.list_areas[order].remove(addr);
+                    self.push_to_order(order + 1, addr.min(buddy_addr));
+                    if notify {
+                        self.notify_top_free(addr.min(buddy_addr), order + 1);
+                    }
+                    self.merges += 1;
+                    highest = order + 1;
+                    current = self.list_areas[order].head;
+                } else {
+                    current = unsafe { node.as_ref().next };
                 }
             }
         }
+        return (self.merges - merges, highest);
+    }
+
+    /// Free blocks are only coalesced with their buddy once their order
+    /// already holds `threshold` free blocks, otherwise they are kept around
+    /// to be handed out again without splitting. 0 always coalesces.
+    pub fn set_merge_threshold(&mut self, threshold: usize) {
+        self.merge_threshold = threshold;
+    }
+
+    /// Serves requests smaller than `TINY_SLOT_SIZE` from slots carved out
+    /// of up to `TINY_BLOCKS` blocks of `TINY_ORDER`, instead of a whole
+    /// block each. A block goes back to the free lists once its last slot
+    /// is freed. Slots already handed out can still be freed after
+    /// disabling.
+    pub fn set_tiny_slab(&mut self, enabled: bool) {
+        self.tiny_enabled = enabled;
+    }
+
+    /// Calls `on_top_free(base)` whenever a free or `coalesce_all` leaves
+    /// the whole heap as one free top order block, so a parent allocator can
+    /// take it back. The block is already on the free lists, and
+    /// `on_top_free` runs with the allocator locked. A heap `trim` left at
+    /// a size that is not a power of two can never be one block, so it is
+    /// no longer reported.
+    pub fn set_on_top_free(&mut self, on_top_free: fn(usize)) {
+        self.on_top_free = Some(on_top_free);
+    }
+
+    fn notify_top_free(&self, addr: usize, order: usize) {
+        // After a trim a top order block may not reach the end of the heap.
+        if PAGE_SIZE << order == self.size
+            && let Some(on_top_free) = self.on_top_free
+        {
+            #[cfg(debug_assertions)]
+            debug!("Heap at {addr:#X} entirely free");
+            on_top_free(addr);
+        }
+    }
+
+    fn is_tiny(layout: Layout) -> bool {
+        return layout.size() < TINY_SLOT_SIZE && layout.align() <= TINY_SLOT_SIZE;
+    }
+
+    /// Index in `tiny` of the slab block `addr` lies in.
+    fn tiny_block_of(&self, addr: usize) -> Option<usize> {
+        return self
+            .tiny
+            .entries()
+            .iter()
+            .position(|&(block, _)| (block..block + (PAGE_SIZE << TINY_ORDER)).contains(&addr));
+    }
+
+    /// Takes a free slot, carving a new block when every held one is full.
+    /// `None` when no slot is free and no more blocks can be held or split
+    /// off.
+    fn allocate_tiny(&mut self) -> Option<NonNull<u8>> {
+        let index = match self
+            .tiny
+            .entries()
+            .iter()
+            .position(|&(_, used)| used != u32::MAX)
+        {
+            Some(index) => index,
+            None => {
+                if self.tiny.is_full() {
+                    return None;
+                }
+                if self.list_areas[TINY_ORDER].nr_free == 0 {
+                    self.split_area_to(TINY_ORDER).ok()?;
+                }
+                let area = self.list_areas[TINY_ORDER].pop()?;
+                self.tiny.insert(area.as_ptr() as usize, 0);
+                #[cfg(feature = "buddy_track_live")]
+                self.live
+                    .insert(area.as_ptr() as usize - self.base as usize, TINY_ORDER);
+                self.tiny.entries().len() - 1
+            }
+        };
+
+        let (block, used) = &mut self.tiny.entries_mut()[index];
+        let slot = used.trailing_ones() as usize;
+        *used |= 1 << slot;
+        return NonNull::new((*block + slot * TINY_SLOT_SIZE) as *mut u8);
+    }
+
+    /// Frees the slot at `addr` from tiny block `index`, giving the block
+    /// back once it is empty.
+    fn deallocate_tiny(&mut self, index: usize, addr: usize) -> Result<(), BAllocatorError> {
+        let &mut (block_addr, ref mut used) = &mut self.tiny.entries_mut()[index];
+        let slot = (addr - block_addr) / TINY_SLOT_SIZE;
+        if !addr.is_multiple_of(TINY_SLOT_SIZE) || *used & (1 << slot) == 0 {
+            return Err(BAllocatorError::InvalidFree);
+        }
+        *used &= !(1 << slot);
+
+        if *used == 0 {
+            self.tiny.remove(block_addr);
+            #[cfg(feature = "buddy_track_live")]
+            self.live.remove(block_addr - self.base as usize);
+            let (addr, order) = self.combine_free_buddies(block_addr, TINY_ORDER);
+            unsafe { self.add_free_area(addr, order) };
+            self.notify_top_free(addr, order);
+        }
+        return Ok(());
+    }
+
+    pub fn merges(&self) -> usize {
+        return self.merges;
+    }
+
+    pub fn splits(&self) -> usize {
+        return self.splits;
+    }
+
+    /// Splits larger blocks until `order` holds at least `count` free blocks,
+    /// so the first allocations at that order do not pay for splitting.
+    /// Returns the number of blocks added to `order`, which is always 0
+    /// past `MAX_ORDER`.
+    pub fn prewarm(&mut self, order: usize, count: usize) -> usize {
+        if order > MAX_ORDER {
+            return 0;
+        }
+        self.claim_pending_heap();
+        let before = self.list_areas[order].len();
+
+        while self.list_areas[order].len() < count
+            && (order + 1..NR_MAX_ORDER).any(|order| self.list_areas[order].nr_free > 0)
+            && self.split_area_to(order + 1).is_ok()
+        {
+            let Some(area) = self.list_areas[order + 1].pop() else {
+                break;
+            };
+            let start_addr = area.as_ptr() as usize;
+            self.push_to_order(order, start_addr);
+            self.push_to_order(order, start_addr + (PAGE_SIZE << order));
+            self.splits += 1;
+        }
+        return self.list_areas[order].len() - before;
     }
 
     fn push_to_order(&mut self, order: usize, addr: usize) {
         debug_assert!(addr != 0, "push_to_order: Given address is NULL.");
         let node_ptr = addr as *mut FreeList;
+        check_write(
+            addr,
+            size_of::<FreeList>(),
+            self.base as usize,
+            self.base as usize + self.size,
+        );
 
         unsafe {
             node_ptr.write_volatile(FreeList::new());
@@ -221,34 +663,243 @@ impl LockedBuddy {
         }
     }
 
-    fn size_align(layout: Layout) -> usize {
-        let new_layout = layout
-            .align_to(align_of::<FreeList>())
-            .expect("adjusting alignment failed")
-            .pad_to_align();
+    /// Bytes on the free lists, merged or not.
+    fn free_bytes(&self) -> usize {
+        return self
+            .list_areas
+            .iter()
+            .enumerate()
+            .map(|(order, area)| area.len() * (PAGE_SIZE << order))
+            .sum();
+    }
 
-        let size_bytes = new_layout.size().max(size_of::<FreeList>());
-        let size_in_pages = size_bytes.div_ceil(PAGE_SIZE);
+    /// Moves every free block to `other` at its order, leaving this
+    /// allocator uninitialized. `other` either is uninitialized and takes
+    /// this heap over, or manages a heap of the same size ending where this
+    /// one starts, which doubles to cover both. Fails with `Unmergeable`
+    /// otherwise or while anything is allocated from this heap. The blocks
+    /// are not merged, `coalesce_all` on `other` does that.
+    pub fn drain_into(&mut self, other: &mut LockedBuddy) -> Result<(), BAllocatorError> {
+        self.claim_pending_heap();
+        other.claim_pending_heap();
+        if !self.is_initialized() || self.free_bytes() != self.size {
+            return Err(BAllocatorError::Unmergeable);
+        }
 
-        assert!(
-            size_in_pages.ilog2() <= MAX_ORDER as u32,
-            "Object is too large to allocate in set largest single block in this allocator."
+        if !other.is_initialized() {
+            other.base = self.base;
+            other.size = self.size;
+        } else if other.base as usize + other.size == self.base as usize
+            && other.size == self.size
+            && self.size.is_power_of_two()
+            && let Some(size) = other.size.checked_mul(2)
+            && size.div_ceil(PAGE_SIZE).ilog2() as usize <= MAX_ORDER
+            && fits_meta_word(size)
+        {
+            other.size = size;
+        } else {
+            return Err(BAllocatorError::Unmergeable);
+        }
+
+        for order in MIN_ORDER..NR_MAX_ORDER {
+            while let Some(area) = self.list_areas[order].pop() {
+                other.push_to_order(order, area.as_ptr() as usize);
+            }
+        }
+        #[cfg(debug_assertions)]
+        debug!(
+            "Drained buddy heap {:?} into {:?}, now {} bytes",
+            self.base, other.base, other.size
         );
+        *self = LockedBuddy::new();
+        return Ok(());
+    }
+
+    /// Removes the free blocks forming the tail of the heap from the free
+    /// lists so a parent allocator can reclaim them, and shrinks the heap to
+    /// match. Returns the number of bytes removed, counting back from the
+    /// end of the heap. Unless what is left is a power of two in size,
+    /// `on_top_free` stops being called for this heap.
+    pub fn trim(&mut self) -> usize {
+        self.claim_pending_heap();
+        let base = self.base as usize;
+        let mut end = base + self.size;
+
+        for order in (MIN_ORDER..=self.top_order()).rev() {
+            let block_size = PAGE_SIZE << order;
+            if end - base < block_size {
+                continue;
+            }
+            if self.list_areas[order].remove(end - block_size) {
+                end -= block_size;
+            }
+        }
 
-        return size_in_pages;
+        let trimmed = base + self.size - end;
+        self.size -= trimmed;
+        #[cfg(debug_assertions)]
+        debug!("Trimmed {trimmed} bytes from the heap tail");
+        return trimmed;
+    }
+
+    /// Checks every free block lies within the heap and is aligned to its
+    /// own size relative to the base, returning the address of the first
+    /// that is not.
+    pub fn validate(&self) -> Result<(), usize> {
+        let base = self.base as usize;
+        for (order, area) in self.list_areas.iter().enumerate() {
+            let block_size = PAGE_SIZE << order;
+            let mut current = area.head;
+            while let Some(node) = current {
+                let addr = node.as_ptr() as usize;
+                if addr < base
+                    || !(addr - base).is_multiple_of(block_size)
+                    || addr - base + block_size > self.size
+                {
+                    #[cfg(debug_assertions)]
+                    error!("Free block {addr:#X} of order {order} is misplaced");
+                    return Err(addr);
+                }
+                current = unsafe { node.as_ref().next };
+            }
+        }
+        return Ok(());
     }
-}
 
-unsafe impl BAllocator for Mutex<LockedBuddy> {
-    unsafe fn try_allocate(&self, layout: Layout) -> Result<NonNull<u8>, BAllocatorError> {
-        let size = LockedBuddy::size_align(layout);
-        let mut allocator = self.lock();
+    /// Touches the heap once per `page_size` bytes, the MMU page size of
+    /// the target, so the first allocations from each block run in the same
+    /// time as later ones. The free lists are left untouched, including a
+    /// heap still waiting to be split.
+    ///
+    /// # Safety
+    /// Nothing may access blocks allocated from this heap while it runs.
+    pub unsafe fn prefault(&self, page_size: usize) {
+        let base = self.base as usize;
+        unsafe { prefault(base, base + self.size, page_size) };
+    }
 
-        let alloc_order = size.ilog2() as usize;
+    pub fn free_counts(&self) -> [usize; NR_MAX_ORDER] {
+        if self.pending_heap {
+            return core::array::from_fn(|order| (order == self.top_order()) as usize);
+        }
+        return core::array::from_fn(|order| self.list_areas[order].len());
+    }
 
-        allocator.split_area_to(alloc_order)?;
+    /// How many blocks of each order could be allocated right now if every
+    /// allocation were of that order, counting what splitting the larger
+    /// free blocks would give, unlike `free_counts`. Merges deferred by
+    /// `merge_threshold` are not taken into account.
+    pub fn allocatable_counts(&self) -> [usize; NR_MAX_ORDER] {
+        let free = self.free_counts();
+        return core::array::from_fn(|order| {
+            return (order..NR_MAX_ORDER)
+                .map(|larger| {
+                    let per_block = 1usize.checked_shl((larger - order) as u32);
+                    free[larger].saturating_mul(per_block.unwrap_or(usize::MAX))
+                })
+                .fold(0, usize::saturating_add);
+        });
+    }
 
-        let region = match allocator.list_areas[alloc_order].pop() {
+    /// Calls `f` with the address and order of every block currently handed
+    /// out. A tiny slab block is reported whole, at `TINY_ORDER`, while any
+    /// of its slots are.
+    ///
+    /// Returns how many live blocks did not fit in the `LIVE_CAPACITY` table
+    /// and so were not reported; the walk is only complete when this is 0.
+    #[cfg(feature = "buddy_track_live")]
+    pub fn for_each_live(&self, mut f: impl FnMut(usize, usize)) -> usize {
+        for &(offset, order) in self.live.blocks.entries() {
+            f(
+                self.base as usize + from_meta_word(offset),
+                from_meta_word(order),
+            );
+        }
+        return self.live.untracked;
+    }
+
+    /// Splits the block at `ptr` down to the order `new_layout` needs,
+    /// returning its upper halves to the free lists. The block keeps its
+    /// address, so nothing is copied.
+    ///
+    /// # Safety
+    /// `ptr` must be a live allocation of `old_layout` from this allocator.
+    pub unsafe fn shrink(
+        &mut self,
+        ptr: NonNull<u8>,
+        old_layout: Layout,
+        new_layout: Layout,
+    ) -> Result<NonNull<u8>, BAllocatorError> {
+        let old_order = LockedBuddy::order_of(old_layout)?;
+        let new_order = LockedBuddy::order_of(new_layout)?;
+        if new_layout.align() > old_layout.align() {
+            return Err(BAllocatorError::Alignment(new_layout));
+        }
+        if new_order > old_order {
+            return Err(BAllocatorError::TooLarge(new_layout));
+        }
+
+        let addr = ptr.as_ptr() as usize;
+        for order in (new_order..old_order).rev() {
+            self.push_to_order(order, addr + (PAGE_SIZE << order));
+            self.splits += 1;
+        }
+        #[cfg(feature = "buddy_track_live")]
+        {
+            self.live.remove(addr - self.base as usize);
+            self.live.insert(addr - self.base as usize, new_order);
+        }
+        #[cfg(debug_assertions)]
+        debug!("Shrank object \"{addr:X}\" in place from order {old_order} to {new_order}");
+        return Ok(ptr);
+    }
+
+    /// Like `allocate`, but only takes a free block of exactly the order
+    /// `layout` needs, failing with `Oom` rather than splitting a larger
+    /// one, so big blocks are kept whole for later large requests. The tiny
+    /// slab is skipped.
+    ///
+    /// # Safety
+    /// As for `allocate`.
+    pub unsafe fn allocate_exact(
+        &mut self,
+        layout: Layout,
+    ) -> Result<NonNull<u8>, BAllocatorError> {
+        return unsafe { self.allocate_from(layout, true) };
+    }
+
+    unsafe fn allocate_from(
+        &mut self,
+        layout: Layout,
+        exact: bool,
+    ) -> Result<NonNull<u8>, BAllocatorError> {
+        if !self.is_initialized() {
+            #[cfg(debug_assertions)]
+            error!("{}", ALLOCATOR_UNINITIALIZED);
+            return Err(BAllocatorError::Uninitialized);
+        }
+        self.claim_pending_heap();
+        #[cfg(debug_assertions)]
+        self.debug_assert_list_consistent();
+        if !exact
+            && self.tiny_enabled
+            && LockedBuddy::is_tiny(layout)
+            && let Some(ptr) = self.allocate_tiny()
+        {
+            #[cfg(debug_assertions)]
+            debug!(
+                "Allocated tiny object \"{:X}\"; layout: {layout:?}",
+                ptr.as_ptr() as usize
+            );
+            return Ok(ptr);
+        }
+        let alloc_order = LockedBuddy::order_of(layout)?;
+
+        if !exact && self.list_areas[alloc_order].nr_free == 0 {
+            self.split_area_to(alloc_order)?;
+        }
+
+        let region = match self.list_areas[alloc_order].pop() {
             Some(f) => f,
             None => {
                 #[cfg(debug_assertions)]
@@ -257,27 +908,121 @@ unsafe impl BAllocator for Mutex<LockedBuddy> {
             }
         };
         let alloc_start = region.as_ptr() as *mut u8;
+        check_alloc(
+            alloc_start as usize,
+            layout,
+            self.base as usize,
+            self.base as usize + self.size,
+        );
+
+        #[cfg(feature = "buddy_track_live")]
+        self.live
+            .insert(alloc_start as usize - self.base as usize, alloc_order);
 
         #[cfg(debug_assertions)]
         debug!(
             "Allocated object \"{:X}\"; layout: {layout:?}",
             alloc_start as usize
         );
+        #[cfg(debug_assertions)]
+        self.debug_assert_list_consistent();
         return Ok(unsafe { NonNull::new_unchecked(alloc_start) });
     }
 
-    unsafe fn try_deallocate(
-        &self,
+    /// Number of pages, a power of two, backing an allocation of `layout`.
+    /// Never fewer than a `MIN_ORDER` block holds.
+    fn size_align(layout: Layout) -> Result<usize, BAllocatorError> {
+        const { assert!(PAGE_SIZE << MIN_ORDER >= size_of::<FreeList>()) };
+        let new_layout = layout.align_to(align_of::<FreeList>())?.pad_to_align();
+
+        let size_bytes = new_layout.size().max(PAGE_SIZE << MIN_ORDER);
+        let size_in_pages = size_bytes
+            .div_ceil(PAGE_SIZE)
+            .checked_next_power_of_two()
+            .ok_or(BAllocatorError::TooLarge(layout))?;
+
+        if size_in_pages.ilog2() > MAX_ORDER as u32 {
+            return Err(BAllocatorError::TooLarge(layout));
+        }
+        return Ok(size_in_pages);
+    }
+
+    /// Order of the block backing an allocation of `layout`.
+    fn order_of(layout: Layout) -> Result<usize, BAllocatorError> {
+        return Ok(LockedBuddy::size_align(layout)?.ilog2() as usize);
+    }
+
+    /// Bytes of heap an allocation of `layout` takes up, rounding included,
+    /// for sizing heaps ahead of time. Tiny slab allocations may take only a
+    /// `TINY_SLOT_SIZE` slot, this is what they take once the slab is full.
+    /// Fails with `TooLarge` past `MAX_ALLOC_SIZE`.
+    pub fn block_size_for(layout: Layout) -> Result<usize, BAllocatorError> {
+        return Ok(PAGE_SIZE << LockedBuddy::order_of(layout)?);
+    }
+}
+
+unsafe impl LockedInner for LockedBuddy {
+    unsafe fn allocate(&mut self, layout: Layout) -> Result<NonNull<u8>, BAllocatorError> {
+        return unsafe { self.allocate_from(layout, false) };
+    }
+
+    /// The whole block is usable, `PAGE_SIZE << order` bytes, or the whole
+    /// slot for tiny slab allocations.
+    unsafe fn allocate_with_usable(
+        &mut self,
+        layout: Layout,
+    ) -> Result<(NonNull<u8>, usize), BAllocatorError> {
+        let ptr = unsafe { self.allocate(layout)? };
+        if self.tiny_block_of(ptr.as_ptr() as usize).is_some() {
+            return Ok((ptr, TINY_SLOT_SIZE));
+        }
+        return Ok((ptr, PAGE_SIZE << LockedBuddy::order_of(layout)?));
+    }
+
+    unsafe fn deallocate(
+        &mut self,
         ptr: NonNull<u8>,
         layout: Layout,
     ) -> Result<(), BAllocatorError> {
-        let mut allocator = self.lock();
+        let addr = ptr.as_ptr() as usize;
+        if LockedBuddy::is_tiny(layout)
+            && let Some(index) = self.tiny_block_of(addr)
+        {
+            let base = self.base as usize;
+            check_free(addr, TINY_SLOT_SIZE, base, base + self.size)?;
+            #[cfg(feature = "fill_on_free")]
+            unsafe {
+                fill_freed(ptr, layout.size())
+            };
+            #[cfg(debug_assertions)]
+            debug!("Deallocated tiny object \"{addr:X}\"; layout: {layout:?}");
+            return self.deallocate_tiny(index, addr);
+        }
+        let dealloc_order = LockedBuddy::order_of(layout)?;
 
-        let size = LockedBuddy::size_align(layout);
-        let dealloc_order = size.ilog2() as usize;
+        let base = self.base as usize;
+        // The block has to be one the heap could have split off: whole,
+        // inside it and aligned to its own size.
+        check_free(addr, PAGE_SIZE << dealloc_order, base, base + self.size)?;
+        if dealloc_order > self.top_order()
+            || !(addr - base).is_multiple_of(PAGE_SIZE << dealloc_order)
+        {
+            #[cfg(debug_assertions)]
+            error!("Rejected free of \"{addr:X}\"; layout: {layout:?}");
+            return Err(BAllocatorError::InvalidFree);
+        }
 
-        unsafe { allocator.add_free_area(ptr.as_ptr() as usize, dealloc_order) };
-        allocator.combine_free_buddies(ptr.as_ptr() as usize);
+        #[cfg(feature = "fill_on_free")]
+        unsafe {
+            fill_freed(ptr, layout.size())
+        };
+
+        #[cfg(feature = "buddy_track_live")]
+        self.live.remove(addr - base);
+
+        let (addr, order) = self.combine_free_buddies(ptr.as_ptr() as usize, dealloc_order);
+        unsafe { self.add_free_area(addr, order) };
+        self.notify_top_free(addr, order);
 
         #[cfg(debug_assertions)]
         debug!(
@@ -286,6 +1031,155 @@ unsafe impl BAllocator for Mutex<LockedBuddy> {
         );
         return Ok(());
     }
+
+    /// Merges deferred by `merge_threshold` are not taken into account.
+    fn can_allocate(&self, layout: Layout) -> bool {
+        if self.tiny_enabled
+            && LockedBuddy::is_tiny(layout)
+            && self
+                .tiny
+                .entries()
+                .iter()
+                .any(|&(_, used)| used != u32::MAX)
+        {
+            return true;
+        }
+        let Ok(order) = LockedBuddy::order_of(layout) else {
+            return false;
+        };
+        if self.pending_heap {
+            return order <= self.top_order();
+        }
+        return (order..NR_MAX_ORDER).any(|order| self.list_areas[order].nr_free > 0);
+    }
+
+    /// Blocks start on a `FreeList` boundary, tiny slab slots only on a
+    /// `TINY_SLOT_SIZE` one.
+    fn base_alignment(&self) -> usize {
+        if self.tiny_enabled {
+            return TINY_SLOT_SIZE;
+        }
+        return align_of::<FreeList>();
+    }
+
+    /// Resizes in place when `new_size` needs a block of the same or a
+    /// smaller order, or a larger one whose extra halves are all free buddies
+    /// above `ptr`. Anything else moves.
+    unsafe fn reallocate(
+        &mut self,
+        ptr: NonNull<u8>,
+        layout: Layout,
+        new_size: usize,
+    ) -> Result<NonNull<u8>, BAllocatorError> {
+        let new_layout = Layout::from_size_align(new_size, layout.align())?;
+        let old_order = LockedBuddy::order_of(layout)?;
+        let new_order = LockedBuddy::order_of(new_layout)?;
+        let addr = ptr.as_ptr() as usize;
+
+        // Tiny slots are never split or grown, they either still fit or move.
+        if LockedBuddy::is_tiny(layout) && self.tiny_block_of(addr).is_some() {
+            if LockedBuddy::is_tiny(new_layout) {
+                return Ok(ptr);
+            }
+            unsafe {
+                let new_ptr = self.allocate(new_layout)?;
+                new_ptr.copy_from_nonoverlapping(ptr, layout.size().min(new_size));
+                self.deallocate(ptr, layout)?;
+                return Ok(new_ptr);
+            }
+        }
+        if new_order <= old_order {
+            return unsafe { self.shrink(ptr, layout, new_layout) };
+        }
+        if self.can_grow_in_place(addr, old_order, new_order) {
+            for order in old_order..new_order {
+                self.list_areas[order].remove(addr + (PAGE_SIZE << order));
+            }
+            #[cfg(feature = "buddy_track_live")]
+            {
+                self.live.remove(addr - self.base as usize);
+                self.live.insert(addr - self.base as usize, new_order);
+            }
+            #[cfg(debug_assertions)]
+            debug!("Grew object \"{addr:X}\" in place from order {old_order} to {new_order}");
+            return Ok(ptr);
+        }
+
+        unsafe {
+            let new_ptr = self.allocate(new_layout)?;
+            new_ptr.copy_from_nonoverlapping(ptr, layout.size().min(new_size));
+            self.deallocate(ptr, layout)?;
+            return Ok(new_ptr);
+        }
+    }
+}
+
+impl AllocBounds for LockedBuddy {
+    fn contains(&self, ptr: NonNull<u8>) -> bool {
+        let base = self.base as usize;
+        return (base..base + self.size).contains(&(ptr.as_ptr() as usize));
+    }
+}
+
+/// Configures a buddy allocator in one place before building it, as
+/// `LinkedListBuilder` does for linked lists.
+#[derive(Debug, Clone, Copy)]
+pub struct BuddyBuilder {
+    merge_threshold: usize,
+    tiny_slab: bool,
+    on_top_free: Option<fn(usize)>,
+}
+
+impl Default for BuddyBuilder {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl BuddyBuilder {
+    /// The defaults of `LockedBuddyAlloc::new`.
+    pub const fn new() -> Self {
+        BuddyBuilder {
+            merge_threshold: 0,
+            tiny_slab: false,
+            on_top_free: None,
+        }
+    }
+
+    /// See `LockedBuddy::set_merge_threshold`.
+    pub const fn merge_threshold(mut self, threshold: usize) -> Self {
+        self.merge_threshold = threshold;
+        return self;
+    }
+
+    /// See `LockedBuddy::set_tiny_slab`.
+    pub const fn tiny_slab(mut self, enabled: bool) -> Self {
+        self.tiny_slab = enabled;
+        return self;
+    }
+
+    /// See `LockedBuddy::set_on_top_free`.
+    pub const fn on_top_free(mut self, on_top_free: fn(usize)) -> Self {
+        self.on_top_free = Some(on_top_free);
+        return self;
+    }
+
+    const fn configure(self) -> LockedBuddy {
+        let mut alloc = LockedBuddy::new();
+        alloc.merge_threshold = self.merge_threshold;
+        alloc.tiny_enabled = self.tiny_slab;
+        alloc.on_top_free = self.on_top_free;
+        return alloc;
+    }
+
+    /// Uninitialized allocator with this configuration, `init` it as usual.
+    pub const fn build(self) -> Alloc<Mutex<LockedBuddy>> {
+        return Alloc::wrap(Mutex::new(self.configure()));
+    }
+
+    pub const fn build_rwlocked(self) -> Alloc<RwLock<LockedBuddy>> {
+        return Alloc::wrap(RwLock::new(self.configure()));
+    }
 }
 
 unsafe impl Sync for Alloc<Mutex<LockedBuddy>> {}
@@ -293,9 +1187,157 @@ unsafe impl Send for Alloc<Mutex<LockedBuddy>> {}
 
 impl Alloc<Mutex<LockedBuddy>> {
     pub const fn new() -> Self {
-        Alloc {
-            alloc: Mutex::new(LockedBuddy::new()),
+        Alloc::wrap(Mutex::new(LockedBuddy::new()))
+    }
+
+    /// Already initialized allocator over a heap at a fixed address, for
+    /// statics that cannot call `init` before first use.
+    ///
+    /// # Safety
+    /// The heap must be valid and unused, as for `init`.
+    pub const unsafe fn new_with_static_heap(start: usize, size: usize) -> Self {
+        Alloc::wrap(Mutex::new(LockedBuddy::new_with_static_heap(start, size)))
+    }
+
+    /// Like `init`, but rounds `start` up to the alignment the free lists
+    /// need and shrinks `size` by the padding, then down to a power of two,
+    /// returning the region actually managed. Fails if less than a page is
+    /// left.
+    ///
+    /// # Safety
+    /// The heap must be valid and unused, as for `init`.
+    pub unsafe fn init_aligned(
+        &self,
+        start: usize,
+        size: usize,
+    ) -> Result<(usize, usize), BAllocatorError> {
+        unsafe {
+            #[cfg(debug_assertions)]
+            debug!("Initialized locked buddy alloc aligned; start: {start:#X}, size: {size}");
+            return self.lock().init_aligned(start, size);
+        }
+    }
+
+    pub fn free_counts(&self) -> [usize; NR_MAX_ORDER] {
+        return self.lock().free_counts();
+    }
+
+    pub fn allocatable_counts(&self) -> [usize; NR_MAX_ORDER] {
+        return self.lock().allocatable_counts();
+    }
+
+    pub fn validate(&self) -> Result<(), usize> {
+        return self.lock().validate();
+    }
+
+    /// See `LockedBuddy::prefault`.
+    ///
+    /// # Safety
+    /// As for `LockedBuddy::prefault`.
+    pub unsafe fn prefault(&self, page_size: usize) {
+        unsafe { self.lock().prefault(page_size) };
+    }
+
+    pub fn set_merge_threshold(&self, threshold: usize) {
+        self.lock().set_merge_threshold(threshold);
+    }
+
+    pub fn set_tiny_slab(&self, enabled: bool) {
+        self.lock().set_tiny_slab(enabled);
+    }
+
+    /// See `LockedBuddy::set_on_top_free`.
+    pub fn set_on_top_free(&self, on_top_free: fn(usize)) {
+        self.lock().set_on_top_free(on_top_free);
+    }
+
+    pub fn trim(&self) -> usize {
+        return self.lock().trim();
+    }
+
+    /// See `LockedBuddy::drain_into`.
+    pub fn drain_into(&self, other: &Self) -> Result<(), BAllocatorError> {
+        if core::ptr::eq(self, other) {
+            return Err(BAllocatorError::Unmergeable);
+        }
+        return self.with_pair(other, |ours, theirs| ours.drain_into(theirs));
+    }
+
+    pub fn coalesce_all(&self) -> (usize, usize) {
+        return self.lock().coalesce_all();
+    }
+
+    pub fn merges(&self) -> usize {
+        return self.lock().merges();
+    }
+
+    pub fn splits(&self) -> usize {
+        return self.lock().splits();
+    }
+
+    pub const fn max_alloc_size(&self) -> usize {
+        return LockedBuddy::MAX_ALLOC_SIZE;
+    }
+
+    pub fn prewarm(&self, order: usize, count: usize) -> usize {
+        return self.lock().prewarm(order, count);
+    }
+
+    /// See `LockedBuddy::shrink`.
+    ///
+    /// # Safety
+    /// `ptr` must be a live allocation of `old_layout` from this allocator.
+    pub unsafe fn shrink(
+        &self,
+        ptr: NonNull<u8>,
+        old_layout: Layout,
+        new_layout: Layout,
+    ) -> Result<NonNull<u8>, BAllocatorError> {
+        return unsafe { self.lock().shrink(ptr, old_layout, new_layout) };
+    }
+
+    /// See `LockedBuddy::allocate_exact`.
+    ///
+    /// # Safety
+    /// As for `try_allocate`.
+    pub unsafe fn try_allocate_exact(
+        &self,
+        layout: Layout,
+    ) -> Result<NonNull<u8>, BAllocatorError> {
+        if layout.size() == 0 {
+            return self.count_alloc(Ok(dangling(layout)));
         }
+        return self.count_alloc(self.record(unsafe { self.lock().allocate_exact(layout) }));
+    }
+
+    /// The lock is held while `f` runs, so it must not allocate from this
+    /// allocator.
+    #[cfg(feature = "buddy_track_live")]
+    pub fn for_each_live(&self, f: impl FnMut(usize, usize)) -> usize {
+        return self.lock().for_each_live(f);
+    }
+}
+
+#[cfg(test)]
+impl Alloc<Mutex<LockedBuddy>> {
+    /// Calls `f` with the address of every block on the free list of
+    /// `order`, front to back.
+    pub(crate) fn debug_free_list(&self, order: usize, mut f: impl FnMut(usize)) {
+        let alloc = self.lock();
+        let mut current = alloc.list_areas[order].head;
+        while let Some(node) = current {
+            f(node.as_ptr() as usize);
+            current = unsafe { node.as_ref().next };
+        }
+    }
+
+    pub(crate) fn desync_free_count(&self, order: usize) {
+        self.lock().list_areas[order].nr_free += 1;
+    }
+
+    #[cfg(feature = "checked")]
+    pub(crate) fn corrupt_size(&self, size: usize) {
+        self.lock().size = size;
     }
 }
 
@@ -313,4 +1355,173 @@ impl AllocInit for Mutex<LockedBuddy> {
             self.lock().init(start, size);
         }
     }
+
+    unsafe fn deinit(&self) -> Result<(), BAllocatorError> {
+        #[cfg(debug_assertions)]
+        debug!("Deinitialized locked buddy alloc");
+        self.lock().deinit();
+        return Ok(());
+    }
+
+    fn is_initialized(&self) -> bool {
+        return self.lock().is_initialized();
+    }
+}
+
+unsafe impl Sync for Alloc<RwLock<LockedBuddy>> {}
+unsafe impl Send for Alloc<RwLock<LockedBuddy>> {}
+
+impl Alloc<RwLock<LockedBuddy>> {
+    pub const fn new() -> Self {
+        Alloc::wrap(RwLock::new(LockedBuddy::new()))
+    }
+
+    /// Already initialized allocator over a heap at a fixed address, for
+    /// statics that cannot call `init` before first use.
+    ///
+    /// # Safety
+    /// The heap must be valid and unused, as for `init`.
+    pub const unsafe fn new_with_static_heap(start: usize, size: usize) -> Self {
+        Alloc::wrap(RwLock::new(LockedBuddy::new_with_static_heap(start, size)))
+    }
+
+    /// # Safety
+    /// See `Alloc<Mutex<LockedBuddy>>::init_aligned`.
+    pub unsafe fn init_aligned(
+        &self,
+        start: usize,
+        size: usize,
+    ) -> Result<(usize, usize), BAllocatorError> {
+        unsafe {
+            #[cfg(debug_assertions)]
+            debug!("Initialized rwlocked buddy alloc aligned; start: {start:#X}, size: {size}");
+            return self.write().init_aligned(start, size);
+        }
+    }
+
+    pub fn free_counts(&self) -> [usize; NR_MAX_ORDER] {
+        return self.read().free_counts();
+    }
+
+    pub fn allocatable_counts(&self) -> [usize; NR_MAX_ORDER] {
+        return self.read().allocatable_counts();
+    }
+
+    pub fn validate(&self) -> Result<(), usize> {
+        return self.read().validate();
+    }
+
+    /// See `LockedBuddy::prefault`.
+    ///
+    /// # Safety
+    /// As for `LockedBuddy::prefault`.
+    pub unsafe fn prefault(&self, page_size: usize) {
+        unsafe { self.read().prefault(page_size) };
+    }
+
+    pub fn set_merge_threshold(&self, threshold: usize) {
+        self.write().set_merge_threshold(threshold);
+    }
+
+    pub fn set_tiny_slab(&self, enabled: bool) {
+        self.write().set_tiny_slab(enabled);
+    }
+
+    /// See `LockedBuddy::set_on_top_free`.
+    pub fn set_on_top_free(&self, on_top_free: fn(usize)) {
+        self.write().set_on_top_free(on_top_free);
+    }
+
+    pub fn trim(&self) -> usize {
+        return self.write().trim();
+    }
+
+    /// See `LockedBuddy::drain_into`.
+    pub fn drain_into(&self, other: &Self) -> Result<(), BAllocatorError> {
+        if core::ptr::eq(self, other) {
+            return Err(BAllocatorError::Unmergeable);
+        }
+        return self.with_pair(other, |ours, theirs| ours.drain_into(theirs));
+    }
+
+    pub fn coalesce_all(&self) -> (usize, usize) {
+        return self.write().coalesce_all();
+    }
+
+    pub fn merges(&self) -> usize {
+        return self.read().merges();
+    }
+
+    pub fn splits(&self) -> usize {
+        return self.read().splits();
+    }
+
+    pub const fn max_alloc_size(&self) -> usize {
+        return LockedBuddy::MAX_ALLOC_SIZE;
+    }
+
+    pub fn prewarm(&self, order: usize, count: usize) -> usize {
+        return self.write().prewarm(order, count);
+    }
+
+    /// See `LockedBuddy::shrink`.
+    ///
+    /// # Safety
+    /// `ptr` must be a live allocation of `old_layout` from this allocator.
+    pub unsafe fn shrink(
+        &self,
+        ptr: NonNull<u8>,
+        old_layout: Layout,
+        new_layout: Layout,
+    ) -> Result<NonNull<u8>, BAllocatorError> {
+        return unsafe { self.write().shrink(ptr, old_layout, new_layout) };
+    }
+
+    /// See `LockedBuddy::allocate_exact`.
+    ///
+    /// # Safety
+    /// As for `try_allocate`.
+    pub unsafe fn try_allocate_exact(
+        &self,
+        layout: Layout,
+    ) -> Result<NonNull<u8>, BAllocatorError> {
+        if layout.size() == 0 {
+            return self.count_alloc(Ok(dangling(layout)));
+        }
+        return self.count_alloc(self.record(unsafe { self.write().allocate_exact(layout) }));
+    }
+
+    /// A read lock is held while `f` runs, so it must not allocate from this
+    /// allocator.
+    #[cfg(feature = "buddy_track_live")]
+    pub fn for_each_live(&self, f: impl FnMut(usize, usize)) -> usize {
+        return self.read().for_each_live(f);
+    }
+}
+
+impl Default for Alloc<RwLock<LockedBuddy>> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl AllocInit for RwLock<LockedBuddy> {
+    unsafe fn init(&self, start: usize, size: usize) {
+        unsafe {
+            #[cfg(debug_assertions)]
+            debug!("Initialized rwlocked buddy alloc; start: {start:#X}, size: {size}");
+            self.write().init(start, size);
+        }
+    }
+
+    unsafe fn deinit(&self) -> Result<(), BAllocatorError> {
+        #[cfg(debug_assertions)]
+        debug!("Deinitialized rwlocked buddy alloc");
+        self.write().deinit();
+        return Ok(());
+    }
+
+    fn is_initialized(&self) -> bool {
+        return self.read().is_initialized();
+    }
 }
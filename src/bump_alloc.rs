@@ -1,16 +1,33 @@
 use conquer_once::spin::OnceCell;
-use spin::Mutex;
 
-use crate::common::Alloc;
+use crate::{
+    common::{Alloc, RwLocked},
+    lock::Mutex,
+};
 
 mod bconst;
 mod locked;
 mod lockless;
 
 pub use crate::bump_alloc::bconst::ConstBump;
-pub use crate::bump_alloc::locked::LockedBump;
+pub use crate::bump_alloc::locked::{BumpBuilder, LockedBump, PADDING_CAPACITY};
 pub use crate::bump_alloc::lockless::LocklessBump;
 
+/// Generation of a bump heap an allocation was made in, for catching blocks
+/// used after the heap was reset. See `LockedBumpAlloc::validate_token`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct AllocToken {
+    generation: usize,
+}
+
 pub type LockedBumpAlloc = Alloc<Mutex<LockedBump>>;
+pub type RwLockedBumpAlloc = RwLocked<LockedBump>;
 pub type LocklessBumpAlloc = Alloc<OnceCell<LocklessBump>>;
+/// A heap too small to allocate from fails the build:
+///
+/// ```compile_fail
+/// use simple_alloc::bump_alloc::ConstBumpAlloc;
+///
+/// static ALLOCATOR: ConstBumpAlloc<0> = ConstBumpAlloc::new();
+/// ```
 pub type ConstBumpAlloc<const S: usize> = Alloc<ConstBump<S>>;
@@ -8,13 +8,24 @@ use core::{
 #[cfg(debug_assertions)]
 use log::{debug, error};
 
-use crate::common::{Alloc, AllocState, BAllocator, BAllocatorError, OOM, align_up};
+#[cfg(feature = "fill_on_free")]
+use crate::common::fill_freed;
+use crate::{
+    bump_alloc::AllocToken,
+    common::{
+        Alloc, AllocBounds, AllocState, BAllocator, BAllocatorError, MISALIGNED, OOM, align_up,
+        check_free,
+    },
+};
 
 #[derive(Debug)]
 pub struct ConstBump<const S: usize> {
     heap: [MaybeUninit<u8>; S],
     offset: AtomicUsize,
     allocations: AtomicUsize,
+    live_bytes: AtomicUsize,
+    /// Bumped whenever `offset` goes back to 0.
+    generation: AtomicUsize,
 }
 
 impl<const S: usize> Default for ConstBump<S> {
@@ -24,20 +35,51 @@ impl<const S: usize> Default for ConstBump<S> {
 }
 
 impl<const S: usize> ConstBump<S> {
+    pub const CAPACITY: usize = S;
+    /// Largest single allocation, the whole heap.
+    pub const MAX_ALLOC_SIZE: usize = S;
+
+    pub const fn capacity() -> usize {
+        return Self::CAPACITY;
+    }
+
     const fn new() -> Self {
+        const { assert!(S > 0, "ConstBump heap cannot be 0 in size") };
         ConstBump {
             heap: [MaybeUninit::<u8>::uninit(); S],
             offset: AtomicUsize::new(0),
             allocations: AtomicUsize::new(0),
+            live_bytes: AtomicUsize::new(0),
+            generation: AtomicUsize::new(0),
         }
     }
 
+    fn can_grow(&self) -> bool {
+        return self.allocations.load(Ordering::SeqCst) == 0;
+    }
+
+    /// An empty `ConstBump<T>` in place of this one. `T` smaller than `S`
+    /// fails the build.
+    fn grow_into<const T: usize>(self) -> ConstBump<T> {
+        const { assert!(T >= S, "ConstBump can only grow into a larger heap") };
+        assert!(self.can_grow(), "ConstBump grown with live allocations");
+        // Tokens from this heap must not validate against the new one.
+        let grown = ConstBump::new();
+        grown
+            .generation
+            .store(self.generation.load(Ordering::SeqCst) + 1, Ordering::SeqCst);
+        return grown;
+    }
+
     fn heap_start(&self) -> usize {
         return self.heap.as_ptr() as usize;
     }
 
+    /// Always exactly `CAPACITY` past `heap_start`, as the heap is an
+    /// inline `[_; S]`.
     fn heap_end(&self) -> usize {
-        return (self.heap.as_ptr() as usize) + S;
+        const { assert!(size_of::<[MaybeUninit<u8>; S]>() == S) };
+        return self.heap_start() + Self::CAPACITY;
     }
 
     fn next(&self) -> usize {
@@ -66,6 +108,13 @@ unsafe impl<const S: usize> BAllocator for ConstBump<S> {
                 Ordering::SeqCst,
             );
             self.allocations.fetch_add(1, Ordering::SeqCst);
+            self.live_bytes.fetch_add(layout.size(), Ordering::SeqCst);
+            debug_assert!(
+                alloc_start.is_multiple_of(layout.align()),
+                "{}: {:#X}, layout: {layout:?}",
+                MISALIGNED,
+                alloc_start
+            );
             #[cfg(debug_assertions)]
             debug!("Allocated object \"{:X}\"; layout: {layout:?}", alloc_start);
             return NonNull::new(alloc_start as *mut u8).ok_or(BAllocatorError::Null);
@@ -74,24 +123,56 @@ unsafe impl<const S: usize> BAllocator for ConstBump<S> {
 
     unsafe fn try_deallocate(
         &self,
-        _ptr: NonNull<u8>,
-        _layout: Layout,
+        ptr: NonNull<u8>,
+        layout: Layout,
     ) -> Result<(), BAllocatorError> {
-        let prev = self.allocations.fetch_sub(1, Ordering::AcqRel);
+        check_free(
+            ptr.as_ptr() as usize,
+            layout.size(),
+            self.heap_start(),
+            self.heap_end(),
+        )?;
+        let Ok(prev) =
+            self.allocations
+                .fetch_update(Ordering::AcqRel, Ordering::Acquire, |count| {
+                    count.checked_sub(1)
+                })
+        else {
+            #[cfg(debug_assertions)]
+            error!("Rejected free with nothing allocated; layout: {layout:?}");
+            return Err(BAllocatorError::InvalidFree);
+        };
+        #[cfg(feature = "fill_on_free")]
+        unsafe {
+            fill_freed(ptr, layout.size())
+        };
 
+        // A free of a block this heap never handed out must not wrap it.
+        let _ = self
+            .live_bytes
+            .fetch_update(Ordering::SeqCst, Ordering::SeqCst, |bytes| {
+                Some(bytes.saturating_sub(layout.size()))
+            });
         if prev == 1 {
             #[cfg(debug_assertions)]
             debug!("All objects deallocated, reseting next pointer to start",);
             self.offset.store(0, Ordering::SeqCst);
+            self.generation.fetch_add(1, Ordering::SeqCst);
         }
 
         #[cfg(debug_assertions)]
         debug!(
-            "Deallocated object \"{:X}\"; layout: {_layout:?}",
-            _ptr.as_ptr() as usize
+            "Deallocated object \"{:X}\"; layout: {layout:?}",
+            ptr.as_ptr() as usize
         );
         return Ok(());
     }
+
+    fn can_allocate(&self, layout: Layout) -> bool {
+        return align_up(self.next(), layout.align())
+            .checked_add(layout.size())
+            .is_some_and(|alloc_end| alloc_end <= self.heap_end());
+    }
 }
 
 unsafe impl<const S: usize> Sync for Alloc<ConstBump<S>> {}
@@ -99,17 +180,150 @@ unsafe impl<const S: usize> Send for Alloc<ConstBump<S>> {}
 
 impl<const S: usize> Alloc<ConstBump<S>> {
     pub const fn new() -> Self {
-        Alloc {
-            alloc: ConstBump::new(),
+        Alloc::wrap(ConstBump::new())
+    }
+
+    /// Allocates as much of `layout.size()` as fits, returning the pointer
+    /// and the number of bytes granted, which is never zero. The block must
+    /// be freed with a layout of the granted size.
+    ///
+    /// # Safety
+    pub unsafe fn try_allocate_partial(
+        &self,
+        layout: Layout,
+    ) -> Result<(NonNull<u8>, usize), BAllocatorError> {
+        let _masked = self.mask_interrupts();
+        let size = layout.size().min(self.remaining_for(layout));
+        if size == 0 {
+            return self.count_alloc(self.record(Err(BAllocatorError::Oom(Some(layout)))));
         }
+
+        let granted = Layout::from_size_align(size, layout.align())?;
+        let ptr = self.count_alloc(self.record(unsafe { self.alloc.try_allocate(granted) }))?;
+        return Ok((ptr, size));
+    }
+
+    /// Deallocates, moving `next` back over the block if it was the most
+    /// recent allocation so its space can be reused straight away.
+    ///
+    /// # Safety
+    pub unsafe fn try_deallocate_lifo(
+        &self,
+        ptr: NonNull<u8>,
+        layout: Layout,
+    ) -> Result<(), BAllocatorError> {
+        unsafe { self.try_deallocate(ptr, layout)? };
+        if layout.size() == 0 {
+            return Ok(());
+        }
+        // Inside the heap, or the free would have failed.
+        let offset = ptr.as_ptr() as usize - self.alloc.heap_start();
+        let _ = self.alloc.offset.compare_exchange(
+            offset + layout.size(),
+            offset,
+            Ordering::SeqCst,
+            Ordering::SeqCst,
+        );
+        return Ok(());
+    }
+
+    /// Like `try_allocate`, also returning a token that `validate_token`
+    /// rejects once every block has been freed and the heap reset, to catch
+    /// blocks used after that.
+    ///
+    /// # Safety
+    pub unsafe fn try_allocate_with_token(
+        &self,
+        layout: Layout,
+    ) -> Result<(NonNull<u8>, AllocToken), BAllocatorError> {
+        let ptr = unsafe { self.try_allocate(layout)? };
+        let token = AllocToken {
+            generation: self.alloc.generation.load(Ordering::SeqCst),
+        };
+        return Ok((ptr, token));
+    }
+
+    /// Whether the heap has not been reset since `token` was handed out.
+    pub fn validate_token(&self, token: &AllocToken) -> bool {
+        return token.generation == self.alloc.generation.load(Ordering::SeqCst);
+    }
+
+    /// Bytes usable by an allocation with `layout`'s alignment, unlike
+    /// `remaining` which does not count the padding needed to align `next`.
+    pub fn remaining_for(&self, layout: Layout) -> usize {
+        return self
+            .alloc
+            .heap_end()
+            .saturating_sub(align_up(self.alloc.next(), layout.align()));
+    }
+
+    /// Bytes between the start of the heap and `next`, including alignment
+    /// padding.
+    pub fn used(&self) -> usize {
+        return self.alloc.offset.load(Ordering::SeqCst);
+    }
+
+    /// Sum of the sizes requested by live allocations. `used() -
+    /// used_bytes_exact()` is the space lost to alignment padding.
+    pub fn used_bytes_exact(&self) -> usize {
+        return self.alloc.live_bytes.load(Ordering::SeqCst);
+    }
+
+    /// Whether `grow_into` may be called, which needs nothing allocated.
+    /// The heap is inline, so moving the allocator would move live blocks
+    /// with it.
+    pub fn can_grow(&self) -> bool {
+        return self.alloc.can_grow();
+    }
+
+    /// Swaps this allocator for a larger empty one when `S` turns out too
+    /// small. Panics unless `can_grow`.
+    ///
+    /// ```compile_fail
+    /// use simple_alloc::bump_alloc::ConstBumpAlloc;
+    ///
+    /// let _ = ConstBumpAlloc::<256>::new().grow_into::<128>();
+    /// ```
+    pub fn grow_into<const T: usize>(self) -> Alloc<ConstBump<T>> {
+        return Alloc::wrap(self.alloc.grow_into());
+    }
+
+    pub const fn max_alloc_size(&self) -> usize {
+        return ConstBump::<S>::MAX_ALLOC_SIZE;
+    }
+
+    /// The heap is part of the allocator, so it is always initialized.
+    pub const fn is_initialized(&self) -> bool {
+        return true;
+    }
+}
+
+#[cfg(test)]
+impl<const S: usize> Alloc<ConstBump<S>> {
+    pub(crate) fn debug_next(&self) -> usize {
+        return self.alloc.next();
+    }
+
+    pub(crate) fn debug_heap_start(&self) -> usize {
+        return self.alloc.heap_start();
     }
 }
 
 impl<const S: usize> AllocState for ConstBump<S> {
+    /// Bytes left at runtime; the total is `CAPACITY`.
     fn remaining(&self) -> usize {
-        return self.heap_end().checked_sub(self.next()).unwrap_or_default();
+        let next = self.next();
+        // Allocations check their end against the heap's before bumping.
+        debug_assert!(next <= self.heap_end(), "Const bump next past heap end");
+        return self.heap_end() - next;
     }
     fn allocations(&self) -> usize {
         return self.allocations.load(Ordering::SeqCst);
     }
 }
+
+impl<const S: usize> AllocBounds for ConstBump<S> {
+    fn contains(&self, ptr: NonNull<u8>) -> bool {
+        return (self.heap_start()..self.heap_end()).contains(&(ptr.as_ptr() as usize));
+    }
+}
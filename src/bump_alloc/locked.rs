@@ -1,20 +1,47 @@
-use core::{alloc::Layout, ptr::NonNull};
+use core::{alloc::Layout, num::NonZeroUsize, ptr::NonNull};
 
 #[cfg(debug_assertions)]
 use log::{debug, error};
-use spin::Mutex;
+use spin::RwLock;
 
-use crate::common::{
-    Alloc, AllocInit, AllocState, BAllocator, BAllocatorError, HEAP_END_OVERFLOWED, HEAP_SIZE_ZERO,
-    HEAP_START_NULL, OOM, align_up,
+#[cfg(feature = "fill_on_free")]
+use crate::common::fill_freed;
+use crate::{
+    bump_alloc::AllocToken,
+    common::{
+        ALLOCATOR_UNINITIALIZED, Alloc, AllocBounds, AllocInit, AllocState, BAllocator,
+        BAllocatorError, BlockMap, HEAP_END_OVERFLOWED, HEAP_SIZE_ZERO, HEAP_START_NULL,
+        IntegrityError, LockedInner, OOM, align_up, check_alloc, check_free, dangling, prefault,
+    },
+    lock::Mutex,
 };
 
+/// Number of live padded allocations whose alignment padding a LIFO free
+/// can give back.
+pub const PADDING_CAPACITY: usize = 16;
+
 #[derive(Debug)]
 pub struct LockedBump {
     start: usize,
     end: usize,
     next: usize,
+    /// Lowest block handed out by `allocate_high`, which grows down from
+    /// `end` towards `next`.
+    top: usize,
     allocations: usize,
+    peak_allocations: usize,
+    live_bytes: usize,
+    /// Start and `next` from before it of allocations that skipped padding
+    /// to align, lowest first, so freeing them LIFO gives the padding back
+    /// too.
+    padding: BlockMap<usize, usize, PADDING_CAPACITY>,
+    /// Bumped whenever `next` goes back to `start` or the heap changes, so
+    /// blocks from before can be told apart.
+    generation: usize,
+    /// Page size and callback told before `next` moves past `committed`.
+    on_grow: Option<(usize, fn(usize))>,
+    /// End of the pages `on_grow` has been told about.
+    committed: usize,
 }
 
 impl Default for LockedBump {
@@ -29,74 +56,415 @@ impl LockedBump {
             start: 0,
             end: 0,
             next: 0,
+            top: 0,
+            allocations: 0,
+            peak_allocations: 0,
+            live_bytes: 0,
+            padding: BlockMap::new((0, 0)),
+            generation: 0,
+            on_grow: None,
+            committed: 0,
+        }
+    }
+
+    const fn new_with_static_heap(heap_start: usize, heap_size: usize) -> Self {
+        NonZeroUsize::new(heap_start).expect(HEAP_START_NULL);
+        NonZeroUsize::new(heap_size).expect(HEAP_SIZE_ZERO);
+        let heap_end = heap_start
+            .checked_add(heap_size)
+            .expect(HEAP_END_OVERFLOWED);
+
+        LockedBump {
+            start: heap_start,
+            end: heap_end,
+            next: heap_start,
+            top: heap_end,
             allocations: 0,
+            peak_allocations: 0,
+            live_bytes: 0,
+            padding: BlockMap::new((0, 0)),
+            generation: 0,
+            on_grow: None,
+            committed: 0,
         }
     }
 
     unsafe fn init(&mut self, heap_start: usize, heap_size: usize) {
         debug_assert!(heap_start != 0, "{}", HEAP_START_NULL);
         debug_assert!(heap_size > 0, "{}", HEAP_SIZE_ZERO);
-        debug_assert!(
-            heap_start + heap_size < usize::MAX,
-            "{}",
-            HEAP_END_OVERFLOWED
-        );
+        let heap_end = heap_start
+            .checked_add(heap_size)
+            .expect(HEAP_END_OVERFLOWED);
 
         self.start = heap_start;
-        self.end = heap_start + heap_size;
+        self.end = heap_end;
         self.next = heap_start;
+        self.top = heap_end;
+        self.padding = BlockMap::new((0, 0));
+        self.generation += 1;
+        self.committed = 0;
+        if let Some((page_size, _)) = self.on_grow {
+            self.committed = LockedBump::first_page_end(heap_start, page_size);
+        }
+    }
+
+    fn is_initialized(&self) -> bool {
+        return self.start != 0;
+    }
+
+    fn deinit(&mut self) {
+        debug_assert_eq!(self.allocations, 0, "Deinitialized with live allocations");
+        let generation = self.generation;
+        *self = LockedBump::new();
+        self.generation = generation + 1;
     }
 
     pub fn allocations(&self) -> usize {
         return self.allocations;
     }
-}
 
-unsafe impl BAllocator for Mutex<LockedBump> {
-    unsafe fn try_allocate(&self, layout: Layout) -> Result<NonNull<u8>, BAllocatorError> {
-        let mut bump = self.lock();
+    /// Token for blocks allocated now, valid until the heap is next reset
+    /// by freeing everything, `init`, `deinit` or `swap_heap`. Moving `next`
+    /// back over a LIFO free does not count as a reset.
+    pub fn token(&self) -> AllocToken {
+        return AllocToken {
+            generation: self.generation,
+        };
+    }
+
+    pub fn validate_token(&self, token: &AllocToken) -> bool {
+        return token.generation == self.generation;
+    }
+
+    /// Calls `on_grow(new_end)` whenever an allocation would take `next`
+    /// into a page, of `page_size` bytes, that `on_grow` has not been told
+    /// about yet, before the pointer is handed out. `new_end` is the end of
+    /// the last page the allocation touches, capped at the end of the heap,
+    /// so memory mapped lazily can be mapped up to it first. The page
+    /// holding the start of the heap is assumed mapped. `end` is still the
+    /// hard limit, and `on_grow` runs with the allocator locked. As mapping
+    /// only ever grows upwards, the first `allocate_high` calls it with the
+    /// end of the heap.
+    pub fn set_on_grow(&mut self, page_size: usize, on_grow: fn(usize)) {
+        assert!(
+            page_size.is_power_of_two(),
+            "Page size must be a power of two"
+        );
+        self.on_grow = Some((page_size, on_grow));
+        self.committed = LockedBump::first_page_end(self.start, page_size);
+    }
+
+    fn first_page_end(heap_start: usize, page_size: usize) -> usize {
+        return (heap_start & !(page_size - 1)).saturating_add(page_size);
+    }
+
+    /// Bytes between the start of the heap and `next`, plus those between
+    /// `top` and the end, including alignment padding.
+    pub fn used(&self) -> usize {
+        return (self.next - self.start) + (self.end - self.top);
+    }
+
+    /// Sum of the sizes requested by live allocations. `used() -
+    /// used_bytes_exact()` is the space lost to alignment padding.
+    pub fn used_bytes_exact(&self) -> usize {
+        return self.live_bytes;
+    }
+
+    /// Largest single allocation the heap could ever hold, its whole size.
+    pub fn max_alloc_size(&self) -> usize {
+        return self.end - self.start;
+    }
+
+    /// Checks that `start <= next <= top <= end`.
+    pub fn self_test(&self) -> Result<(), IntegrityError> {
+        if !(self.start..=self.top).contains(&self.next) || self.top > self.end {
+            return Err(IntegrityError::NextOutOfRange {
+                start: self.start,
+                next: self.next,
+                end: self.top,
+            });
+        }
+        return Ok(());
+    }
+
+    /// Touches the heap once per `page_size` bytes, the MMU page size of
+    /// the target, so no allocation pays for the first access to its pages.
+    ///
+    /// # Safety
+    /// Nothing may access memory handed out from this heap while it runs,
+    /// which is simplest to ensure by calling it straight after `init`.
+    pub unsafe fn prefault(&self, page_size: usize) {
+        unsafe { prefault(self.start, self.end, page_size) };
+    }
+
+    /// Writes little endian `total: u64`, `used: u64`, `allocations: u32` and
+    /// `peak allocations: u32`, the counts saturating, returning the number of
+    /// bytes written.
+    pub fn encode_stats(&self, buf: &mut [u8; 24]) -> usize {
+        let total = (self.end - self.start) as u64;
+        let used = self.used() as u64;
+        let allocations = u32::try_from(self.allocations).unwrap_or(u32::MAX);
+        let peak = u32::try_from(self.peak_allocations).unwrap_or(u32::MAX);
+
+        buf[0..8].copy_from_slice(&total.to_le_bytes());
+        buf[8..16].copy_from_slice(&used.to_le_bytes());
+        buf[16..20].copy_from_slice(&allocations.to_le_bytes());
+        buf[20..24].copy_from_slice(&peak.to_le_bytes());
+        return buf.len();
+    }
+
+    /// Takes over `other`'s heap if it starts where ours ends and nothing is
+    /// allocated from it, leaving `other` uninitialized.
+    fn merge_with(&mut self, other: &mut LockedBump) -> Result<(), BAllocatorError> {
+        if !self.is_initialized() || self.end != other.start || other.allocations != 0 {
+            return Err(BAllocatorError::Unmergeable);
+        }
+
+        #[cfg(debug_assertions)]
+        debug!(
+            "Merged bump heap {:#X}..{:#X} into {:#X}..{:#X}",
+            other.start, other.end, self.start, self.end
+        );
+        if self.top == self.end {
+            self.top = other.end;
+        }
+        self.end = other.end;
+        let generation = other.generation;
+        *other = LockedBump::new();
+        other.generation = generation + 1;
+        return Ok(());
+    }
+
+    unsafe fn swap_heap(&mut self, heap_start: usize, heap_size: usize) -> (usize, usize) {
+        let old = (self.start, self.end - self.start);
+        unsafe { self.init(heap_start, heap_size) };
+        self.allocations = 0;
+        self.live_bytes = 0;
+
+        #[cfg(debug_assertions)]
+        debug!(
+            "Swapped bump heap {:#X}..{:#X} for {heap_start:#X}..{:#X}",
+            old.0,
+            old.0 + old.1,
+            self.end
+        );
+        return old;
+    }
+
+    unsafe fn allocate_partial(
+        &mut self,
+        layout: Layout,
+    ) -> Result<(NonNull<u8>, usize), BAllocatorError> {
+        let available = self.top.saturating_sub(align_up(self.next, layout.align()));
+        let size = layout.size().min(available);
+        if size == 0 {
+            return Err(BAllocatorError::Oom(Some(layout)));
+        }
 
-        let alloc_start = align_up(bump.next, layout.align());
+        let granted = Layout::from_size_align(size, layout.align())?;
+        let ptr = unsafe { self.allocate(granted)? };
+        return Ok((ptr, size));
+    }
+
+    /// Allocates just below `top`, moving it down, so the heap can be shared
+    /// by two users growing towards each other. Out of memory once `top`
+    /// would pass `next`. Blocks are freed as usual.
+    fn allocate_high(&mut self, layout: Layout) -> Result<NonNull<u8>, BAllocatorError> {
+        if !self.is_initialized() {
+            #[cfg(debug_assertions)]
+            error!("{}", ALLOCATOR_UNINITIALIZED);
+            return Err(BAllocatorError::Uninitialized);
+        }
+        let Some(alloc_start) = self
+            .top
+            .checked_sub(layout.size())
+            .map(|start| start & !(layout.align() - 1))
+            .filter(|&start| start >= self.next)
+        else {
+            #[cfg(debug_assertions)]
+            error!("{}", OOM);
+            return Err(BAllocatorError::Oom(Some(layout)));
+        };
+        check_alloc(alloc_start, layout, self.start, self.end);
+        if let Some((_, on_grow)) = self.on_grow
+            && self.committed < self.end
+        {
+            self.committed = self.end;
+            #[cfg(debug_assertions)]
+            debug!("Growing bump heap to {:#X}", self.committed);
+            on_grow(self.committed);
+        }
+
+        self.top = alloc_start;
+        self.allocations += 1;
+        self.peak_allocations = self.peak_allocations.max(self.allocations);
+        self.live_bytes += layout.size();
+        #[cfg(debug_assertions)]
+        debug!(
+            "Allocated high object \"{:X}\"; layout: {layout:?}",
+            alloc_start
+        );
+        return NonNull::new(alloc_start as *mut u8).ok_or(BAllocatorError::Null);
+    }
+
+    unsafe fn deallocate_lifo(
+        &mut self,
+        ptr: NonNull<u8>,
+        layout: Layout,
+    ) -> Result<(), BAllocatorError> {
+        let addr = ptr.as_ptr() as usize;
+        unsafe { self.deallocate(ptr, layout)? };
+        if addr.checked_add(layout.size()) == Some(self.next) {
+            self.next = self.pop_padding(addr).unwrap_or(addr);
+        }
+        return Ok(());
+    }
+
+    /// Forgets the padding of blocks at or above `addr`, which `next` has
+    /// moved back below, returning the `next` from before the one at `addr`
+    /// if it was padded.
+    fn pop_padding(&mut self, addr: usize) -> Option<usize> {
+        let mut before = None;
+        while let Some(&(start, next)) = self.padding.entries().last()
+            && start >= addr
+        {
+            self.padding.pop();
+            if start == addr {
+                before = Some(next);
+            }
+        }
+        return before;
+    }
+}
+
+unsafe impl LockedInner for LockedBump {
+    unsafe fn allocate(&mut self, layout: Layout) -> Result<NonNull<u8>, BAllocatorError> {
+        if !self.is_initialized() {
+            #[cfg(debug_assertions)]
+            error!("{}", ALLOCATOR_UNINITIALIZED);
+            return Err(BAllocatorError::Uninitialized);
+        }
+        let alloc_start = align_up(self.next, layout.align());
         let alloc_end = match alloc_start.checked_add(layout.size()) {
             Some(end) => end,
             None => return Err(BAllocatorError::Overflowed),
         };
 
-        if alloc_end > bump.end {
+        if alloc_end > self.top {
             #[cfg(debug_assertions)]
             error!("{}", OOM);
             return Err(BAllocatorError::Oom(Some(layout)));
         } else {
-            bump.next = alloc_end;
-            bump.allocations += 1;
-            #[cfg(debug_assertions)]
+            if let Some((page_size, on_grow)) = self.on_grow
+                && alloc_end > self.committed
+            {
+                self.committed = align_up(alloc_end, page_size).min(self.end);
+                #[cfg(debug_assertions)]
+                debug!("Growing bump heap to {:#X}", self.committed);
+                on_grow(self.committed);
+            }
+            self.pop_padding(self.next);
+            // Past `PADDING_CAPACITY` the padding is not given back.
+            if alloc_start != self.next {
+                self.padding.insert(alloc_start, self.next);
+            }
+            self.next = alloc_end;
+            self.allocations += 1;
+            self.peak_allocations = self.peak_allocations.max(self.allocations);
+            self.live_bytes += layout.size();
+            check_alloc(alloc_start, layout, self.start, self.end);
             #[cfg(debug_assertions)]
             debug!("Allocated object \"{:X}\"; layout: {layout:?}", alloc_start);
             return NonNull::new(alloc_start as *mut u8).ok_or(BAllocatorError::Null);
         }
     }
 
-    unsafe fn try_deallocate(
-        &self,
-        _ptr: NonNull<u8>,
-        _layout: Layout,
+    unsafe fn deallocate(
+        &mut self,
+        ptr: NonNull<u8>,
+        layout: Layout,
     ) -> Result<(), BAllocatorError> {
-        let mut bump = self.lock();
+        check_free(ptr.as_ptr() as usize, layout.size(), self.start, self.end)?;
+        if self.allocations == 0 {
+            #[cfg(debug_assertions)]
+            error!("Rejected free with nothing allocated; layout: {layout:?}");
+            return Err(BAllocatorError::InvalidFree);
+        }
+        #[cfg(feature = "fill_on_free")]
+        unsafe {
+            fill_freed(ptr, layout.size())
+        };
 
-        bump.allocations -= 1;
-        if bump.allocations == 0 {
+        self.allocations -= 1;
+        self.live_bytes = self.live_bytes.saturating_sub(layout.size());
+        if self.allocations == 0 {
             #[cfg(debug_assertions)]
             debug!("All objects deallocated, reseting next pointer to start",);
-            bump.next = bump.start;
+            self.next = self.start;
+            self.top = self.end;
+            self.padding = BlockMap::new((0, 0));
+            self.generation += 1;
         }
 
         #[cfg(debug_assertions)]
         debug!(
-            "Deallocated object \"{:X}\"; layout: {_layout:?}",
-            _ptr.as_ptr() as usize
+            "Deallocated object \"{:X}\"; layout: {layout:?}",
+            ptr.as_ptr() as usize
         );
         return Ok(());
     }
+
+    fn can_allocate(&self, layout: Layout) -> bool {
+        return align_up(self.next, layout.align())
+            .checked_add(layout.size())
+            .is_some_and(|alloc_end| alloc_end <= self.top);
+    }
+}
+
+/// Configures a bump allocator in one place before building it, as
+/// `LinkedListBuilder` does for linked lists.
+#[derive(Debug, Clone, Copy)]
+pub struct BumpBuilder {
+    on_grow: Option<(usize, fn(usize))>,
+}
+
+impl Default for BumpBuilder {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl BumpBuilder {
+    /// The defaults of `LockedBumpAlloc::new`.
+    pub const fn new() -> Self {
+        BumpBuilder { on_grow: None }
+    }
+
+    /// See `LockedBump::set_on_grow`.
+    pub const fn on_grow(mut self, page_size: usize, on_grow: fn(usize)) -> Self {
+        assert!(
+            page_size.is_power_of_two(),
+            "Page size must be a power of two"
+        );
+        self.on_grow = Some((page_size, on_grow));
+        return self;
+    }
+
+    /// `init` works out which pages `on_grow` has been told about.
+    const fn configure(self) -> LockedBump {
+        let mut alloc = LockedBump::new();
+        alloc.on_grow = self.on_grow;
+        return alloc;
+    }
+
+    /// Uninitialized allocator with this configuration, `init` it as usual.
+    pub const fn build(self) -> Alloc<Mutex<LockedBump>> {
+        return Alloc::wrap(Mutex::new(self.configure()));
+    }
+
+    pub const fn build_rwlocked(self) -> Alloc<RwLock<LockedBump>> {
+        return Alloc::wrap(RwLock::new(self.configure()));
+    }
 }
 
 unsafe impl Sync for Alloc<Mutex<LockedBump>> {}
@@ -104,9 +472,142 @@ unsafe impl Send for Alloc<Mutex<LockedBump>> {}
 
 impl Alloc<Mutex<LockedBump>> {
     pub const fn new() -> Self {
-        Alloc {
-            alloc: Mutex::new(LockedBump::new()),
+        Alloc::wrap(Mutex::new(LockedBump::new()))
+    }
+
+    /// Already initialized allocator over a heap at a fixed address, for
+    /// statics that cannot call `init` before first use.
+    ///
+    /// # Safety
+    /// The heap must be valid and unused, as for `init`.
+    pub const unsafe fn new_with_static_heap(start: usize, size: usize) -> Self {
+        Alloc::wrap(Mutex::new(LockedBump::new_with_static_heap(start, size)))
+    }
+
+    /// Deallocates, moving `next` back over the block if it was the most
+    /// recent allocation so its space can be reused straight away. The
+    /// padding skipped to align it is given back too, for up to
+    /// `PADDING_CAPACITY` live padded blocks.
+    ///
+    /// # Safety
+    pub unsafe fn try_deallocate_lifo(
+        &self,
+        ptr: NonNull<u8>,
+        layout: Layout,
+    ) -> Result<(), BAllocatorError> {
+        if layout.size() == 0 {
+            return unsafe { self.try_deallocate(ptr, layout) };
+        }
+        let _masked = self.mask_interrupts();
+        let result = unsafe { self.alloc.lock().deallocate_lifo(ptr, layout) };
+        return self.count_dealloc(ptr, result);
+    }
+
+    /// Allocates as much of `layout.size()` as fits, returning the pointer
+    /// and the number of bytes granted, which is never zero. The block must
+    /// be freed with a layout of the granted size.
+    ///
+    /// # Safety
+    pub unsafe fn try_allocate_partial(
+        &self,
+        layout: Layout,
+    ) -> Result<(NonNull<u8>, usize), BAllocatorError> {
+        return self.count_alloc(self.record(unsafe { self.lock().allocate_partial(layout) }));
+    }
+
+    /// Allocates from the end of the heap downwards, while `try_allocate`
+    /// keeps growing up from the start, failing with `Oom` once the two
+    /// meet. Free the block with `try_deallocate`.
+    ///
+    /// # Safety
+    pub unsafe fn try_allocate_high(&self, layout: Layout) -> Result<NonNull<u8>, BAllocatorError> {
+        if layout.size() == 0 {
+            return self.count_alloc(Ok(dangling(layout)));
+        }
+        return self.count_alloc(self.record(self.lock().allocate_high(layout)));
+    }
+
+    /// Like `try_allocate`, also returning a token that `validate_token`
+    /// rejects once the heap has been reset, to catch blocks used after
+    /// that.
+    ///
+    /// # Safety
+    pub unsafe fn try_allocate_with_token(
+        &self,
+        layout: Layout,
+    ) -> Result<(NonNull<u8>, AllocToken), BAllocatorError> {
+        let ptr = unsafe { self.try_allocate(layout)? };
+        return Ok((ptr, self.lock().token()));
+    }
+
+    /// Whether the heap has not been reset since `token` was handed out.
+    pub fn validate_token(&self, token: &AllocToken) -> bool {
+        return self.lock().validate_token(token);
+    }
+
+    /// Installs `[new_start, new_start + new_size)` as the heap, returning the
+    /// previous `(start, size)`. Pointers into the old heap stay valid until
+    /// the caller reuses it, but must not be freed through this allocator.
+    ///
+    /// # Safety
+    /// The new region must be valid and unused, as for `init`.
+    pub unsafe fn swap_heap(&self, new_start: usize, new_size: usize) -> (usize, usize) {
+        unsafe {
+            return self.lock().swap_heap(new_start, new_size);
+        }
+    }
+
+    /// See `LockedBump::set_on_grow`.
+    pub fn set_on_grow(&self, page_size: usize, on_grow: fn(usize)) {
+        self.lock().set_on_grow(page_size, on_grow);
+    }
+
+    pub fn encode_stats(&self, buf: &mut [u8; 24]) -> usize {
+        return self.lock().encode_stats(buf);
+    }
+
+    pub fn used(&self) -> usize {
+        return self.lock().used();
+    }
+
+    pub fn used_bytes_exact(&self) -> usize {
+        return self.lock().used_bytes_exact();
+    }
+
+    pub fn max_alloc_size(&self) -> usize {
+        return self.lock().max_alloc_size();
+    }
+
+    pub fn self_test(&self) -> Result<(), IntegrityError> {
+        return self.lock().self_test();
+    }
+
+    /// See `LockedBump::prefault`.
+    ///
+    /// # Safety
+    /// As for `LockedBump::prefault`.
+    pub unsafe fn prefault(&self, page_size: usize) {
+        unsafe { self.lock().prefault(page_size) };
+    }
+
+    /// Extends this heap over `other`'s when `other` is physically adjacent
+    /// and has no live allocations. `other` is left uninitialized.
+    pub fn merge_with(&self, other: &Self) -> Result<(), BAllocatorError> {
+        if core::ptr::eq(self, other) {
+            return Err(BAllocatorError::Unmergeable);
         }
+        return self.with_pair(other, |ours, theirs| ours.merge_with(theirs));
+    }
+}
+
+#[cfg(test)]
+impl Alloc<Mutex<LockedBump>> {
+    pub(crate) fn debug_next(&self) -> usize {
+        return self.lock().next;
+    }
+
+    pub(crate) fn corrupt_next(&self, next: usize) {
+        self.lock().next = next;
     }
 }
 
@@ -124,15 +625,173 @@ impl AllocInit for Mutex<LockedBump> {
             self.lock().init(start, size);
         }
     }
+
+    unsafe fn deinit(&self) -> Result<(), BAllocatorError> {
+        #[cfg(debug_assertions)]
+        debug!("Deinitialized locked bump alloc");
+        self.lock().deinit();
+        return Ok(());
+    }
+
+    fn is_initialized(&self) -> bool {
+        return self.lock().is_initialized();
+    }
 }
 
-impl AllocState for Mutex<LockedBump> {
+impl AllocState for LockedBump {
     fn remaining(&self) -> usize {
-        let alloc = self.lock();
-        return alloc.end.checked_sub(alloc.next).unwrap_or_default();
+        // Blocks from either end check they fit between `next` and `top`.
+        debug_assert!(self.next <= self.top, "Bump next past top");
+        return self.top - self.next;
     }
     fn allocations(&self) -> usize {
-        let alloc = self.lock();
-        return alloc.allocations;
+        return self.allocations;
+    }
+}
+
+impl AllocBounds for LockedBump {
+    fn contains(&self, ptr: NonNull<u8>) -> bool {
+        return (self.start..self.end).contains(&(ptr.as_ptr() as usize));
+    }
+}
+
+unsafe impl Sync for Alloc<RwLock<LockedBump>> {}
+unsafe impl Send for Alloc<RwLock<LockedBump>> {}
+
+impl Alloc<RwLock<LockedBump>> {
+    pub const fn new() -> Self {
+        Alloc::wrap(RwLock::new(LockedBump::new()))
+    }
+
+    /// Already initialized allocator over a heap at a fixed address, for
+    /// statics that cannot call `init` before first use.
+    ///
+    /// # Safety
+    /// The heap must be valid and unused, as for `init`.
+    pub const unsafe fn new_with_static_heap(start: usize, size: usize) -> Self {
+        Alloc::wrap(RwLock::new(LockedBump::new_with_static_heap(start, size)))
+    }
+
+    /// Allocates as much of `layout.size()` as fits, returning the pointer
+    /// and the number of bytes granted, which is never zero. The block must
+    /// be freed with a layout of the granted size.
+    ///
+    /// # Safety
+    pub unsafe fn try_allocate_partial(
+        &self,
+        layout: Layout,
+    ) -> Result<(NonNull<u8>, usize), BAllocatorError> {
+        return self.count_alloc(self.record(unsafe { self.write().allocate_partial(layout) }));
+    }
+
+    /// Allocates from the end of the heap downwards, while `try_allocate`
+    /// keeps growing up from the start, failing with `Oom` once the two
+    /// meet. Free the block with `try_deallocate`.
+    ///
+    /// # Safety
+    pub unsafe fn try_allocate_high(&self, layout: Layout) -> Result<NonNull<u8>, BAllocatorError> {
+        if layout.size() == 0 {
+            return self.count_alloc(Ok(dangling(layout)));
+        }
+        return self.count_alloc(self.record(self.write().allocate_high(layout)));
+    }
+
+    /// Like `try_allocate`, also returning a token that `validate_token`
+    /// rejects once the heap has been reset, to catch blocks used after
+    /// that.
+    ///
+    /// # Safety
+    pub unsafe fn try_allocate_with_token(
+        &self,
+        layout: Layout,
+    ) -> Result<(NonNull<u8>, AllocToken), BAllocatorError> {
+        let ptr = unsafe { self.try_allocate(layout)? };
+        return Ok((ptr, self.read().token()));
+    }
+
+    /// Whether the heap has not been reset since `token` was handed out.
+    pub fn validate_token(&self, token: &AllocToken) -> bool {
+        return self.read().validate_token(token);
+    }
+
+    /// Installs `[new_start, new_start + new_size)` as the heap, returning the
+    /// previous `(start, size)`. Pointers into the old heap stay valid until
+    /// the caller reuses it, but must not be freed through this allocator.
+    ///
+    /// # Safety
+    /// The new region must be valid and unused, as for `init`.
+    pub unsafe fn swap_heap(&self, new_start: usize, new_size: usize) -> (usize, usize) {
+        unsafe {
+            return self.write().swap_heap(new_start, new_size);
+        }
+    }
+
+    /// See `LockedBump::set_on_grow`.
+    pub fn set_on_grow(&self, page_size: usize, on_grow: fn(usize)) {
+        self.write().set_on_grow(page_size, on_grow);
+    }
+
+    pub fn encode_stats(&self, buf: &mut [u8; 24]) -> usize {
+        return self.read().encode_stats(buf);
+    }
+
+    pub fn used(&self) -> usize {
+        return self.read().used();
+    }
+
+    pub fn used_bytes_exact(&self) -> usize {
+        return self.read().used_bytes_exact();
+    }
+
+    pub fn max_alloc_size(&self) -> usize {
+        return self.read().max_alloc_size();
+    }
+
+    pub fn self_test(&self) -> Result<(), IntegrityError> {
+        return self.read().self_test();
+    }
+
+    /// See `LockedBump::prefault`.
+    ///
+    /// # Safety
+    /// As for `LockedBump::prefault`.
+    pub unsafe fn prefault(&self, page_size: usize) {
+        unsafe { self.read().prefault(page_size) };
+    }
+
+    /// Extends this heap over `other`'s when `other` is physically adjacent
+    /// and has no live allocations. `other` is left uninitialized.
+    pub fn merge_with(&self, other: &Self) -> Result<(), BAllocatorError> {
+        if core::ptr::eq(self, other) {
+            return Err(BAllocatorError::Unmergeable);
+        }
+        return self.with_pair(other, |ours, theirs| ours.merge_with(theirs));
+    }
+}
+
+impl Default for Alloc<RwLock<LockedBump>> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl AllocInit for RwLock<LockedBump> {
+    unsafe fn init(&self, start: usize, size: usize) {
+        unsafe {
+            #[cfg(debug_assertions)]
+            debug!("Initialized rwlocked bump alloc; start: {start:#X}, size: {size}");
+            self.write().init(start, size);
+        }
+    }
+
+    unsafe fn deinit(&self) -> Result<(), BAllocatorError> {
+        #[cfg(debug_assertions)]
+        debug!("Deinitialized rwlocked bump alloc");
+        self.write().deinit();
+        return Ok(());
+    }
+
+    fn is_initialized(&self) -> bool {
+        return self.read().is_initialized();
     }
 }
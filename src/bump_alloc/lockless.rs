@@ -8,17 +8,26 @@ use conquer_once::spin::OnceCell;
 #[cfg(debug_assertions)]
 use log::{debug, error};
 
+#[cfg(feature = "fill_on_free")]
+use crate::common::fill_freed;
 use crate::common::{
-    ALLOCATOR_UNINITIALIZED, Alloc, AllocInit, AllocState, BAllocator, BAllocatorError,
-    HEAP_END_OVERFLOWED, HEAP_SIZE_ZERO, HEAP_START_NULL, OOM, align_up,
+    ALLOCATOR_UNINITIALIZED, Alloc, AllocBounds, AllocInit, AllocState, BAllocator,
+    BAllocatorError, HEAP_END_OVERFLOWED, HEAP_SIZE_ZERO, HEAP_START_NULL, MISALIGNED, OOM,
+    align_up, check_free,
 };
 
 #[derive(Debug)]
 pub struct LocklessBump {
     start: usize,
     end: usize,
-    next: AtomicUsize,
-    allocations: AtomicUsize,
+    /// Bits of `state` holding the offset of `next` from `start`, enough for
+    /// the whole heap. The live allocation count takes the bits above.
+    shift: u32,
+    /// The live allocation count and the offset of `next`, in one word so
+    /// that claiming a block and freeing the last one each move both at
+    /// once.
+    state: AtomicUsize,
+    live_bytes: AtomicUsize,
 }
 
 impl Default for LocklessBump {
@@ -32,27 +41,157 @@ impl LocklessBump {
         LocklessBump {
             start: 0,
             end: 0,
-            next: AtomicUsize::new(0),
-            allocations: AtomicUsize::new(0),
+            shift: 0,
+            state: AtomicUsize::new(0),
+            live_bytes: AtomicUsize::new(0),
         }
     }
 
     unsafe fn init(&mut self, heap_start: usize, heap_size: usize) {
         debug_assert!(heap_start != 0, "{}", HEAP_START_NULL);
         debug_assert!(heap_size > 0, "{}", HEAP_SIZE_ZERO);
-        debug_assert!(
-            heap_start + heap_size < usize::MAX,
-            "{}",
-            HEAP_END_OVERFLOWED
+        let heap_end = heap_start
+            .checked_add(heap_size)
+            .expect(HEAP_END_OVERFLOWED);
+        let shift = usize::BITS - heap_size.leading_zeros();
+        assert!(
+            shift < usize::BITS,
+            "Heap too large to count lockless bump allocations"
         );
 
         self.start = heap_start;
-        self.end = heap_start + heap_size;
-        self.next = AtomicUsize::new(heap_start);
+        self.end = heap_end;
+        self.shift = shift;
+        self.state = AtomicUsize::new(0);
+    }
+
+    /// Splits `state` into the live allocation count and `next`.
+    fn unpack(&self, state: usize) -> (usize, usize) {
+        return (
+            state >> self.shift,
+            self.start + (state & ((1 << self.shift) - 1)),
+        );
+    }
+
+    fn pack(&self, allocations: usize, next: usize) -> usize {
+        return (allocations << self.shift) | (next - self.start);
     }
 
     pub fn allocations(&self) -> usize {
-        return self.allocations.load(Ordering::SeqCst);
+        return self.unpack(self.state.load(Ordering::SeqCst)).0;
+    }
+
+    fn next(&self) -> usize {
+        return self.unpack(self.state.load(Ordering::SeqCst)).1;
+    }
+
+    /// Moves `next` past a block aligned to `align`, of the size `size_at`
+    /// picks for the block's start, returning the start and size. Only
+    /// bumps `next` if no one else has moved it since it was read,
+    /// otherwise two threads could hand out the same block.
+    fn claim(
+        &self,
+        align: usize,
+        size_at: impl Fn(usize) -> Result<usize, BAllocatorError>,
+    ) -> Result<(usize, usize), BAllocatorError> {
+        let mut state = self.state.load(Ordering::SeqCst);
+
+        loop {
+            let (allocations, next) = self.unpack(state);
+            if allocations == usize::MAX >> self.shift {
+                #[cfg(debug_assertions)]
+                error!("Too many live lockless bump allocations");
+                return Err(BAllocatorError::Oom(None));
+            }
+            let alloc_start = align_up(next, align);
+            let size = size_at(alloc_start)?;
+
+            match self.state.compare_exchange_weak(
+                state,
+                self.pack(allocations + 1, alloc_start + size),
+                Ordering::SeqCst,
+                Ordering::SeqCst,
+            ) {
+                Ok(_) => return Ok((alloc_start, size)),
+                Err(current) => state = current,
+            }
+        }
+    }
+
+    /// Drops a live allocation, moving `next` back to the start of the heap
+    /// in the same step if it was the last. Fails, changing nothing, if none
+    /// are live.
+    fn release(&self) -> Result<(), BAllocatorError> {
+        let mut state = self.state.load(Ordering::SeqCst);
+
+        loop {
+            let (allocations, next) = self.unpack(state);
+            let Some(allocations) = allocations.checked_sub(1) else {
+                #[cfg(debug_assertions)]
+                error!("Rejected free with nothing allocated");
+                return Err(BAllocatorError::InvalidFree);
+            };
+            let next = if allocations == 0 { self.start } else { next };
+
+            match self.state.compare_exchange_weak(
+                state,
+                self.pack(allocations, next),
+                Ordering::SeqCst,
+                Ordering::SeqCst,
+            ) {
+                Ok(_) => {
+                    #[cfg(debug_assertions)]
+                    if allocations == 0 {
+                        debug!("All objects deallocated, reseting next pointer to start");
+                    }
+                    return Ok(());
+                }
+                Err(current) => state = current,
+            }
+        }
+    }
+
+    fn allocate_partial(&self, layout: Layout) -> Result<(NonNull<u8>, usize), BAllocatorError> {
+        // What fits is worked out again whenever another thread moves `next`
+        // first.
+        let (alloc_start, size) = self.claim(layout.align(), |alloc_start| {
+            let size = layout.size().min(self.end.saturating_sub(alloc_start));
+            if size == 0 {
+                return Err(BAllocatorError::Oom(Some(layout)));
+            }
+            return Ok(size);
+        })?;
+
+        self.live_bytes.fetch_add(size, Ordering::SeqCst);
+        #[cfg(debug_assertions)]
+        debug!("Allocated {size} of {layout:?} at \"{alloc_start:X}\"");
+        return Ok((
+            NonNull::new(alloc_start as *mut u8).ok_or(BAllocatorError::Null)?,
+            size,
+        ));
+    }
+
+    /// Moves `next` back from `end` to `addr`, if nothing was claimed past
+    /// the freed block `[addr, end)` since.
+    fn rewind(&self, addr: usize, end: usize) {
+        let mut state = self.state.load(Ordering::SeqCst);
+
+        loop {
+            let (allocations, next) = self.unpack(state);
+            if next != end {
+                return;
+            }
+
+            match self.state.compare_exchange_weak(
+                state,
+                self.pack(allocations, addr),
+                Ordering::SeqCst,
+                Ordering::SeqCst,
+            ) {
+                Ok(_) => return,
+                Err(current) => state = current,
+            }
+        }
     }
 }
 
@@ -60,48 +199,67 @@ unsafe impl BAllocator for OnceCell<LocklessBump> {
     unsafe fn try_allocate(&self, layout: Layout) -> Result<NonNull<u8>, BAllocatorError> {
         let alloc = self.get().expect(ALLOCATOR_UNINITIALIZED);
 
-        let next = alloc.next.load(Ordering::SeqCst);
-
-        let alloc_start = align_up(next, layout.align());
-        let alloc_end = match alloc_start.checked_add(layout.size()) {
-            Some(end) => end,
-            None => return Err(BAllocatorError::Overflowed),
-        };
+        let (alloc_start, _) = alloc.claim(layout.align(), |alloc_start| {
+            let alloc_end = alloc_start
+                .checked_add(layout.size())
+                .ok_or(BAllocatorError::Overflowed)?;
+            if alloc_end > alloc.end {
+                #[cfg(debug_assertions)]
+                error!("{}", OOM);
+                return Err(BAllocatorError::Oom(Some(layout)));
+            }
+            return Ok(layout.size());
+        })?;
 
-        if alloc_end > alloc.end {
-            #[cfg(debug_assertions)]
-            error!("{}", OOM);
-            return Err(BAllocatorError::Oom(Some(layout)));
-        } else {
-            alloc.next.store(alloc_end, Ordering::SeqCst);
-            alloc.allocations.fetch_add(1, Ordering::SeqCst);
-            #[cfg(debug_assertions)]
-            debug!("Allocated object \"{:X}\"; layout: {layout:?}", alloc_start);
-            return NonNull::new(alloc_start as *mut u8).ok_or(BAllocatorError::Null);
-        }
+        alloc.live_bytes.fetch_add(layout.size(), Ordering::SeqCst);
+        debug_assert!(
+            alloc_start.is_multiple_of(layout.align()),
+            "{}: {:#X}, layout: {layout:?}",
+            MISALIGNED,
+            alloc_start
+        );
+        #[cfg(debug_assertions)]
+        debug!("Allocated object \"{:X}\"; layout: {layout:?}", alloc_start);
+        return NonNull::new(alloc_start as *mut u8).ok_or(BAllocatorError::Null);
     }
 
     unsafe fn try_deallocate(
         &self,
-        _ptr: NonNull<u8>,
-        _layout: Layout,
+        ptr: NonNull<u8>,
+        layout: Layout,
     ) -> Result<(), BAllocatorError> {
         let alloc = self.get().expect(ALLOCATOR_UNINITIALIZED);
-        let prev = alloc.allocations.fetch_sub(1, Ordering::AcqRel);
+        check_free(ptr.as_ptr() as usize, layout.size(), alloc.start, alloc.end)?;
+        // Filled before it is released, after which it may be claimed again.
+        #[cfg(feature = "fill_on_free")]
+        unsafe {
+            fill_freed(ptr, layout.size())
+        };
 
-        if prev == 1 {
-            #[cfg(debug_assertions)]
-            debug!("All objects deallocated, reseting next pointer to start",);
-            alloc.next.store(alloc.start, Ordering::SeqCst);
-        }
+        // A free of a block this heap never handed out must not wrap it.
+        let _ = alloc
+            .live_bytes
+            .fetch_update(Ordering::SeqCst, Ordering::SeqCst, |bytes| {
+                Some(bytes.saturating_sub(layout.size()))
+            });
+        alloc.release()?;
 
         #[cfg(debug_assertions)]
         debug!(
-            "Deallocated object \"{:X}\"; layout: {_layout:?}",
-            _ptr.as_ptr() as usize
+            "Deallocated object \"{:X}\"; layout: {layout:?}",
+            ptr.as_ptr() as usize
         );
         return Ok(());
     }
+
+    fn can_allocate(&self, layout: Layout) -> bool {
+        let Some(alloc) = self.get() else {
+            return false;
+        };
+        return align_up(alloc.next(), layout.align())
+            .checked_add(layout.size())
+            .is_some_and(|alloc_end| alloc_end <= alloc.end);
+    }
 }
 
 unsafe impl Sync for Alloc<OnceCell<LocklessBump>> {}
@@ -109,9 +267,69 @@ unsafe impl Send for Alloc<OnceCell<LocklessBump>> {}
 
 impl Alloc<OnceCell<LocklessBump>> {
     pub const fn new() -> Self {
-        Alloc {
-            alloc: OnceCell::uninit(),
+        Alloc::wrap(OnceCell::uninit())
+    }
+
+    /// Allocates as much of `layout.size()` as fits, returning the pointer
+    /// and the number of bytes granted, which is never zero. The block must
+    /// be freed with a layout of the granted size.
+    ///
+    /// # Safety
+    pub unsafe fn try_allocate_partial(
+        &self,
+        layout: Layout,
+    ) -> Result<(NonNull<u8>, usize), BAllocatorError> {
+        let _masked = self.mask_interrupts();
+        let alloc = self.alloc.get().expect(ALLOCATOR_UNINITIALIZED);
+        return self.count_alloc(self.record(alloc.allocate_partial(layout)));
+    }
+
+    /// Deallocates, moving `next` back over the block if it was the most
+    /// recent allocation so its space can be reused straight away.
+    ///
+    /// # Safety
+    pub unsafe fn try_deallocate_lifo(
+        &self,
+        ptr: NonNull<u8>,
+        layout: Layout,
+    ) -> Result<(), BAllocatorError> {
+        unsafe { self.try_deallocate(ptr, layout)? };
+        if layout.size() == 0 {
+            return Ok(());
         }
+        // Inside the heap, or the free would have failed.
+        let addr = ptr.as_ptr() as usize;
+        let alloc = self.alloc.get().expect(ALLOCATOR_UNINITIALIZED);
+        alloc.rewind(addr, addr + layout.size());
+        return Ok(());
+    }
+
+    /// Bytes between the start of the heap and `next`, including alignment
+    /// padding.
+    pub fn used(&self) -> usize {
+        let alloc = self.alloc.get().expect(ALLOCATOR_UNINITIALIZED);
+        return alloc.next() - alloc.start;
+    }
+
+    /// Sum of the sizes requested by live allocations. `used() -
+    /// used_bytes_exact()` is the space lost to alignment padding.
+    pub fn used_bytes_exact(&self) -> usize {
+        let alloc = self.alloc.get().expect(ALLOCATOR_UNINITIALIZED);
+        return alloc.live_bytes.load(Ordering::SeqCst);
+    }
+
+    /// Largest single allocation the heap could ever hold, its whole size.
+    pub fn max_alloc_size(&self) -> usize {
+        let alloc = self.alloc.get().expect(ALLOCATOR_UNINITIALIZED);
+        return alloc.end - alloc.start;
+    }
+}
+
+#[cfg(test)]
+impl Alloc<OnceCell<LocklessBump>> {
+    pub(crate) fn debug_next(&self) -> usize {
+        let alloc = self.alloc.get().expect(ALLOCATOR_UNINITIALIZED);
+        return alloc.next();
     }
 }
 
@@ -133,19 +351,32 @@ impl AllocInit for OnceCell<LocklessBump> {
             return bump;
         });
     }
+
+    fn is_initialized(&self) -> bool {
+        return self.get().is_some();
+    }
 }
 
 impl AllocState for OnceCell<LocklessBump> {
     fn remaining(&self) -> usize {
         let alloc = self.get().expect(ALLOCATOR_UNINITIALIZED);
 
-        return alloc
-            .end
-            .checked_sub(alloc.next.load(Ordering::SeqCst))
-            .unwrap_or_default();
+        let next = alloc.next();
+        // `claim` only packs offsets of blocks that fit in the heap.
+        debug_assert!(next <= alloc.end, "Lockless bump next past heap end");
+        return alloc.end - next;
     }
     fn allocations(&self) -> usize {
         let alloc = self.get().expect(ALLOCATOR_UNINITIALIZED);
-        return alloc.allocations.load(Ordering::SeqCst);
+        return alloc.allocations();
+    }
+}
+
+impl AllocBounds for OnceCell<LocklessBump> {
+    fn contains(&self, ptr: NonNull<u8>) -> bool {
+        return match self.get() {
+            Some(alloc) => (alloc.start..alloc.end).contains(&(ptr.as_ptr() as usize)),
+            None => false,
+        };
     }
 }
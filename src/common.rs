@@ -1,23 +1,211 @@
 use core::{
     alloc::{GlobalAlloc, Layout, LayoutError},
     fmt::{Debug, Formatter, Result as FmtResult},
-    ptr::{NonNull, null_mut, write_bytes},
+    mem::MaybeUninit,
+    ptr::{NonNull, null_mut, without_provenance_mut, write_bytes},
+    sync::atomic::{AtomicUsize, Ordering},
 };
 
+#[cfg(any(
+    feature = "bump_alloc",
+    feature = "linked_list_alloc",
+    feature = "buddy_alloc"
+))]
+use core::ops::{Deref, DerefMut};
+
+use conquer_once::spin::OnceCell;
 #[cfg(debug_assertions)]
 use log::error;
+use spin::RwLock;
+
+use crate::lock::Mutex;
 
 pub const HEAP_START_NULL: &str = "Given heap start pointer is NULL";
 pub const HEAP_SIZE_ZERO: &str = "Heap cannot be 0 in size";
 pub const HEAP_END_OVERFLOWED: &str = "Heap end address overflowed";
 pub const ALLOCATOR_UNINITIALIZED: &str = "Allocator not initialized";
+#[cfg(any(
+    feature = "bump_alloc",
+    feature = "buddy_alloc",
+    feature = "tlsf_alloc"
+))]
 pub const OOM: &str = "Out of memory";
+pub const MISALIGNED: &str = "Allocated object not aligned to requested layout";
+#[cfg(any(
+    feature = "linked_list_alloc",
+    feature = "buddy_alloc",
+    feature = "tlsf_alloc"
+))]
+pub const WRITE_OUT_OF_BOUNDS: &str = "Metadata write outside the heap";
+pub const ALLOC_OUT_OF_BOUNDS: &str = "Allocated object outside the heap";
+pub const FREE_OUT_OF_BOUNDS: &str = "Freed object outside the heap";
+
+/// Times the `_bounded` methods of locked allocators try to take the lock
+/// before giving up with `WouldBlock`.
+pub const LOCK_RETRIES: usize = 1000;
+
+/// Pattern written over freed memory when the `fill_on_free` feature is enabled.
+pub const FREE_FILL: u8 = 0xDE;
+
+/// Number of live tagged allocations an `Alloc` can attribute at once with
+/// the `tagging` feature.
+#[cfg(feature = "tagging")]
+pub const TAG_CAPACITY: usize = 64;
+
+/// Heap offsets and counts in allocator metadata tables. With the
+/// `compact_meta` feature they are `u32`, halving the tables on 64-bit
+/// targets, and heaps must be at most 4 GiB.
+#[cfg(all(
+    feature = "compact_meta",
+    any(feature = "linked_list_alloc", feature = "buddy_alloc")
+))]
+pub(crate) type MetaWord = u32;
+#[cfg(all(
+    not(feature = "compact_meta"),
+    any(feature = "linked_list_alloc", feature = "buddy_alloc")
+))]
+pub(crate) type MetaWord = usize;
+
+// Without `compact_meta` the casts below are from `usize` to itself.
+
+/// Whether offsets into a heap of `size` bytes fit a `MetaWord`.
+#[cfg(any(feature = "linked_list_alloc", feature = "buddy_alloc"))]
+#[allow(clippy::unnecessary_cast)]
+pub(crate) const fn fits_meta_word(size: usize) -> bool {
+    return size > 0 && size - 1 <= MetaWord::MAX as usize;
+}
+
+/// `value` as a `MetaWord`, which the heap size checks in `init` guarantee
+/// for offsets and counts.
+#[cfg(any(
+    feature = "linked_list_alloc",
+    all(feature = "buddy_alloc", feature = "buddy_track_live")
+))]
+#[allow(clippy::useless_conversion, clippy::unnecessary_fallible_conversions)]
+pub(crate) fn meta_word(value: usize) -> MetaWord {
+    return MetaWord::try_from(value).expect("Value too large for compact_meta");
+}
+
+#[cfg(any(feature = "linked_list_alloc", feature = "buddy_alloc"))]
+#[allow(clippy::unnecessary_cast)]
+pub(crate) const fn from_meta_word(value: MetaWord) -> usize {
+    return value as usize;
+}
+
+/// `assert!` in debug builds and, with the `checked` feature, in release
+/// builds too. Otherwise compiled out like `debug_assert!`.
+macro_rules! check {
+    ($($arg:tt)*) => {
+        if cfg!(any(debug_assertions, feature = "checked")) {
+            assert!($($arg)*);
+        }
+    };
+}
+
+fn within(addr: usize, size: usize, start: usize, end: usize) -> bool {
+    return addr >= start
+        && addr
+            .checked_add(size)
+            .is_some_and(|addr_end| addr_end <= end);
+}
+
+/// Checks allocator metadata about to be written at `[addr, addr + size)`
+/// stays inside the heap `[start, end)`.
+#[cfg(any(
+    feature = "linked_list_alloc",
+    feature = "buddy_alloc",
+    feature = "tlsf_alloc"
+))]
+#[track_caller]
+pub(crate) fn check_write(addr: usize, size: usize, start: usize, end: usize) {
+    check!(
+        within(addr, size, start, end),
+        "{WRITE_OUT_OF_BOUNDS}: {addr:#X}, size: {size}, heap: {start:#X}..{end:#X}"
+    );
+}
+
+/// Checks a block about to be handed out for `layout` lies inside the heap
+/// and is aligned.
+#[track_caller]
+pub(crate) fn check_alloc(addr: usize, layout: Layout, start: usize, end: usize) {
+    check!(
+        within(addr, layout.size(), start, end),
+        "{ALLOC_OUT_OF_BOUNDS}: {addr:#X}, layout: {layout:?}, heap: {start:#X}..{end:#X}"
+    );
+    check!(
+        addr.is_multiple_of(layout.align()),
+        "{MISALIGNED}: {addr:#X}, layout: {layout:?}"
+    );
+}
+
+/// Checks a block being freed lies inside the heap. The pointer comes from
+/// the caller, so unlike the checks above this fails with `InvalidFree`
+/// rather than panicking, and in every build.
+pub(crate) fn check_free(
+    addr: usize,
+    size: usize,
+    start: usize,
+    end: usize,
+) -> Result<(), BAllocatorError> {
+    if within(addr, size, start, end) {
+        return Ok(());
+    }
+    #[cfg(debug_assertions)]
+    error!("{FREE_OUT_OF_BOUNDS}: {addr:#X}, size: {size}, heap: {start:#X}..{end:#X}");
+    return Err(BAllocatorError::InvalidFree);
+}
+
+/// Non-null pointer aligned to `layout`, handed out for zero sized
+/// allocations so they use no heap memory, as std does.
+pub(crate) fn dangling(layout: Layout) -> NonNull<u8> {
+    return unsafe { NonNull::new_unchecked(without_provenance_mut(layout.align())) };
+}
 
 pub fn align_up(addr: usize, align: usize) -> usize {
+    debug_assert!(
+        align.is_power_of_two(),
+        "align_up: {align} not a power of two"
+    );
+    // Most requests share an alignment the bump pointer already satisfies.
+    if addr & (align - 1) == 0 {
+        return addr;
+    }
     let offset = (addr as *const u8).align_offset(align);
     addr + offset
 }
 
+/// Overwrites a freed region with `FREE_FILL` so reads through dangling
+/// pointers see garbage rather than stale data. Allocators must call this
+/// before writing their own free list metadata into the region.
+#[cfg(feature = "fill_on_free")]
+pub(crate) unsafe fn fill_freed(ptr: NonNull<u8>, size: usize) {
+    unsafe { write_bytes(ptr.as_ptr(), FREE_FILL, size) };
+}
+
+/// Touches one byte in every `page_size` bytes of `[start, end)` so the
+/// backing pages are faulted in now rather than on first use. Each byte is
+/// written back with the value it already holds, leaving free list nodes and
+/// live data stored in the range as they were. Bytes are moved as
+/// `MaybeUninit` as most of the heap has never been written.
+#[cfg(any(
+    feature = "bump_alloc",
+    feature = "linked_list_alloc",
+    feature = "buddy_alloc"
+))]
+pub(crate) unsafe fn prefault(start: usize, end: usize, page_size: usize) {
+    assert!(page_size > 0, "Prefault page size cannot be 0");
+    let mut addr = start;
+    while addr < end {
+        let byte = addr as *mut MaybeUninit<u8>;
+        unsafe { byte.write_volatile(byte.read_volatile()) };
+        addr = match addr.checked_add(page_size) {
+            Some(next) => next,
+            None => break,
+        };
+    }
+}
+
+#[derive(Clone)]
 pub enum BAllocatorError {
     Oom(Option<Layout>),
     Overflowed,
@@ -25,6 +213,15 @@ pub enum BAllocatorError {
     Alignment(Layout),
     Layout(LayoutError),
     Null,
+    Unmergeable,
+    InvalidFree,
+    TooLarge(Layout),
+    Uninitialized,
+    WouldBlock,
+    Frozen,
+    GuardOverwritten,
+    Unsupported,
+    InterruptGuardSet,
 }
 
 impl Debug for BAllocatorError {
@@ -40,10 +237,70 @@ impl Debug for BAllocatorError {
             }
             BAllocatorError::Layout(e) => write!(f, "Layout Error: {e:?}"),
             BAllocatorError::Null => write!(f, "NULL pointer"),
+            BAllocatorError::Unmergeable => {
+                write!(f, "Allocators are not adjacent or still in use")
+            }
+            BAllocatorError::InvalidFree => {
+                write!(f, "Freed pointer was not allocated by this allocator")
+            }
+            BAllocatorError::TooLarge(layout) => {
+                write!(
+                    f,
+                    "Larger than the largest block of the allocator: {layout:?}"
+                )
+            }
+            BAllocatorError::Uninitialized => write!(f, "{}", ALLOCATOR_UNINITIALIZED),
+            BAllocatorError::WouldBlock => write!(f, "Allocator lock is held elsewhere"),
+            BAllocatorError::Frozen => write!(f, "Allocator is frozen"),
+            BAllocatorError::GuardOverwritten => {
+                write!(f, "Guard bytes past the end of the block were overwritten")
+            }
+            BAllocatorError::Unsupported => write!(f, "Not supported by this allocator"),
+            BAllocatorError::InterruptGuardSet => {
+                write!(f, "An interrupt guard is already set")
+            }
         }
     }
 }
 
+/// First inconsistency a `self_test` found in an allocator's bookkeeping.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum IntegrityError {
+    /// The free region at `addr` reaches outside the heap.
+    OutOfBounds { addr: usize, size: usize },
+    /// The free regions at `first` and `second` overlap.
+    Overlap { first: usize, second: usize },
+    /// The free region at `addr` is too small to hold its own free list node.
+    Undersized { addr: usize, size: usize },
+    /// The bump pointer is outside `start..=end`.
+    NextOutOfRange {
+        start: usize,
+        next: usize,
+        end: usize,
+    },
+    /// The metadata no longer matches the checksum taken when frozen.
+    ChecksumMismatch { expected: usize, found: usize },
+}
+
+impl BAllocatorError {
+    pub fn is_oom(&self) -> bool {
+        return matches!(self, BAllocatorError::Oom(_));
+    }
+}
+
+impl From<LayoutError> for BAllocatorError {
+    fn from(e: LayoutError) -> Self {
+        return BAllocatorError::Layout(e);
+    }
+}
+
+#[cfg(feature = "allocator_api")]
+impl From<BAllocatorError> for core::alloc::AllocError {
+    fn from(_: BAllocatorError) -> Self {
+        return core::alloc::AllocError;
+    }
+}
+
 /// # Safety
 pub unsafe trait BAllocator {
     /// # Safety
@@ -56,6 +313,70 @@ pub unsafe trait BAllocator {
         layout: Layout,
     ) -> Result<(), BAllocatorError>;
 
+    /// Whether `try_allocate` would currently succeed for `layout`, without
+    /// changing any allocator state. Allocators that cannot tell without
+    /// allocating keep the default, which answers `true` and leaves it to
+    /// `try_allocate` to fail, so `reserve` never turns them away.
+    fn can_allocate(&self, _layout: Layout) -> bool {
+        return true;
+    }
+
+    /// Like `try_allocate`, also returning how many bytes from the pointer
+    /// the caller may use, which can be more than `layout.size()` when the
+    /// allocator rounds the block up. The block is still freed with
+    /// `layout`.
+    ///
+    /// # Safety
+    unsafe fn try_allocate_with_usable(
+        &self,
+        layout: Layout,
+    ) -> Result<(NonNull<u8>, usize), BAllocatorError> {
+        let ptr = unsafe { self.try_allocate(layout)? };
+        return Ok((ptr, layout.size()));
+    }
+
+    /// Alignment every block from `try_allocate` has whatever layout was
+    /// asked for, so callers need not request it themselves. Zero sized
+    /// allocations are only aligned to their layout.
+    fn base_alignment(&self) -> usize {
+        return 1;
+    }
+
+    /// Fails early with `Oom` if `bytes` could not currently be allocated in
+    /// one piece, without allocating anything.
+    fn reserve(&self, bytes: usize) -> Result<(), BAllocatorError> {
+        let layout = Layout::from_size_align(bytes, 1)?;
+        if !self.can_allocate(layout) {
+            return Err(BAllocatorError::Oom(Some(layout)));
+        }
+        return Ok(());
+    }
+
+    /// Resizes the block at `ptr` to `new_size`, keeping its alignment and
+    /// contents. By default always moves the block; allocators that can
+    /// resize in place override this.
+    ///
+    /// # Safety
+    /// `ptr` must have been allocated by this allocator with `layout`.
+    unsafe fn try_reallocate(
+        &self,
+        ptr: NonNull<u8>,
+        layout: Layout,
+        new_size: usize,
+    ) -> Result<NonNull<u8>, BAllocatorError> {
+        unsafe {
+            let new_layout = Layout::from_size_align(new_size, layout.align())?;
+            let new_ptr = self.try_allocate(new_layout)?;
+            new_ptr.copy_from_nonoverlapping(ptr, layout.size().min(new_size));
+            self.try_deallocate(ptr, layout)?;
+            return Ok(new_ptr);
+        }
+    }
+
+    /// Zeroes exactly `layout.size()` bytes from the returned pointer, after
+    /// the allocator is done writing its own metadata, so free list nodes
+    /// that were inside the block never show through.
+    ///
     /// # Safety
     unsafe fn try_allocate_zeroed(&self, layout: Layout) -> Result<NonNull<u8>, BAllocatorError> {
         let size = layout.size();
@@ -66,6 +387,54 @@ pub unsafe trait BAllocator {
         return Ok(ptr);
     }
 
+    /// Like `try_reallocate`, zeroing `[layout.size(), new_size)` when the
+    /// block grows. The old contents are kept and nothing is zeroed on
+    /// shrink.
+    ///
+    /// # Safety
+    /// `ptr` must have been allocated by this allocator with `layout`.
+    unsafe fn try_reallocate_zeroed(
+        &self,
+        ptr: NonNull<u8>,
+        layout: Layout,
+        new_size: usize,
+    ) -> Result<NonNull<u8>, BAllocatorError> {
+        let old_size = layout.size();
+        let new_ptr = unsafe { self.try_reallocate(ptr, layout, new_size)? };
+
+        if new_size > old_size {
+            unsafe { write_bytes(new_ptr.as_ptr().add(old_size), 0, new_size - old_size) };
+        }
+        return Ok(new_ptr);
+    }
+
+    /// Allocates `rows` rows of `cols` elements each, every row starting at a
+    /// multiple of `row_align`, returning the pointer and the row stride in
+    /// bytes. Free with `Layout::from_size_align(rows * stride, align)`
+    /// where `align` is the larger of `row_align` and `elem.align()`.
+    ///
+    /// # Safety
+    unsafe fn try_allocate_2d(
+        &self,
+        rows: usize,
+        cols: usize,
+        elem: Layout,
+        row_align: usize,
+    ) -> Result<(NonNull<u8>, usize), BAllocatorError> {
+        let row = elem
+            .size()
+            .checked_mul(cols)
+            .ok_or(BAllocatorError::Overflowed)?;
+        let row = Layout::from_size_align(row, row_align.max(elem.align()))?.pad_to_align();
+        let stride = row.size();
+        let size = stride
+            .checked_mul(rows)
+            .ok_or(BAllocatorError::Overflowed)?;
+
+        let ptr = unsafe { self.try_allocate(Layout::from_size_align(size, row.align())?)? };
+        return Ok((ptr, stride));
+    }
+
     /// # Safety
     unsafe fn try_deallocate_zeroed(
         &self,
@@ -80,15 +449,272 @@ pub unsafe trait BAllocator {
     }
 }
 
+/// Typed helpers over `BAllocator`, kept out of it so `&dyn BAllocator`
+/// stays usable.
+pub trait BAllocatorExt: BAllocator {
+    /// Allocates space for a `T`, left uninitialized.
+    ///
+    /// # Safety
+    unsafe fn try_allocate_typed<T>(&self) -> Result<NonNull<MaybeUninit<T>>, BAllocatorError> {
+        unsafe {
+            return Ok(self.try_allocate(Layout::new::<T>())?.cast());
+        }
+    }
+
+    /// Frees a `T` from `try_allocate_typed` without dropping it.
+    ///
+    /// # Safety
+    unsafe fn try_deallocate_typed<T>(
+        &self,
+        ptr: NonNull<MaybeUninit<T>>,
+    ) -> Result<(), BAllocatorError> {
+        unsafe {
+            return self.try_deallocate(ptr.cast(), Layout::new::<T>());
+        }
+    }
+
+    /// Moves the `T` at `src`, which may belong to another allocator, into a
+    /// new allocation from this one. `src` is left as it was, for the caller
+    /// to free or reset along with the rest of its allocator.
+    ///
+    /// # Safety
+    /// `src` must point to an initialized `T`, which must not be used again
+    /// unless `T` is `Copy`.
+    unsafe fn migrate<T>(&self, src: NonNull<T>) -> Result<NonNull<T>, BAllocatorError> {
+        unsafe {
+            let dst = self.try_allocate_typed::<T>()?.cast::<T>();
+            dst.copy_from_nonoverlapping(src, 1);
+            return Ok(dst);
+        }
+    }
+}
+
+impl<A: BAllocator + ?Sized> BAllocatorExt for A {}
+
+/// Checked versions of the `GlobalAlloc` methods, for generic code holding
+/// any `GlobalAlloc`, including a `&dyn GlobalAlloc`.
+pub trait GlobalAllocExt: GlobalAlloc {
+    /// `realloc`, validating the new layout and turning null into `Oom`.
+    ///
+    /// # Safety
+    /// As for `GlobalAlloc::realloc`.
+    unsafe fn realloc_checked(
+        &self,
+        ptr: NonNull<u8>,
+        layout: Layout,
+        new_size: usize,
+    ) -> Result<NonNull<u8>, BAllocatorError> {
+        let new_layout = Layout::from_size_align(new_size, layout.align())?;
+        unsafe {
+            return NonNull::new(self.realloc(ptr.as_ptr(), layout, new_size))
+                .ok_or(BAllocatorError::Oom(Some(new_layout)));
+        }
+    }
+
+    /// `alloc_zeroed`, turning null into `Oom`.
+    ///
+    /// # Safety
+    /// As for `GlobalAlloc::alloc_zeroed`.
+    unsafe fn alloc_zeroed_checked(&self, layout: Layout) -> Result<NonNull<u8>, BAllocatorError> {
+        unsafe {
+            return NonNull::new(self.alloc_zeroed(layout))
+                .ok_or(BAllocatorError::Oom(Some(layout)));
+        }
+    }
+}
+
+impl<G: GlobalAlloc + ?Sized> GlobalAllocExt for G {}
+
+/// Non-locking allocation logic of an allocator, wrapped in a `Mutex` to
+/// provide `BAllocator`.
+///
+/// # Safety
+/// `Mutex<A>` and `RwLock<A>` implement the unsafe `BAllocator` by
+/// forwarding to these methods, so implementors take on its contract:
+/// blocks handed out must be valid for `layout`, not overlap any other live
+/// block and stay valid until freed.
+pub unsafe trait LockedInner {
+    /// # Safety
+    unsafe fn allocate(&mut self, layout: Layout) -> Result<NonNull<u8>, BAllocatorError>;
+
+    /// # Safety
+    unsafe fn deallocate(
+        &mut self,
+        ptr: NonNull<u8>,
+        layout: Layout,
+    ) -> Result<(), BAllocatorError>;
+
+    /// See `BAllocator::can_allocate`.
+    fn can_allocate(&self, _layout: Layout) -> bool {
+        return true;
+    }
+
+    /// See `BAllocator::base_alignment`.
+    fn base_alignment(&self) -> usize {
+        return 1;
+    }
+
+    /// See `BAllocator::try_allocate_with_usable`.
+    ///
+    /// # Safety
+    unsafe fn allocate_with_usable(
+        &mut self,
+        layout: Layout,
+    ) -> Result<(NonNull<u8>, usize), BAllocatorError> {
+        let ptr = unsafe { self.allocate(layout)? };
+        return Ok((ptr, layout.size()));
+    }
+
+    /// See `BAllocator::try_reallocate`.
+    ///
+    /// # Safety
+    unsafe fn reallocate(
+        &mut self,
+        ptr: NonNull<u8>,
+        layout: Layout,
+        new_size: usize,
+    ) -> Result<NonNull<u8>, BAllocatorError> {
+        unsafe {
+            let new_layout = Layout::from_size_align(new_size, layout.align())?;
+            let new_ptr = self.allocate(new_layout)?;
+            new_ptr.copy_from_nonoverlapping(ptr, layout.size().min(new_size));
+            self.deallocate(ptr, layout)?;
+            return Ok(new_ptr);
+        }
+    }
+}
+
+unsafe impl<A: LockedInner> BAllocator for Mutex<A> {
+    unsafe fn try_allocate(&self, layout: Layout) -> Result<NonNull<u8>, BAllocatorError> {
+        unsafe {
+            return self.lock().allocate(layout);
+        }
+    }
+
+    unsafe fn try_allocate_with_usable(
+        &self,
+        layout: Layout,
+    ) -> Result<(NonNull<u8>, usize), BAllocatorError> {
+        unsafe {
+            return self.lock().allocate_with_usable(layout);
+        }
+    }
+
+    unsafe fn try_deallocate(
+        &self,
+        ptr: NonNull<u8>,
+        layout: Layout,
+    ) -> Result<(), BAllocatorError> {
+        unsafe {
+            return self.lock().deallocate(ptr, layout);
+        }
+    }
+
+    fn can_allocate(&self, layout: Layout) -> bool {
+        return self.lock().can_allocate(layout);
+    }
+
+    fn base_alignment(&self) -> usize {
+        return self.lock().base_alignment();
+    }
+
+    unsafe fn try_reallocate(
+        &self,
+        ptr: NonNull<u8>,
+        layout: Layout,
+        new_size: usize,
+    ) -> Result<NonNull<u8>, BAllocatorError> {
+        unsafe {
+            return self.lock().reallocate(ptr, layout, new_size);
+        }
+    }
+}
+
+unsafe impl<A: LockedInner> BAllocator for RwLock<A> {
+    unsafe fn try_allocate(&self, layout: Layout) -> Result<NonNull<u8>, BAllocatorError> {
+        unsafe {
+            return self.write().allocate(layout);
+        }
+    }
+
+    unsafe fn try_allocate_with_usable(
+        &self,
+        layout: Layout,
+    ) -> Result<(NonNull<u8>, usize), BAllocatorError> {
+        unsafe {
+            return self.write().allocate_with_usable(layout);
+        }
+    }
+
+    unsafe fn try_deallocate(
+        &self,
+        ptr: NonNull<u8>,
+        layout: Layout,
+    ) -> Result<(), BAllocatorError> {
+        unsafe {
+            return self.write().deallocate(ptr, layout);
+        }
+    }
+
+    fn can_allocate(&self, layout: Layout) -> bool {
+        return self.read().can_allocate(layout);
+    }
+
+    fn base_alignment(&self) -> usize {
+        return self.read().base_alignment();
+    }
+
+    unsafe fn try_reallocate(
+        &self,
+        ptr: NonNull<u8>,
+        layout: Layout,
+        new_size: usize,
+    ) -> Result<NonNull<u8>, BAllocatorError> {
+        unsafe {
+            return self.write().reallocate(ptr, layout, new_size);
+        }
+    }
+}
+
 pub trait AllocInit {
     /// # Safety
     unsafe fn init(&self, start: usize, size: usize);
+
+    /// Resets the allocator to its state before `init`, so it can be
+    /// initialized again, possibly on another heap. Allocators whose heap is
+    /// set once keep the default, which fails with `Unsupported`.
+    ///
+    /// # Safety
+    /// Nothing may be left allocated, which is checked in debug builds.
+    unsafe fn deinit(&self) -> Result<(), BAllocatorError> {
+        return Err(BAllocatorError::Unsupported);
+    }
+
+    /// Whether `init` has run since creation or the last `deinit`.
+    /// Allocators that do not track it keep the default, which answers
+    /// `true` so callers do not initialize a heap already in use.
+    fn is_initialized(&self) -> bool {
+        return true;
+    }
 }
 
 impl<A: BAllocator + AllocInit> AllocInit for Alloc<A> {
     unsafe fn init(&self, start: usize, size: usize) {
+        let _masked = self.mask_interrupts();
         unsafe { self.alloc.init(start, size) };
     }
+
+    unsafe fn deinit(&self) -> Result<(), BAllocatorError> {
+        let _masked = self.mask_interrupts();
+        unsafe { self.alloc.deinit()? };
+        self.last_error.store(None);
+        return Ok(());
+    }
+
+    fn is_initialized(&self) -> bool {
+        let _masked = self.mask_interrupts();
+        return self.alloc.is_initialized();
+    }
 }
 
 pub trait AllocState {
@@ -96,25 +722,669 @@ pub trait AllocState {
     fn allocations(&self) -> usize;
 }
 
+impl<A: LockedInner + AllocState> AllocState for Mutex<A> {
+    fn remaining(&self) -> usize {
+        return self.lock().remaining();
+    }
+
+    fn allocations(&self) -> usize {
+        return self.lock().allocations();
+    }
+}
+
+impl<A: LockedInner + AllocState> AllocState for RwLock<A> {
+    fn remaining(&self) -> usize {
+        return self.read().remaining();
+    }
+
+    fn allocations(&self) -> usize {
+        return self.read().allocations();
+    }
+}
+
+pub trait AllocBounds {
+    fn contains(&self, ptr: NonNull<u8>) -> bool;
+}
+
+impl<A: LockedInner + AllocBounds> AllocBounds for Mutex<A> {
+    fn contains(&self, ptr: NonNull<u8>) -> bool {
+        return self.lock().contains(ptr);
+    }
+}
+
+impl<A: LockedInner + AllocBounds> AllocBounds for RwLock<A> {
+    fn contains(&self, ptr: NonNull<u8>) -> bool {
+        return self.read().contains(ptr);
+    }
+}
+
+impl<A: BAllocator + AllocBounds> AllocBounds for Alloc<A> {
+    fn contains(&self, ptr: NonNull<u8>) -> bool {
+        let _masked = self.mask_interrupts();
+        return self.alloc.contains(ptr);
+    }
+}
+
 impl<A: BAllocator + AllocState> AllocState for Alloc<A> {
     fn remaining(&self) -> usize {
+        let _masked = self.mask_interrupts();
         return self.alloc.remaining();
     }
 
     fn allocations(&self) -> usize {
+        let _masked = self.mask_interrupts();
         return self.alloc.allocations();
     }
 }
 
-#[derive(Clone)]
+/*
+ * Handed out blocks belong to the caller, so what an allocator has to
+ * remember about them is kept in a fixed table beside it rather than in the
+ * blocks themselves. Lookups are linear, the tables are small.
+ */
+#[cfg(any(
+    feature = "bump_alloc",
+    feature = "linked_list_alloc",
+    feature = "buddy_alloc",
+    feature = "tagging"
+))]
+#[derive(Debug, Clone, Copy)]
+pub(crate) struct BlockMap<K, V, const N: usize> {
+    entries: [(K, V); N],
+    len: usize,
+}
+
+#[cfg(any(
+    feature = "bump_alloc",
+    feature = "linked_list_alloc",
+    feature = "buddy_alloc",
+    feature = "tagging"
+))]
+impl<K: Copy + PartialEq, V: Copy, const N: usize> BlockMap<K, V, N> {
+    /// Empty map, with `unused` filling the entries not yet taken.
+    pub(crate) const fn new(unused: (K, V)) -> Self {
+        return BlockMap {
+            entries: [unused; N],
+            len: 0,
+        };
+    }
+
+    pub(crate) fn is_full(&self) -> bool {
+        return self.len == N;
+    }
+
+    pub(crate) fn entries(&self) -> &[(K, V)] {
+        return &self.entries[..self.len];
+    }
+
+    #[cfg(feature = "buddy_alloc")]
+    pub(crate) fn entries_mut(&mut self) -> &mut [(K, V)] {
+        return &mut self.entries[..self.len];
+    }
+
+    /// Adds `value` for `key`, which must not be in the map yet. Returns
+    /// `false` without adding it when full.
+    pub(crate) fn insert(&mut self, key: K, value: V) -> bool {
+        if self.is_full() {
+            return false;
+        }
+        self.entries[self.len] = (key, value);
+        self.len += 1;
+        return true;
+    }
+
+    #[cfg(feature = "linked_list_alloc")]
+    pub(crate) fn get(&self, key: K) -> Option<V> {
+        return self
+            .entries()
+            .iter()
+            .find(|&&(k, _)| k == key)
+            .map(|&(_, value)| value);
+    }
+
+    /// Removes the entry inserted last, for a map only ever used as a stack.
+    #[cfg(feature = "bump_alloc")]
+    pub(crate) fn pop(&mut self) -> Option<(K, V)> {
+        self.len = self.len.checked_sub(1)?;
+        return Some(self.entries[self.len]);
+    }
+
+    /// Removes `key`, moving the last entry into its place.
+    #[cfg(any(
+        feature = "linked_list_alloc",
+        feature = "buddy_alloc",
+        feature = "tagging"
+    ))]
+    pub(crate) fn remove(&mut self, key: K) -> Option<V> {
+        let i = self.entries().iter().position(|&(k, _)| k == key)?;
+        let value = self.entries[i].1;
+        self.len -= 1;
+        self.entries[i] = self.entries[self.len];
+        return Some(value);
+    }
+}
+
+/// Masks interrupts around an allocator's critical section, so an interrupt
+/// handler that allocates cannot spin forever on a lock held by the code it
+/// interrupted. On Cortex-M `disable` would save PRIMASK and set it, and
+/// `restore` would write the saved value back.
+pub trait InterruptGuard: Sync {
+    /// Disables interrupts, returning the state for `restore` to put back.
+    fn disable(&self) -> usize;
+
+    fn restore(&self, state: usize);
+}
+
+/// Interrupts masked by an `InterruptGuard` until dropped.
+pub(crate) struct Masked<'a> {
+    guard: Option<&'a dyn InterruptGuard>,
+    state: usize,
+}
+
+impl Drop for Masked<'_> {
+    fn drop(&mut self) {
+        if let Some(guard) = self.guard {
+            guard.restore(self.state);
+        }
+    }
+}
+
+/// Lock guard taken with interrupts masked. Fields drop in order, so the
+/// lock is released before interrupts are restored.
+#[cfg(any(
+    feature = "bump_alloc",
+    feature = "linked_list_alloc",
+    feature = "buddy_alloc"
+))]
+pub(crate) struct MaskedGuard<'a, G> {
+    guard: G,
+    _masked: Masked<'a>,
+}
+
+#[cfg(any(
+    feature = "bump_alloc",
+    feature = "linked_list_alloc",
+    feature = "buddy_alloc"
+))]
+impl<G: Deref> Deref for MaskedGuard<'_, G> {
+    type Target = G::Target;
+
+    fn deref(&self) -> &G::Target {
+        return &self.guard;
+    }
+}
+
+#[cfg(any(
+    feature = "bump_alloc",
+    feature = "linked_list_alloc",
+    feature = "buddy_alloc"
+))]
+impl<G: DerefMut> DerefMut for MaskedGuard<'_, G> {
+    fn deref_mut(&mut self) -> &mut G::Target {
+        return &mut self.guard;
+    }
+}
+
+/// Last error of an `Alloc`, kept in atomics so recording one from an
+/// interrupt handler cannot spin on a lock held by the code it interrupted.
+/// Failures racing each other may leave the code of one with the layout of
+/// another.
+struct ErrorSlot {
+    /// 0 for no error, otherwise one more than the variant's index.
+    code: AtomicUsize,
+    size: AtomicUsize,
+    /// 0 when the error carries no layout.
+    align: AtomicUsize,
+}
+
+impl ErrorSlot {
+    const fn new() -> ErrorSlot {
+        ErrorSlot {
+            code: AtomicUsize::new(0),
+            size: AtomicUsize::new(0),
+            align: AtomicUsize::new(0),
+        }
+    }
+
+    fn store(&self, error: Option<&BAllocatorError>) {
+        let (code, layout) = match error {
+            None => (0, None),
+            Some(BAllocatorError::Oom(layout)) => (1, *layout),
+            Some(BAllocatorError::Overflowed) => (2, None),
+            Some(BAllocatorError::Underflowed) => (3, None),
+            Some(BAllocatorError::Alignment(layout)) => (4, Some(*layout)),
+            Some(BAllocatorError::Layout(_)) => (5, None),
+            Some(BAllocatorError::Null) => (6, None),
+            Some(BAllocatorError::Unmergeable) => (7, None),
+            Some(BAllocatorError::InvalidFree) => (8, None),
+            Some(BAllocatorError::TooLarge(layout)) => (9, Some(*layout)),
+            Some(BAllocatorError::Uninitialized) => (10, None),
+            Some(BAllocatorError::WouldBlock) => (11, None),
+            Some(BAllocatorError::Frozen) => (12, None),
+            Some(BAllocatorError::GuardOverwritten) => (13, None),
+            Some(BAllocatorError::Unsupported) => (14, None),
+            Some(BAllocatorError::InterruptGuardSet) => (15, None),
+        };
+        self.size
+            .store(layout.map_or(0, |layout| layout.size()), Ordering::Relaxed);
+        self.align
+            .store(layout.map_or(0, |layout| layout.align()), Ordering::Relaxed);
+        self.code.store(code, Ordering::Release);
+    }
+
+    fn load(&self) -> Option<BAllocatorError> {
+        let code = self.code.load(Ordering::Acquire);
+        let layout = Layout::from_size_align(
+            self.size.load(Ordering::Relaxed),
+            self.align.load(Ordering::Relaxed),
+        )
+        .ok();
+        // Only a racing failure without a layout leaves none here.
+        let some_layout = || layout.unwrap_or(Layout::new::<u8>());
+
+        return Some(match code {
+            0 => return None,
+            1 => BAllocatorError::Oom(layout),
+            2 => BAllocatorError::Overflowed,
+            3 => BAllocatorError::Underflowed,
+            4 => BAllocatorError::Alignment(some_layout()),
+            // `LayoutError` carries nothing, any invalid layout makes one.
+            5 => BAllocatorError::Layout(Layout::from_size_align(0, 0).unwrap_err()),
+            6 => BAllocatorError::Null,
+            7 => BAllocatorError::Unmergeable,
+            8 => BAllocatorError::InvalidFree,
+            9 => BAllocatorError::TooLarge(some_layout()),
+            10 => BAllocatorError::Uninitialized,
+            11 => BAllocatorError::WouldBlock,
+            12 => BAllocatorError::Frozen,
+            13 => BAllocatorError::GuardOverwritten,
+            14 => BAllocatorError::Unsupported,
+            _ => BAllocatorError::InterruptGuardSet,
+        });
+    }
+}
+
+/// Lifetime counts of calls through an `Alloc`, whatever their outcome,
+/// unlike the `allocations` gauge of live blocks.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct AllocMetrics {
+    pub total_alloc_calls: usize,
+    pub total_alloc_failures: usize,
+    pub total_dealloc_calls: usize,
+}
+
 pub struct Alloc<A: BAllocator> {
     pub(crate) alloc: A,
+    last_error: ErrorSlot,
+    alloc_calls: AtomicUsize,
+    alloc_failures: AtomicUsize,
+    dealloc_calls: AtomicUsize,
+    interrupt_guard: OnceCell<&'static dyn InterruptGuard>,
+    /// Size and tag of each live tagged block, by address.
+    #[cfg(feature = "tagging")]
+    tags: Mutex<BlockMap<usize, (usize, u16), TAG_CAPACITY>>,
+}
+
+impl<A: BAllocator + Clone> Clone for Alloc<A> {
+    fn clone(&self) -> Self {
+        Alloc {
+            alloc: self.alloc.clone(),
+            last_error: {
+                let last_error = ErrorSlot::new();
+                last_error.store(self.last_error().as_ref());
+                last_error
+            },
+            alloc_calls: AtomicUsize::new(self.alloc_calls.load(Ordering::Relaxed)),
+            alloc_failures: AtomicUsize::new(self.alloc_failures.load(Ordering::Relaxed)),
+            dealloc_calls: AtomicUsize::new(self.dealloc_calls.load(Ordering::Relaxed)),
+            interrupt_guard: match self.interrupt_guard.get() {
+                Some(&guard) => OnceCell::new(guard),
+                None => OnceCell::uninit(),
+            },
+            #[cfg(feature = "tagging")]
+            tags: Mutex::new({
+                let _masked = self.mask_interrupts();
+                *self.tags.lock()
+            }),
+        }
+    }
 }
 
+impl<A: BAllocator> Alloc<A> {
+    pub(crate) const fn wrap(alloc: A) -> Self {
+        Alloc {
+            alloc,
+            last_error: ErrorSlot::new(),
+            alloc_calls: AtomicUsize::new(0),
+            alloc_failures: AtomicUsize::new(0),
+            dealloc_calls: AtomicUsize::new(0),
+            interrupt_guard: OnceCell::uninit(),
+            #[cfg(feature = "tagging")]
+            tags: Mutex::new(BlockMap::new((0, (0, 0)))),
+        }
+    }
+
+    /// Calls `guard` to mask interrupts whenever this allocator's lock is
+    /// held. Without one interrupts are left alone. Fails with
+    /// `InterruptGuardSet` if a guard was already set.
+    pub fn set_interrupt_guard(
+        &self,
+        guard: &'static dyn InterruptGuard,
+    ) -> Result<(), BAllocatorError> {
+        return self
+            .interrupt_guard
+            .try_init_once(|| guard)
+            .map_err(|_| BAllocatorError::InterruptGuardSet);
+    }
+
+    pub(crate) fn mask_interrupts(&self) -> Masked<'_> {
+        let guard = self.interrupt_guard.get().copied();
+        let state = guard.map_or(0, |guard| guard.disable());
+        return Masked { guard, state };
+    }
+
+    /// Masks interrupts with whichever guard of the two is set, as masking
+    /// is not specific to one allocator.
+    #[cfg(any(feature = "bump_alloc", feature = "buddy_alloc"))]
+    fn mask_interrupts_pair<'a>(&'a self, other: &'a Self) -> Masked<'a> {
+        if self.interrupt_guard.get().is_some() {
+            return self.mask_interrupts();
+        }
+        return other.mask_interrupts();
+    }
+
+    /// Like `try_allocate`, also attributing the block to `tag` in
+    /// `usage_by_tag` until it is freed. Fails with `Oom` without allocating
+    /// once `TAG_CAPACITY` tagged blocks are live.
+    ///
+    /// # Safety
+    /// As for `try_allocate`.
+    #[cfg(feature = "tagging")]
+    pub unsafe fn try_allocate_tagged(
+        &self,
+        layout: Layout,
+        tag: u16,
+    ) -> Result<NonNull<u8>, BAllocatorError> {
+        if layout.size() == 0 {
+            return unsafe { self.try_allocate(layout) };
+        }
+        let _masked = self.mask_interrupts();
+        let mut tags = self.tags.lock();
+        if tags.is_full() {
+            #[cfg(debug_assertions)]
+            error!("Tag table full, not allocating {layout:?} for tag {tag}");
+            return self.count_alloc(self.record(Err(BAllocatorError::Oom(Some(layout)))));
+        }
+        let ptr = self.count_alloc(self.record(unsafe { self.alloc.try_allocate(layout) }))?;
+        tags.insert(ptr.as_ptr() as usize, (layout.size(), tag));
+        return Ok(ptr);
+    }
+
+    /// Live bytes of each tag, sorted by tag. Entries past the number of
+    /// distinct tags are `(0, 0)`, and once `N` tags are found the rest are
+    /// left out.
+    #[cfg(feature = "tagging")]
+    pub fn usage_by_tag<const N: usize>(&self) -> [(u16, usize); N] {
+        let _masked = self.mask_interrupts();
+        let tags = self.tags.lock();
+        let mut usage = [(0, 0); N];
+        let mut len = 0;
+        for &(_, (size, tag)) in tags.entries() {
+            match usage[..len].iter_mut().find(|(t, _)| *t == tag) {
+                Some((_, bytes)) => *bytes += size,
+                None if len < N => {
+                    usage[len] = (tag, size);
+                    len += 1;
+                }
+                None => {}
+            }
+        }
+        usage[..len].sort_unstable_by_key(|&(tag, _)| tag);
+        return usage;
+    }
+
+    /// The most recent error from allocating or freeing through this
+    /// allocator, for finding out why `GlobalAlloc::alloc` returned null.
+    pub fn last_error(&self) -> Option<BAllocatorError> {
+        return self.last_error.load();
+    }
+
+    /// Counts of `try_allocate`, `try_allocate_with_usable` and
+    /// `try_deallocate` calls since creation, zero sized ones included.
+    /// Reallocations are only counted when they fall back to an allocate
+    /// and a free, for zero sized blocks.
+    pub fn metrics(&self) -> AllocMetrics {
+        return AllocMetrics {
+            total_alloc_calls: self.alloc_calls.load(Ordering::Relaxed),
+            total_alloc_failures: self.alloc_failures.load(Ordering::Relaxed),
+            total_dealloc_calls: self.dealloc_calls.load(Ordering::Relaxed),
+        };
+    }
+
+    pub(crate) fn count_alloc<T>(
+        &self,
+        result: Result<T, BAllocatorError>,
+    ) -> Result<T, BAllocatorError> {
+        self.alloc_calls.fetch_add(1, Ordering::Relaxed);
+        if result.is_err() {
+            self.alloc_failures.fetch_add(1, Ordering::Relaxed);
+        }
+        return result;
+    }
+
+    pub(crate) fn record<T>(
+        &self,
+        result: Result<T, BAllocatorError>,
+    ) -> Result<T, BAllocatorError> {
+        if let Err(e) = &result {
+            self.last_error.store(Some(e));
+        }
+        return result;
+    }
+
+    /// Counts and records the free of `ptr`, dropping its tag once freed.
+    /// Called with interrupts masked, for the tag table lock.
+    pub(crate) fn count_dealloc(
+        &self,
+        _ptr: NonNull<u8>,
+        result: Result<(), BAllocatorError>,
+    ) -> Result<(), BAllocatorError> {
+        self.dealloc_calls.fetch_add(1, Ordering::Relaxed);
+        self.record(result)?;
+        #[cfg(feature = "tagging")]
+        self.tags.lock().remove(_ptr.as_ptr() as usize);
+        return Ok(());
+    }
+}
+
+/// Allocator whose stat queries only take a read lock, so they do not
+/// contend with each other, only with allocations.
+pub type RwLocked<A> = Alloc<RwLock<A>>;
+
+fn lock_bounded<G>(mut try_lock: impl FnMut() -> Option<G>) -> Result<G, BAllocatorError> {
+    for _ in 0..LOCK_RETRIES {
+        if let Some(guard) = try_lock() {
+            return Ok(guard);
+        }
+        core::hint::spin_loop();
+    }
+    #[cfg(debug_assertions)]
+    error!("Gave up on the allocator lock after {LOCK_RETRIES} tries");
+    return Err(BAllocatorError::WouldBlock);
+}
+
+impl<A: LockedInner> Alloc<Mutex<A>> {
+    /// Takes the lock with interrupts masked, as every method of a locked
+    /// `Alloc` must.
+    #[cfg(any(
+        feature = "bump_alloc",
+        feature = "linked_list_alloc",
+        feature = "buddy_alloc"
+    ))]
+    pub(crate) fn lock(&self) -> MaskedGuard<'_, impl DerefMut<Target = A> + '_> {
+        let masked = self.mask_interrupts();
+        return MaskedGuard {
+            guard: self.alloc.lock(),
+            _masked: masked,
+        };
+    }
+
+    /// Runs `f` with both `self` and `other` locked. The locks are taken in
+    /// address order, so two threads locking the same pair the other way
+    /// round cannot deadlock.
+    #[cfg(any(feature = "bump_alloc", feature = "buddy_alloc"))]
+    pub(crate) fn with_pair<T>(&self, other: &Self, f: impl FnOnce(&mut A, &mut A) -> T) -> T {
+        debug_assert!(!core::ptr::eq(self, other), "Allocator paired with itself");
+        let _masked = self.mask_interrupts_pair(other);
+        // Locals drop in reverse, so the locks are released in the opposite
+        // order to how they were taken.
+        if (self as *const Self) < (other as *const Self) {
+            let mut ours = self.alloc.lock();
+            let mut theirs = other.alloc.lock();
+            return f(&mut ours, &mut theirs);
+        }
+        let mut theirs = other.alloc.lock();
+        let mut ours = self.alloc.lock();
+        return f(&mut ours, &mut theirs);
+    }
+
+    /// Like `try_allocate`, but fails with `WouldBlock` rather than spinning
+    /// forever when the lock is still held after `LOCK_RETRIES` tries, so a
+    /// real time caller can back off instead of hanging on priority
+    /// inversion.
+    ///
+    /// # Safety
+    pub unsafe fn try_allocate_bounded(
+        &self,
+        layout: Layout,
+    ) -> Result<NonNull<u8>, BAllocatorError> {
+        if layout.size() == 0 {
+            return self.count_alloc(Ok(dangling(layout)));
+        }
+        let _masked = self.mask_interrupts();
+        let result = lock_bounded(|| self.alloc.try_lock())
+            .and_then(|mut alloc| unsafe { alloc.allocate(layout) });
+        return self.count_alloc(self.record(result));
+    }
+
+    /// # Safety
+    pub unsafe fn try_deallocate_bounded(
+        &self,
+        ptr: NonNull<u8>,
+        layout: Layout,
+    ) -> Result<(), BAllocatorError> {
+        if layout.size() == 0 {
+            self.dealloc_calls.fetch_add(1, Ordering::Relaxed);
+            return Ok(());
+        }
+        let _masked = self.mask_interrupts();
+        // The lock is released before `count_dealloc` takes the tag table's.
+        let result = lock_bounded(|| self.alloc.try_lock())
+            .and_then(|mut alloc| unsafe { alloc.deallocate(ptr, layout) });
+        return self.count_dealloc(ptr, result);
+    }
+}
+
+impl<A: LockedInner> Alloc<RwLock<A>> {
+    /// See `Alloc<Mutex<A>>::lock`.
+    #[cfg(any(
+        feature = "bump_alloc",
+        feature = "linked_list_alloc",
+        feature = "buddy_alloc"
+    ))]
+    pub(crate) fn write(&self) -> MaskedGuard<'_, impl DerefMut<Target = A> + '_> {
+        let masked = self.mask_interrupts();
+        return MaskedGuard {
+            guard: self.alloc.write(),
+            _masked: masked,
+        };
+    }
+
+    /// See `Alloc<Mutex<A>>::lock`.
+    #[cfg(any(
+        feature = "bump_alloc",
+        feature = "linked_list_alloc",
+        feature = "buddy_alloc"
+    ))]
+    pub(crate) fn read(&self) -> MaskedGuard<'_, impl Deref<Target = A> + '_> {
+        let masked = self.mask_interrupts();
+        return MaskedGuard {
+            guard: self.alloc.read(),
+            _masked: masked,
+        };
+    }
+
+    /// See `Alloc<Mutex<A>>::with_pair`.
+    #[cfg(any(feature = "bump_alloc", feature = "buddy_alloc"))]
+    pub(crate) fn with_pair<T>(&self, other: &Self, f: impl FnOnce(&mut A, &mut A) -> T) -> T {
+        debug_assert!(!core::ptr::eq(self, other), "Allocator paired with itself");
+        let _masked = self.mask_interrupts_pair(other);
+        if (self as *const Self) < (other as *const Self) {
+            let mut ours = self.alloc.write();
+            let mut theirs = other.alloc.write();
+            return f(&mut ours, &mut theirs);
+        }
+        let mut theirs = other.alloc.write();
+        let mut ours = self.alloc.write();
+        return f(&mut ours, &mut theirs);
+    }
+
+    /// # Safety
+    /// See `Alloc<Mutex<A>>::try_allocate_bounded`.
+    pub unsafe fn try_allocate_bounded(
+        &self,
+        layout: Layout,
+    ) -> Result<NonNull<u8>, BAllocatorError> {
+        if layout.size() == 0 {
+            return self.count_alloc(Ok(dangling(layout)));
+        }
+        let _masked = self.mask_interrupts();
+        let result = lock_bounded(|| self.alloc.try_write())
+            .and_then(|mut alloc| unsafe { alloc.allocate(layout) });
+        return self.count_alloc(self.record(result));
+    }
+
+    /// # Safety
+    pub unsafe fn try_deallocate_bounded(
+        &self,
+        ptr: NonNull<u8>,
+        layout: Layout,
+    ) -> Result<(), BAllocatorError> {
+        if layout.size() == 0 {
+            self.dealloc_calls.fetch_add(1, Ordering::Relaxed);
+            return Ok(());
+        }
+        let _masked = self.mask_interrupts();
+        // The lock is released before `count_dealloc` takes the tag table's.
+        let result = lock_bounded(|| self.alloc.try_write())
+            .and_then(|mut alloc| unsafe { alloc.deallocate(ptr, layout) });
+        return self.count_dealloc(ptr, result);
+    }
+}
+
+/// Zero sized allocations never reach the allocator, they get a dangling
+/// pointer and freeing them is a no-op.
 unsafe impl<A: BAllocator> BAllocator for Alloc<A> {
     unsafe fn try_allocate(&self, layout: Layout) -> Result<NonNull<u8>, BAllocatorError> {
+        if layout.size() == 0 {
+            return self.count_alloc(Ok(dangling(layout)));
+        }
+        let _masked = self.mask_interrupts();
         unsafe {
-            return self.alloc.try_allocate(layout);
+            return self.count_alloc(self.record(self.alloc.try_allocate(layout)));
+        }
+    }
+
+    unsafe fn try_allocate_with_usable(
+        &self,
+        layout: Layout,
+    ) -> Result<(NonNull<u8>, usize), BAllocatorError> {
+        if layout.size() == 0 {
+            return self.count_alloc(Ok((dangling(layout), 0)));
+        }
+        let _masked = self.mask_interrupts();
+        unsafe {
+            return self.count_alloc(self.record(self.alloc.try_allocate_with_usable(layout)));
         }
     }
 
@@ -123,20 +1393,87 @@ unsafe impl<A: BAllocator> BAllocator for Alloc<A> {
         ptr: NonNull<u8>,
         layout: Layout,
     ) -> Result<(), BAllocatorError> {
+        if layout.size() == 0 {
+            self.dealloc_calls.fetch_add(1, Ordering::Relaxed);
+            return Ok(());
+        }
+        let _masked = self.mask_interrupts();
+        return self.count_dealloc(ptr, unsafe { self.alloc.try_deallocate(ptr, layout) });
+    }
+
+    fn can_allocate(&self, layout: Layout) -> bool {
+        if layout.size() == 0 {
+            return true;
+        }
+        let _masked = self.mask_interrupts();
+        return self.alloc.can_allocate(layout);
+    }
+
+    fn base_alignment(&self) -> usize {
+        let _masked = self.mask_interrupts();
+        return self.alloc.base_alignment();
+    }
+
+    unsafe fn try_reallocate(
+        &self,
+        ptr: NonNull<u8>,
+        layout: Layout,
+        new_size: usize,
+    ) -> Result<NonNull<u8>, BAllocatorError> {
         unsafe {
-            return self.alloc.try_deallocate(ptr, layout);
+            if layout.size() == 0 || new_size == 0 {
+                let new_layout = Layout::from_size_align(new_size, layout.align())?;
+                let new_ptr = self.try_allocate(new_layout)?;
+                new_ptr.copy_from_nonoverlapping(ptr, layout.size().min(new_size));
+                self.try_deallocate(ptr, layout)?;
+                return Ok(new_ptr);
+            }
+            let _masked = self.mask_interrupts();
+            let new_ptr = self.record(self.alloc.try_reallocate(ptr, layout, new_size))?;
+            #[cfg(feature = "tagging")]
+            {
+                let mut tags = self.tags.lock();
+                if let Some((_, tag)) = tags.remove(ptr.as_ptr() as usize) {
+                    tags.insert(new_ptr.as_ptr() as usize, (new_size, tag));
+                }
+            }
+            return Ok(new_ptr);
         }
     }
 }
 
 unsafe impl<A: BAllocator> GlobalAlloc for Alloc<A> {
+    /// With the `oom_panic` feature a failed allocation panics instead of
+    /// returning null. A global allocator must not unwind, so that is only
+    /// sound when built with `panic = "abort"`.
     unsafe fn alloc(&self, layout: Layout) -> *mut u8 {
         unsafe {
-            match self.alloc.try_allocate(layout) {
+            match self.try_allocate(layout) {
                 Ok(mut ptr) => return ptr.as_mut(),
                 Err(_e) => {
                     #[cfg(debug_assertions)]
                     error!("GlobalAlloc, Allocation error: {:?}", _e);
+                    if cfg!(feature = "oom_panic") {
+                        panic!(
+                            "GlobalAlloc, Allocation of {layout:?} failed: {:?}, {:?}",
+                            _e,
+                            self.metrics()
+                        );
+                    }
+                    return null_mut();
+                }
+            }
+        }
+    }
+
+    unsafe fn realloc(&self, ptr: *mut u8, layout: Layout, new_size: usize) -> *mut u8 {
+        assert!(!ptr.is_null(), "Given pointer to reallocate is NULL.");
+        unsafe {
+            match self.try_reallocate(NonNull::new_unchecked(ptr), layout, new_size) {
+                Ok(ptr) => return ptr.as_ptr(),
+                Err(_e) => {
+                    #[cfg(debug_assertions)]
+                    error!("GlobalAlloc, Reallocation error: {:?}", _e);
                     return null_mut();
                 }
             }
@@ -146,10 +1483,7 @@ unsafe impl<A: BAllocator> GlobalAlloc for Alloc<A> {
     unsafe fn dealloc(&self, ptr: *mut u8, layout: Layout) {
         assert!(!ptr.is_null(), "Given pointer to deallocate is NULL.");
         unsafe {
-            if let Err(_e) = self
-                .alloc
-                .try_deallocate(NonNull::new_unchecked(ptr), layout)
-            {
+            if let Err(_e) = self.try_deallocate(NonNull::new_unchecked(ptr), layout) {
                 #[cfg(debug_assertions)]
                 error!("GlobalAlloc, Deallocation error: {:?}", _e)
             }
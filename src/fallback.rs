@@ -0,0 +1,94 @@
+use core::{alloc::Layout, ptr::NonNull};
+
+use crate::common::{AllocBounds, BAllocator, BAllocatorError};
+
+/// Allocates from `primary` and falls back to `secondary` when it is out of
+/// memory, such as a bump arena backed by a linked list heap for overflow.
+/// Frees go to `primary` if it contains the pointer, else to `secondary`.
+///
+/// Both allocators are initialized on their own, through `primary` and
+/// `secondary`.
+pub struct FallbackAlloc<P, S> {
+    primary: P,
+    secondary: S,
+}
+
+impl<P: BAllocator + AllocBounds, S: BAllocator> FallbackAlloc<P, S> {
+    pub const fn new(primary: P, secondary: S) -> Self {
+        FallbackAlloc { primary, secondary }
+    }
+
+    pub fn primary(&self) -> &P {
+        return &self.primary;
+    }
+
+    pub fn secondary(&self) -> &S {
+        return &self.secondary;
+    }
+}
+
+unsafe impl<P: BAllocator + AllocBounds, S: BAllocator> BAllocator for FallbackAlloc<P, S> {
+    unsafe fn try_allocate(&self, layout: Layout) -> Result<NonNull<u8>, BAllocatorError> {
+        unsafe {
+            return match self.primary.try_allocate(layout) {
+                Err(e) if e.is_oom() => self.secondary.try_allocate(layout),
+                result => result,
+            };
+        }
+    }
+
+    unsafe fn try_deallocate(
+        &self,
+        ptr: NonNull<u8>,
+        layout: Layout,
+    ) -> Result<(), BAllocatorError> {
+        unsafe {
+            if self.primary.contains(ptr) {
+                return self.primary.try_deallocate(ptr, layout);
+            }
+            return self.secondary.try_deallocate(ptr, layout);
+        }
+    }
+
+    fn can_allocate(&self, layout: Layout) -> bool {
+        return self.primary.can_allocate(layout) || self.secondary.can_allocate(layout);
+    }
+
+    fn base_alignment(&self) -> usize {
+        return self
+            .primary
+            .base_alignment()
+            .min(self.secondary.base_alignment());
+    }
+
+    /// Blocks in `primary` that cannot grow there are moved to `secondary`.
+    /// Blocks in `secondary` stay there.
+    unsafe fn try_reallocate(
+        &self,
+        ptr: NonNull<u8>,
+        layout: Layout,
+        new_size: usize,
+    ) -> Result<NonNull<u8>, BAllocatorError> {
+        unsafe {
+            if !self.primary.contains(ptr) {
+                return self.secondary.try_reallocate(ptr, layout, new_size);
+            }
+            match self.primary.try_reallocate(ptr, layout, new_size) {
+                Err(e) if e.is_oom() => {}
+                result => return result,
+            }
+
+            let new_layout = Layout::from_size_align(new_size, layout.align())?;
+            let new_ptr = self.secondary.try_allocate(new_layout)?;
+            new_ptr.copy_from_nonoverlapping(ptr, layout.size().min(new_size));
+            self.primary.try_deallocate(ptr, layout)?;
+            return Ok(new_ptr);
+        }
+    }
+}
+
+impl<P: AllocBounds, S: AllocBounds> AllocBounds for FallbackAlloc<P, S> {
+    fn contains(&self, ptr: NonNull<u8>) -> bool {
+        return self.primary.contains(ptr) || self.secondary.contains(ptr);
+    }
+}
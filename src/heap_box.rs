@@ -0,0 +1,70 @@
+use core::{
+    alloc::Layout,
+    fmt::{Debug, Formatter, Result as FmtResult},
+    ops::{Deref, DerefMut},
+    ptr::NonNull,
+};
+
+use crate::common::{BAllocator, BAllocatorError};
+
+/// Owning pointer to a `T` in a `BAllocator`, freed on drop, for no_std code
+/// without the nightly allocator API.
+pub struct HeapBox<'a, T, A: BAllocator> {
+    ptr: NonNull<T>,
+    layout: Layout,
+    allocator: &'a A,
+}
+
+impl<'a, T, A: BAllocator> HeapBox<'a, T, A> {
+    pub fn try_new(value: T, allocator: &'a A) -> Result<Self, BAllocatorError> {
+        let layout = Layout::new::<T>();
+        unsafe {
+            let ptr = allocator.try_allocate(layout)?.cast::<T>();
+            ptr.write(value);
+            return Ok(HeapBox {
+                ptr,
+                layout,
+                allocator,
+            });
+        }
+    }
+
+    pub fn into_inner(self) -> T {
+        let this = core::mem::ManuallyDrop::new(self);
+        unsafe {
+            let value = this.ptr.read();
+            // Dropping a value is not allowed to fail, neither is this.
+            let _ = this.allocator.try_deallocate(this.ptr.cast(), this.layout);
+            return value;
+        }
+    }
+}
+
+impl<T, A: BAllocator> Deref for HeapBox<'_, T, A> {
+    type Target = T;
+
+    fn deref(&self) -> &T {
+        return unsafe { self.ptr.as_ref() };
+    }
+}
+
+impl<T, A: BAllocator> DerefMut for HeapBox<'_, T, A> {
+    fn deref_mut(&mut self) -> &mut T {
+        return unsafe { self.ptr.as_mut() };
+    }
+}
+
+impl<T: Debug, A: BAllocator> Debug for HeapBox<'_, T, A> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> FmtResult {
+        return Debug::fmt(&**self, f);
+    }
+}
+
+impl<T, A: BAllocator> Drop for HeapBox<'_, T, A> {
+    fn drop(&mut self) {
+        unsafe {
+            self.ptr.drop_in_place();
+            let _ = self.allocator.try_deallocate(self.ptr.cast(), self.layout);
+        }
+    }
+}
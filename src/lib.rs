@@ -1,4 +1,5 @@
 #![no_std]
+#![cfg_attr(feature = "allocator_api", feature(allocator_api))]
 #![allow(clippy::needless_return)] // I prefer specifying when a fn to return instead of the compiler trying to figure it out.
 
 #[cfg(feature = "buddy_alloc")]
@@ -7,9 +8,23 @@ pub mod buddy_alloc;
 pub mod bump_alloc;
 #[cfg(feature = "linked_list_alloc")]
 pub mod linked_list_alloc;
+#[cfg(feature = "tlsf_alloc")]
+pub mod tlsf_alloc;
+#[cfg(any(test, feature = "bench"))]
+pub mod bench;
+pub mod fallback;
+pub mod heap_box;
+pub mod ops;
 pub(crate) mod common;
+pub(crate) mod lock;
 //pub mod linked_list_alloc;
-pub use crate::common::{AllocInit, AllocState, BAllocator, BAllocatorError, align_up};
+#[cfg(feature = "tagging")]
+pub use crate::common::TAG_CAPACITY;
+pub use crate::common::{
+    AllocBounds, AllocInit, AllocMetrics, AllocState, BAllocator, BAllocatorError, BAllocatorExt,
+    FREE_FILL, GlobalAllocExt, IntegrityError, InterruptGuard, LOCK_RETRIES, LockedInner, RwLocked,
+    align_up,
+};
 
 #[cfg(test)]
 mod tests;
@@ -1,11 +1,16 @@
-use spin::Mutex;
-
-use crate::common::Alloc;
+use crate::{
+    common::{Alloc, RwLocked},
+    lock::Mutex,
+};
 
 mod bconst;
 mod locked;
 mod lockless;
 
-use crate::linked_list_alloc::locked::LockedLinkedList;
+pub use crate::linked_list_alloc::locked::{
+    CoalescePolicy, FitStrategy, GUARD_FILL, GUARD_SIZE, LinkedListBuilder, LockedLinkedList,
+    PADDED_CAPACITY,
+};
 
 pub type LockedLinkedListAlloc = Alloc<Mutex<LockedLinkedList>>;
+pub type RwLockedLinkedListAlloc = RwLocked<LockedLinkedList>;
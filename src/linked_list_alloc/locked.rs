@@ -3,17 +3,25 @@
 
 use core::{
     alloc::Layout,
+    fmt::{Debug, Formatter, Result as FmtResult},
     mem::{align_of, size_of},
-    ptr::NonNull,
+    ptr::{NonNull, write_bytes},
 };
 
 #[cfg(debug_assertions)]
-use log::{debug, trace};
-use spin::Mutex;
+use log::{debug, error, trace};
+use spin::RwLock;
 
-use crate::common::{
-    Alloc, AllocInit, BAllocator, BAllocatorError, HEAP_END_OVERFLOWED, HEAP_SIZE_ZERO,
-    HEAP_START_NULL, align_up,
+#[cfg(feature = "fill_on_free")]
+use crate::common::fill_freed;
+use crate::{
+    common::{
+        ALLOCATOR_UNINITIALIZED, Alloc, AllocBounds, AllocInit, AllocState, BAllocator,
+        BAllocatorError, BlockMap, HEAP_END_OVERFLOWED, HEAP_SIZE_ZERO, HEAP_START_NULL,
+        IntegrityError, LockedInner, MetaWord, align_up, check_alloc, check_free, check_write,
+        dangling, fits_meta_word, from_meta_word, meta_word, prefault,
+    },
+    lock::Mutex,
 };
 
 #[derive(Debug)]
@@ -26,18 +34,135 @@ impl Node {
     const fn new(size: usize) -> Self {
         Self { size, next: None }
     }
+}
+
+/// Where the nodes describing free regions are stored.
+#[derive(Debug, Clone, Copy)]
+enum Meta {
+    /// At the start of the free region itself.
+    Inline,
+    /// In a side table with one node slot per `align_of::<Node>()` bytes of
+    /// heap, so the heap is never written to by the allocator.
+    Side { heap: usize, table: usize },
+}
+
+impl Meta {
+    fn node_ptr(self, addr: usize) -> *mut Node {
+        return match self {
+            Meta::Inline => addr as *mut Node,
+            Meta::Side { heap, table } => {
+                (table + (addr - heap) / align_of::<Node>() * size_of::<Node>()) as *mut Node
+            }
+        };
+    }
 
-    fn start_addr(&self) -> usize {
-        self as *const Self as usize
+    fn region_start(self, node: &Node) -> usize {
+        let addr = node as *const Node as usize;
+        return match self {
+            Meta::Inline => addr,
+            Meta::Side { heap, table } => {
+                heap + (addr - table) / size_of::<Node>() * align_of::<Node>()
+            }
+        };
+    }
+
+    fn region_end(self, node: &Node) -> usize {
+        return self.region_start(node) + node.size;
     }
 
-    fn end_addr(&self) -> usize {
-        self.start_addr() + self.size
+    /// Span node writes for the heap `[start, end)` must stay inside, the
+    /// heap itself or its side table.
+    fn node_span(self, start: usize, end: usize) -> (usize, usize) {
+        return match self {
+            Meta::Inline => (start, end),
+            Meta::Side { table, .. } => (
+                table,
+                table + LockedLinkedList::side_table_size(end - start),
+            ),
+        };
     }
 }
 
+/// Bytes of `GUARD_FILL` written past the end of every block when guard bytes
+/// are enabled.
+pub const GUARD_SIZE: usize = 8;
+/// Pattern guard bytes are filled with, checked again on free.
+pub const GUARD_FILL: u8 = 0xAB;
+/// Number of live blocks that can have taken the rest of their region with
+/// `set_min_split`.
+pub const PADDED_CAPACITY: usize = 16;
+
+/// Which free region an allocation is carved from. Not `PartialEq`, as
+/// `RandomFit`'s entropy source has no meaningful equality.
+#[derive(Debug, Clone, Copy)]
+pub enum FitStrategy {
+    /// The first one large enough, front to back.
+    FirstFit,
+    /// The smallest one large enough, which walks the whole free list but
+    /// leaves the large regions for large requests.
+    BestFit,
+    /// Any one large enough, picked with the given entropy source so block
+    /// addresses are harder to predict. Walks the free list up to twice.
+    RandomFit(fn() -> u64),
+}
+
+/// When freed regions are merged with their neighbours.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CoalescePolicy {
+    /// On every free, which walks the whole free list.
+    OnFree,
+    /// Only when an allocation finds no region big enough, or on
+    /// `coalesce_all`, so frees take constant time.
+    Deferred,
+}
+
 pub struct LockedLinkedList {
     head: Node,
+    meta: Meta,
+    start: usize,
+    end: usize,
+    free_regions: usize,
+    free_bytes: usize,
+    /// Size of the largest free region. Raised as regions are freed or
+    /// merged, the list is only walked for it again when that region is
+    /// taken.
+    largest_free: usize,
+    allocations: usize,
+    coalesce: CoalescePolicy,
+    stack_mode: bool,
+    combine_steps: usize,
+    /// Checksum of the free list taken by `freeze_and_checksum`, set while
+    /// frozen.
+    frozen: Option<usize>,
+    strategy: FitStrategy,
+    min_align: usize,
+    guard_bytes: bool,
+    zero_on_alloc: bool,
+    min_split: usize,
+    /// Bytes grown by of each block that took the rest of its region, by
+    /// offset from the heap start, so freeing it with its original layout
+    /// gives the whole region back.
+    padded: BlockMap<MetaWord, MetaWord, PADDED_CAPACITY>,
+}
+
+impl Debug for Alloc<Mutex<LockedLinkedList>> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> FmtResult {
+        let alloc = self.lock();
+        writeln!(f, "LockedLinkedList {{")?;
+        writeln!(f, "    start: {:#X}", alloc.start)?;
+        writeln!(f, "    end: {:#X}", alloc.end)?;
+        writeln!(f, "    free: [")?;
+        let mut total = 0;
+        let mut current = &alloc.head.next;
+        while let Some(node) = current {
+            writeln!(f, "    {:#X}: {}", alloc.meta.region_start(node), node.size)?;
+            total += node.size;
+            current = &node.next;
+        }
+        writeln!(f, "    ]")?;
+        writeln!(f, "    total: {}", total)?;
+        writeln!(f, "}}")
+    }
 }
 
 impl Default for LockedLinkedList {
@@ -48,36 +173,249 @@ impl Default for LockedLinkedList {
 
 impl LockedLinkedList {
     const fn new() -> Self {
-        Self { head: Node::new(0) }
+        Self {
+            head: Node::new(0),
+            meta: Meta::Inline,
+            start: 0,
+            end: 0,
+            free_regions: 0,
+            free_bytes: 0,
+            largest_free: 0,
+            allocations: 0,
+            coalesce: CoalescePolicy::OnFree,
+            stack_mode: false,
+            combine_steps: 0,
+            frozen: None,
+            strategy: FitStrategy::FirstFit,
+            min_align: align_of::<Node>(),
+            guard_bytes: false,
+            zero_on_alloc: false,
+            min_split: size_of::<Node>(),
+            padded: BlockMap::new((0, 0)),
+        }
     }
 
     unsafe fn init(&mut self, start: usize, size: usize) {
         debug_assert!(start != 0, "{}", HEAP_START_NULL);
         debug_assert!(size > 0, "{}", HEAP_SIZE_ZERO);
-        debug_assert!(start + size < usize::MAX, "{}", HEAP_END_OVERFLOWED);
+        let end = start.checked_add(size).expect(HEAP_END_OVERFLOWED);
         debug_assert_eq!(
             align_up(start, align_of::<Node>()),
             start,
             "Given start is not 8 byte aligned"
         );
+
+        assert!(fits_meta_word(size), "Heap too large for compact_meta");
+
+        self.start = start;
+        self.end = end;
+
         unsafe {
             self.add_free_region(start, size);
         }
     }
 
-    unsafe fn combine_free_regions(&mut self) {
+    unsafe fn init_aligned(
+        &mut self,
+        start: usize,
+        size: usize,
+    ) -> Result<(usize, usize), BAllocatorError> {
+        let aligned = align_up(start, align_of::<Node>());
+        let size = size
+            .checked_sub(aligned - start)
+            .filter(|&size| size >= size_of::<Node>())
+            .ok_or(BAllocatorError::Oom(None))?;
+
+        unsafe { self.init(aligned, size) };
+        return Ok((aligned, size));
+    }
+
+    /// Bytes of side table `init_with_side_table` needs for a heap of
+    /// `heap_size` bytes.
+    pub const fn side_table_size(heap_size: usize) -> usize {
+        return heap_size.div_ceil(align_of::<Node>()) * size_of::<Node>();
+    }
+
+    unsafe fn init_with_side_table(
+        &mut self,
+        start: usize,
+        size: usize,
+        table: usize,
+        table_size: usize,
+    ) {
+        assert!(
+            table_size >= LockedLinkedList::side_table_size(size),
+            "Side table too small for heap"
+        );
+        debug_assert_eq!(
+            align_up(table, align_of::<Node>()),
+            table,
+            "Given side table is not 8 byte aligned"
+        );
+
+        self.meta = Meta::Side { heap: start, table };
+        unsafe { self.init(start, size) };
+    }
+
+    fn is_initialized(&self) -> bool {
+        return self.start != 0;
+    }
+
+    /// Forgets the heap, keeping the configuration.
+    fn deinit(&mut self) {
+        debug_assert_eq!(self.allocations, 0, "Deinitialized with live allocations");
+        *self = LockedLinkedList {
+            coalesce: self.coalesce,
+            stack_mode: self.stack_mode,
+            strategy: self.strategy,
+            min_align: self.min_align,
+            guard_bytes: self.guard_bytes,
+            zero_on_alloc: self.zero_on_alloc,
+            min_split: self.min_split,
+            ..LockedLinkedList::new()
+        };
+    }
+
+    /// Puts the whole heap back on the free list as a single region,
+    /// forgetting every live allocation.
+    unsafe fn dealloc_all(&mut self) {
+        if !self.is_initialized() {
+            return;
+        }
+
+        self.head.next = None;
+        self.frozen = None;
+        self.free_regions = 0;
+        self.free_bytes = 0;
+        self.largest_free = 0;
+        self.allocations = 0;
+        self.padded = BlockMap::new((0, 0));
+        unsafe { self.add_free_region(self.start, self.end - self.start) };
+    }
+
+    /// Returns the number of merges done.
+    unsafe fn combine_free_regions(&mut self) -> usize {
+        let meta = self.meta;
+        let mut merges = 0;
         let mut current = &mut self.head;
 
         while let Some(ref mut node) = current.next {
-            let node_start = node.start_addr();
-            if let Some(ref mut next) = node.next
-                && node_start + node.size == next.start_addr()
+            self.combine_steps += 1;
+            let node_start = meta.region_start(node);
+            // The grown region may touch the one after it too.
+            while let Some(ref mut next) = node.next
+                && node_start + node.size == meta.region_start(next)
             {
                 node.size += next.size;
                 node.next = next.next.take();
+                self.free_regions -= 1;
+                merges += 1;
             }
+            self.largest_free = self.largest_free.max(node.size);
             current = node;
         }
+        return merges;
+    }
+
+    /// Sorts the free list by address so `combine_free_regions` merges every
+    /// pair of touching regions, not only ones that were freed back to back.
+    fn sort_free_regions(&mut self) {
+        let meta = self.meta;
+        let mut unsorted = self.head.next.take();
+
+        while let Some(node) = unsorted {
+            unsorted = node.next.take();
+            let start = meta.region_start(node);
+            let mut current = &mut self.head;
+            while current
+                .next
+                .as_ref()
+                .is_some_and(|next| meta.region_start(next) < start)
+            {
+                self.combine_steps += 1;
+                current = current.next.as_mut().unwrap();
+            }
+            node.next = current.next.take();
+            current.next = Some(node);
+        }
+    }
+
+    /// Merges every pair of touching free regions, returning the number of
+    /// merges, so 0 means another pass would not help. Does nothing while
+    /// frozen.
+    pub fn coalesce_all(&mut self) -> usize {
+        if self.frozen.is_some() {
+            return 0;
+        }
+        self.sort_free_regions();
+        return unsafe { self.combine_free_regions() };
+    }
+
+    pub fn set_coalesce_policy(&mut self, policy: CoalescePolicy) {
+        self.coalesce = policy;
+    }
+
+    /// For allocations freed in LIFO order. Allocating leaves the split off
+    /// tail of its region at the front of the free list, so freeing the most
+    /// recent allocation, or the next one down once that tail has grown over
+    /// it, merges with the tail in constant time instead of walking the list.
+    /// Other frees are handled as usual.
+    pub fn set_stack_mode(&mut self, enabled: bool) {
+        self.stack_mode = enabled;
+    }
+
+    /// Allocations that would leave less than `bytes` of their region free
+    /// after them take the whole region instead, so the free list does not
+    /// fill up with shards too small to be useful. At most `PADDED_CAPACITY`
+    /// such blocks are live at once, past that regions are split as usual.
+    /// Sizes up to a node keep the default of always splitting.
+    pub fn set_min_split(&mut self, bytes: usize) {
+        self.min_split = bytes;
+    }
+
+    /// Remainders shorter than this are absorbed by `alloc_from_region`, 0
+    /// when none are.
+    fn absorb_below(&self) -> usize {
+        if self.min_split <= size_of::<Node>() || self.padded.is_full() {
+            return 0;
+        }
+        return self.min_split;
+    }
+
+    /// Frees `[addr, addr + size)` by growing the region at the front of the
+    /// free list down over it, if that region starts right where it ends.
+    fn free_into_front(&mut self, addr: usize, size: usize) -> bool {
+        let meta = self.meta;
+        let Some(front) = self
+            .head
+            .next
+            .take_if(|front| meta.region_start(front) == addr + size)
+        else {
+            return false;
+        };
+
+        let mut merged = Node::new(size + front.size);
+        merged.next = front.next.take();
+        self.free_bytes += size;
+        self.largest_free = self.largest_free.max(merged.size);
+        let node_ptr = meta.node_ptr(addr);
+        self.check_node_write(node_ptr);
+        unsafe {
+            node_ptr.write_volatile(merged);
+            self.head.next = Some(&mut *node_ptr);
+        }
+        return true;
+    }
+
+    /// Free list nodes visited while coalescing, to keep an eye on the cost
+    /// of frees.
+    pub fn combine_steps(&self) -> usize {
+        return self.combine_steps;
+    }
+
+    fn check_node_write(&self, node_ptr: *mut Node) {
+        let (start, end) = self.meta.node_span(self.start, self.end);
+        check_write(node_ptr as usize, size_of::<Node>(), start, end);
     }
 
     unsafe fn add_free_region(&mut self, addr: usize, size: usize) {
@@ -86,27 +424,123 @@ impl LockedLinkedList {
 
         let mut new_node = Node::new(size);
         new_node.next = self.head.next.take();
-        let node_ptr = addr as *mut Node;
+        self.free_regions += 1;
+        self.free_bytes += size;
+        self.largest_free = self.largest_free.max(size);
+        let node_ptr = self.meta.node_ptr(addr);
+        self.check_node_write(node_ptr);
 
         unsafe {
             #[cfg(debug_assertions)]
-            trace!(
-                "Added free region: {:?}, at Addr: {:#X}",
-                new_node, node_ptr as usize
-            );
+            trace!("Added free region: {:?}, at Addr: {:#X}", new_node, addr);
             node_ptr.write_volatile(new_node);
             self.head.next = Some(&mut *node_ptr)
         }
     }
 
-    fn find_region(&mut self, size: usize, align: usize) -> Option<(&'static mut Node, usize)> {
+    fn find_region(
+        &mut self,
+        size: usize,
+        align: usize,
+    ) -> Option<(&'static mut Node, usize, usize)> {
+        let meta = self.meta;
+        let absorb_below = self.absorb_below();
+        let chosen = match self.strategy {
+            FitStrategy::FirstFit => None,
+            FitStrategy::BestFit => Some(self.best_fit(size, align)?),
+            FitStrategy::RandomFit(rng) => Some(self.random_fit(size, align, rng)?),
+        };
+        let mut current = &mut self.head;
+
+        while let Some(ref mut region) = current.next {
+            if chosen.is_none_or(|start| meta.region_start(region) == start)
+                && let Ok((alloc_start, alloc_end)) =
+                    Self::alloc_from_region(meta, region, size, align, absorb_below)
+            {
+                self.free_regions -= 1;
+                self.free_bytes -= region.size;
+                let was_largest = region.size == self.largest_free;
+                let next = region.next.take();
+                let ret = Some((current.next.take()?, alloc_start, alloc_end));
+                current.next = next;
+                if was_largest {
+                    self.rescan_largest_free();
+                }
+                return ret;
+            } else {
+                current = current.next.as_mut()?
+            }
+        }
+
+        return None;
+    }
+
+    /// Start of the smallest free region a block of `size` and `align` fits.
+    fn best_fit(&self, size: usize, align: usize) -> Option<usize> {
+        let absorb_below = self.absorb_below();
+        let mut best: Option<&Node> = None;
+        let mut current = &self.head.next;
+        while let Some(region) = current {
+            if best.is_none_or(|best| region.size < best.size)
+                && Self::alloc_from_region(self.meta, region, size, align, absorb_below).is_ok()
+            {
+                best = Some(region);
+            }
+            current = &region.next;
+        }
+        return best.map(|region| self.meta.region_start(region));
+    }
+
+    /// Start of a free region a block of `size` and `align` fits, chosen by
+    /// `rng` among all that do. `rng` is only called if there is a choice.
+    fn random_fit(&self, size: usize, align: usize, rng: fn() -> u64) -> Option<usize> {
+        let absorb_below = self.absorb_below();
+        let fits = |region: &Node| {
+            Self::alloc_from_region(self.meta, region, size, align, absorb_below).is_ok()
+        };
+
+        let mut fitting = 0;
+        let mut current = &self.head.next;
+        while let Some(region) = current {
+            fitting += fits(region) as u64;
+            current = &region.next;
+        }
+        let mut skip = match fitting {
+            0 => return None,
+            1 => 0,
+            _ => rng() % fitting,
+        };
+
+        let mut current = &self.head.next;
+        while let Some(region) = current {
+            if fits(region) {
+                if skip == 0 {
+                    return Some(self.meta.region_start(region));
+                }
+                skip -= 1;
+            }
+            current = &region.next;
+        }
+        return None;
+    }
+
+    /// Takes the free region fully covering `[addr, addr + size)` out of the list.
+    fn find_region_at(&mut self, addr: usize, size: usize) -> Option<&'static mut Node> {
+        let meta = self.meta;
         let mut current = &mut self.head;
+        let end = addr.checked_add(size)?;
 
         while let Some(ref mut region) = current.next {
-            if let Ok(alloc_start) = Self::alloc_from_region(region, size, align) {
+            if meta.region_start(region) <= addr && end <= meta.region_end(region) {
+                self.free_regions -= 1;
+                self.free_bytes -= region.size;
+                let was_largest = region.size == self.largest_free;
                 let next = region.next.take();
-                let ret = Some((current.next.take()?, alloc_start));
+                let ret = current.next.take();
                 current.next = next;
+                if was_largest {
+                    self.rescan_largest_free();
+                }
                 return ret;
             } else {
                 current = current.next.as_mut()?
@@ -116,79 +550,736 @@ impl LockedLinkedList {
         return None;
     }
 
-    fn alloc_from_region(region: &Node, size: usize, align: usize) -> Result<usize, ()> {
-        let alloc_start = align_up(region.start_addr(), align);
+    unsafe fn allocate_at(
+        &mut self,
+        addr: usize,
+        layout: Layout,
+    ) -> Result<NonNull<u8>, BAllocatorError> {
+        if self.frozen.is_some() {
+            return Err(BAllocatorError::Frozen);
+        }
+        let (size, align) = self.size_align(layout)?;
+        if !addr.is_multiple_of(align) {
+            return Err(BAllocatorError::Alignment(layout));
+        }
+
+        let region = self
+            .find_region_at(addr, size)
+            .ok_or(BAllocatorError::Oom(Some(layout)))?;
+        let (region_start, region_end) =
+            (self.meta.region_start(region), self.meta.region_end(region));
+        let lead_size = addr - region_start;
+        let trail_size = region_end - (addr + size);
+
+        // Remainders too small to hold a node cannot be put back on the list.
+        if (lead_size > 0 && lead_size < size_of::<Node>())
+            || (trail_size > 0 && trail_size < size_of::<Node>())
+        {
+            unsafe { self.add_free_region(region_start, region_end - region_start) };
+            return Err(BAllocatorError::Alignment(layout));
+        }
+
+        unsafe {
+            if lead_size > 0 {
+                self.add_free_region(region_start, lead_size);
+            }
+            if trail_size > 0 {
+                self.add_free_region(addr + size, trail_size);
+            }
+        }
+
+        self.allocations += 1;
+        unsafe { self.prepare_block(addr, layout) };
+        #[cfg(debug_assertions)]
+        debug!(
+            "Allocated object \"{:X}\" at requested address; layout: {layout:?}",
+            addr
+        );
+        return NonNull::new(addr as *mut u8).ok_or(BAllocatorError::Null);
+    }
+
+    /// Where in `region` a block of `size` and `align` goes, as `(start,
+    /// end)`. The gaps left before and after it are either empty or can hold
+    /// a node, so both can go straight back on the list. A gap after it
+    /// shorter than `absorb_below` is made part of the block instead.
+    fn alloc_from_region(
+        meta: Meta,
+        region: &Node,
+        size: usize,
+        align: usize,
+        absorb_below: usize,
+    ) -> Result<(usize, usize), ()> {
+        let (region_start, region_end) = (meta.region_start(region), meta.region_end(region));
+        let mut alloc_start = align_up(region_start, align);
+
+        // The leading gap is returned as a free region, so it has to be able
+        // to hold a node.
+        let lead_size = alloc_start - region_start;
+        if lead_size > 0 && lead_size < size_of::<Node>() {
+            alloc_start = align_up(region_start + size_of::<Node>(), align);
+        }
         let alloc_end = alloc_start.checked_add(size).ok_or(())?;
 
-        if alloc_end > region.end_addr() {
+        if alloc_end > region_end {
             return Err(());
         }
 
-        let excess_size = region.end_addr() - alloc_end;
+        let excess_size = region_end - alloc_end;
+        if excess_size > 0 && excess_size < absorb_below {
+            return Ok((alloc_start, region_end));
+        }
         if excess_size > 0 && excess_size < size_of::<Node>() {
             return Err(());
         }
 
-        Ok(alloc_start)
+        Ok((alloc_start, alloc_end))
+    }
+
+    pub fn free_region_count(&self) -> usize {
+        return self.free_regions;
+    }
+
+    /// Walks the free list for `largest_free`, once the region it was the
+    /// size of has been taken off.
+    fn rescan_largest_free(&mut self) {
+        let mut largest = 0;
+        let mut current = &self.head.next;
+        while let Some(region) = current {
+            largest = largest.max(region.size);
+            current = &region.next;
+        }
+        self.largest_free = largest;
+    }
+
+    /// Largest single allocation that currently fits, the size of the
+    /// largest free region.
+    pub fn max_alloc_size(&self) -> usize {
+        return self.largest_free;
+    }
+
+    /// `1 - largest_free / total_free`, 0 when all free memory is in a
+    /// single region and approaching 1 as it is split into many small ones.
+    pub fn fragmentation(&self) -> f32 {
+        if self.free_bytes == 0 {
+            return 0.0;
+        }
+        return 1.0 - self.largest_free as f32 / self.free_bytes as f32;
+    }
+
+    /// Rotate and xor of the start and size of every free region, in list
+    /// order. Stops after `free_regions` nodes so a corrupted chain cannot
+    /// loop forever, and mixes in the count of nodes walked.
+    fn checksum(&self) -> usize {
+        let mut checksum: usize = 0;
+        let mut walked = 0;
+        let mut current = &self.head.next;
+        while let Some(region) = current
+            && walked <= self.free_regions
+        {
+            checksum = checksum.rotate_left(7) ^ self.meta.region_start(region);
+            checksum = checksum.rotate_left(7) ^ region.size;
+            walked += 1;
+            current = &region.next;
+        }
+        return checksum.rotate_left(7) ^ walked;
+    }
+
+    /// Freezes the free list, making allocations and frees fail with
+    /// `Frozen`, and stores a checksum of it for `verify_checksum`. Returns
+    /// the checksum.
+    pub fn freeze_and_checksum(&mut self) -> usize {
+        let checksum = self.checksum();
+        self.frozen = Some(checksum);
+        return checksum;
+    }
+
+    pub fn thaw(&mut self) {
+        self.frozen = None;
+    }
+
+    pub fn is_frozen(&self) -> bool {
+        return self.frozen.is_some();
+    }
+
+    /// Recomputes the checksum stored by `freeze_and_checksum`, failing if
+    /// the free list changed since. Always succeeds when not frozen.
+    pub fn verify_checksum(&self) -> Result<(), IntegrityError> {
+        let Some(expected) = self.frozen else {
+            return Ok(());
+        };
+        let found = self.checksum();
+        if found != expected {
+            #[cfg(debug_assertions)]
+            error!("Free list checksum {found:#X} does not match {expected:#X}");
+            return Err(IntegrityError::ChecksumMismatch { expected, found });
+        }
+        return Ok(());
     }
 
-    fn size_align(layout: Layout) -> (usize, usize) {
-        let layout = layout
-            .align_to(align_of::<Node>())
-            .expect("adjusting alignment failed")
+    /// Walks the free list checking every region is inside the heap, can
+    /// hold a node and overlaps no other region. Quadratic in the number of
+    /// free regions, but only reads.
+    pub fn self_test(&self) -> Result<(), IntegrityError> {
+        let meta = self.meta;
+        let mut current = &self.head.next;
+        while let Some(region) = current {
+            let (addr, size) = (meta.region_start(region), region.size);
+            if addr < self.start || addr.checked_add(size).is_none_or(|end| end > self.end) {
+                return Err(IntegrityError::OutOfBounds { addr, size });
+            }
+            if size < size_of::<Node>() {
+                return Err(IntegrityError::Undersized { addr, size });
+            }
+
+            let mut other = &region.next;
+            while let Some(next) = other {
+                let next_addr = meta.region_start(next);
+                if addr < next_addr.saturating_add(next.size) && next_addr < addr + size {
+                    return Err(IntegrityError::Overlap {
+                        first: addr,
+                        second: next_addr,
+                    });
+                }
+                other = &next.next;
+            }
+            current = &region.next;
+        }
+        return Ok(());
+    }
+
+    /// Faults in the heap ahead of time, touching it once per `page_size`
+    /// bytes, the MMU page size of the target. Free regions keep their
+    /// headers.
+    ///
+    /// # Safety
+    /// Nothing may access blocks allocated from this heap while it runs.
+    pub unsafe fn prefault(&self, page_size: usize) {
+        unsafe { prefault(self.start, self.end, page_size) };
+    }
+
+    /// Whether `[addr, addr + size)` reaches outside the heap or overlaps a
+    /// free region, as happens when freeing with a larger layout than was
+    /// allocated.
+    #[cfg(debug_assertions)]
+    fn overlaps_free(&self, addr: usize, size: usize) -> bool {
+        let Some(end) = addr.checked_add(size) else {
+            return true;
+        };
+        if addr < self.start || end > self.end {
+            return true;
+        }
+
+        let mut current = &self.head.next;
+        while let Some(region) = current {
+            if addr < self.meta.region_end(region) && self.meta.region_start(region) < end {
+                return true;
+            }
+            current = &region.next;
+        }
+        return false;
+    }
+
+    /// The size and alignment actually taken from the heap for `layout`,
+    /// including guard bytes. Blocks may be freed with any layout this maps
+    /// to the same size as the one they were allocated with.
+    /// Fails with `Layout` where padding `layout` out overflows.
+    fn size_align(&self, layout: Layout) -> Result<(usize, usize), BAllocatorError> {
+        let guard = if self.guard_bytes { GUARD_SIZE } else { 0 };
+        let layout = Layout::from_size_align(layout.size() + guard, layout.align())?
+            .align_to(self.min_align)?
             .pad_to_align();
         let size = layout.size().max(size_of::<Node>());
-        (size, layout.align())
+        return Ok((size, layout.align()));
+    }
+
+    /// Writes the guard bytes and zeroes the block, as configured.
+    unsafe fn prepare_block(&self, addr: usize, layout: Layout) {
+        unsafe {
+            if self.guard_bytes {
+                write_bytes((addr + layout.size()) as *mut u8, GUARD_FILL, GUARD_SIZE);
+            }
+            if self.zero_on_alloc {
+                write_bytes(addr as *mut u8, 0, layout.size());
+            }
+        }
+    }
+
+    fn guard_intact(ptr: NonNull<u8>, layout: Layout) -> bool {
+        let guard =
+            unsafe { core::slice::from_raw_parts(ptr.as_ptr().add(layout.size()), GUARD_SIZE) };
+        return guard.iter().all(|&b| b == GUARD_FILL);
     }
 }
 
-unsafe impl BAllocator for Mutex<LockedLinkedList> {
-    unsafe fn try_allocate(&self, layout: Layout) -> Result<NonNull<u8>, BAllocatorError> {
-        let (size, align) = LockedLinkedList::size_align(layout);
-        let mut allocator = self.lock();
+unsafe impl LockedInner for LockedLinkedList {
+    unsafe fn allocate(&mut self, layout: Layout) -> Result<NonNull<u8>, BAllocatorError> {
+        if !self.is_initialized() {
+            #[cfg(debug_assertions)]
+            error!("{}", ALLOCATOR_UNINITIALIZED);
+            return Err(BAllocatorError::Uninitialized);
+        }
+        if self.frozen.is_some() {
+            return Err(BAllocatorError::Frozen);
+        }
+        let (size, align) = self.size_align(layout)?;
 
-        if let Some((region, alloc_start)) = allocator.find_region(size, align) {
-            let alloc_end = match alloc_start.checked_add(size) {
-                Some(t) => t,
-                None => return Err(BAllocatorError::Oom(Some(layout))),
-            };
-            match region.end_addr().checked_sub(alloc_end) {
-                Some(excess_size) => unsafe {
-                    allocator.add_free_region(alloc_end, excess_size);
-                },
-                None => return Err(BAllocatorError::Underflowed),
+        let mut found = self.find_region(size, align);
+        if found.is_none() && self.coalesce == CoalescePolicy::Deferred && self.coalesce_all() > 0 {
+            found = self.find_region(size, align);
+        }
+
+        if let Some((region, alloc_start, alloc_end)) = found {
+            let (region_start, region_end) =
+                (self.meta.region_start(region), self.meta.region_end(region));
+
+            let lead_size = alloc_start - region_start;
+            if lead_size > 0 {
+                unsafe { self.add_free_region(region_start, lead_size) };
+            }
+            // An exact fit leaves nothing behind, and `add_free_region`
+            // only takes regions a node fits in.
+            let excess_size = region_end - alloc_end;
+            if excess_size > 0 {
+                unsafe { self.add_free_region(alloc_end, excess_size) };
             }
+            check_alloc(alloc_start, layout, self.start, self.end);
 
+            if alloc_end - alloc_start > size {
+                let tracked = self.padded.insert(
+                    meta_word(alloc_start - self.start),
+                    meta_word(alloc_end - alloc_start - size),
+                );
+                debug_assert!(tracked, "Absorbed a remainder with the padded table full");
+            }
+            self.allocations += 1;
+            unsafe { self.prepare_block(alloc_start, layout) };
             return Ok(unsafe { NonNull::new_unchecked(alloc_start as *mut u8) });
+        } else if layout.size() <= self.end - self.start
+            && align_up(self.start, align)
+                .checked_add(size)
+                .is_none_or(|alloc_end| alloc_end > self.end)
+        {
+            // The size fits the heap, but not once aligned and padded.
+            #[cfg(debug_assertions)]
+            error!("Heap can never satisfy alignment of layout: {layout:?}");
+            return Err(BAllocatorError::Alignment(layout));
         } else {
             return Err(BAllocatorError::Oom(Some(layout)));
         }
     }
 
-    unsafe fn try_deallocate(
-        &self,
-        ptr: core::ptr::NonNull<u8>,
+    /// Blocks are padded to the node alignment and at least a node long, so
+    /// they can hold one once freed. With guard bytes only `layout.size()`
+    /// is usable.
+    unsafe fn allocate_with_usable(
+        &mut self,
+        layout: Layout,
+    ) -> Result<(NonNull<u8>, usize), BAllocatorError> {
+        let ptr = unsafe { self.allocate(layout)? };
+        if self.guard_bytes {
+            return Ok((ptr, layout.size()));
+        }
+        return Ok((ptr, self.size_align(layout)?.0));
+    }
+
+    unsafe fn deallocate(
+        &mut self,
+        ptr: NonNull<u8>,
         layout: Layout,
     ) -> Result<(), BAllocatorError> {
-        let (size, _) = LockedLinkedList::size_align(layout);
+        if self.frozen.is_some() {
+            return Err(BAllocatorError::Frozen);
+        }
+        let addr = ptr.as_ptr() as usize;
+        let extra = MetaWord::try_from(addr.wrapping_sub(self.start))
+            .ok()
+            .and_then(|offset| self.padded.get(offset))
+            .map_or(0, from_meta_word);
+        let size = self.size_align(layout)?.0 + extra;
 
+        #[cfg(debug_assertions)]
+        if self.overlaps_free(addr, size) {
+            error!(
+                "Rejected free of \"{:X}\"; layout: {layout:?}",
+                ptr.as_ptr() as usize
+            );
+            return Err(BAllocatorError::InvalidFree);
+        }
+        check_free(addr, size, self.start, self.end)?;
+        if self.guard_bytes && !LockedLinkedList::guard_intact(ptr, layout) {
+            #[cfg(debug_assertions)]
+            error!(
+                "Guard bytes of \"{:X}\" overwritten; layout: {layout:?}",
+                ptr.as_ptr() as usize
+            );
+            return Err(BAllocatorError::GuardOverwritten);
+        }
+
+        #[cfg(feature = "fill_on_free")]
         unsafe {
-            self.lock().add_free_region(ptr.as_ptr() as usize, size);
-            self.lock().combine_free_regions();
+            fill_freed(ptr, layout.size())
+        };
+        self.padded.remove(meta_word(addr - self.start));
+        if self.stack_mode && self.free_into_front(addr, size) {
+            self.allocations = self.allocations.saturating_sub(1);
+            return Ok(());
         }
+
+        unsafe {
+            self.add_free_region(addr, size);
+            if self.coalesce == CoalescePolicy::OnFree {
+                self.combine_free_regions();
+            }
+        }
+        self.allocations = self.allocations.saturating_sub(1);
         return Ok(());
     }
+
+    /// Every block is aligned to at least `min_align`, a `Node` boundary
+    /// unless configured higher.
+    fn base_alignment(&self) -> usize {
+        return self.min_align;
+    }
+
+    /// Regions that `CoalescePolicy::Deferred` has not merged yet are not
+    /// taken into account.
+    fn can_allocate(&self, layout: Layout) -> bool {
+        let Ok((size, align)) = self.size_align(layout) else {
+            return false;
+        };
+        let absorb_below = self.absorb_below();
+        let mut current = &self.head.next;
+
+        while let Some(region) = current {
+            if LockedLinkedList::alloc_from_region(self.meta, region, size, align, absorb_below)
+                .is_ok()
+            {
+                return true;
+            }
+            current = &region.next;
+        }
+        return false;
+    }
+}
+
+impl AllocState for LockedLinkedList {
+    fn remaining(&self) -> usize {
+        return self.free_bytes;
+    }
+    fn allocations(&self) -> usize {
+        return self.allocations;
+    }
+}
+
+impl AllocBounds for LockedLinkedList {
+    fn contains(&self, ptr: NonNull<u8>) -> bool {
+        return (self.start..self.end).contains(&(ptr.as_ptr() as usize));
+    }
+}
+
+/// Configures a linked list allocator in one place before building it. The
+/// builder methods are const so the result can initialize a static.
+#[derive(Debug, Clone, Copy)]
+pub struct LinkedListBuilder {
+    coalesce: CoalescePolicy,
+    stack_mode: bool,
+    strategy: FitStrategy,
+    min_align: usize,
+    guard_bytes: bool,
+    zero_on_alloc: bool,
+    min_split: usize,
+}
+
+impl Default for LinkedListBuilder {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl LinkedListBuilder {
+    /// The defaults of `LockedLinkedListAlloc::new`.
+    pub const fn new() -> Self {
+        LinkedListBuilder {
+            coalesce: CoalescePolicy::OnFree,
+            stack_mode: false,
+            strategy: FitStrategy::FirstFit,
+            min_align: align_of::<Node>(),
+            guard_bytes: false,
+            zero_on_alloc: false,
+            min_split: size_of::<Node>(),
+        }
+    }
+
+    /// Aligns every block to at least `align`, a power of two.
+    pub const fn min_align(mut self, align: usize) -> Self {
+        assert!(
+            align.is_power_of_two(),
+            "Minimum alignment not a power of two"
+        );
+        if align > self.min_align {
+            self.min_align = align;
+        }
+        return self;
+    }
+
+    pub const fn strategy(mut self, strategy: FitStrategy) -> Self {
+        self.strategy = strategy;
+        return self;
+    }
+
+    /// Places `GUARD_SIZE` bytes of `GUARD_FILL` after every block. Frees
+    /// that find them overwritten fail with `GuardOverwritten`, leaking the
+    /// block rather than trusting its neighbours.
+    pub const fn guard_bytes(mut self, enabled: bool) -> Self {
+        self.guard_bytes = enabled;
+        return self;
+    }
+
+    /// Zeroes every block on allocation.
+    pub const fn zero_on_alloc(mut self, enabled: bool) -> Self {
+        self.zero_on_alloc = enabled;
+        return self;
+    }
+
+    pub const fn coalesce(mut self, policy: CoalescePolicy) -> Self {
+        self.coalesce = policy;
+        return self;
+    }
+
+    /// See `LockedLinkedList::set_stack_mode`.
+    pub const fn stack_mode(mut self, enabled: bool) -> Self {
+        self.stack_mode = enabled;
+        return self;
+    }
+
+    /// See `LockedLinkedList::set_min_split`.
+    pub const fn min_split(mut self, bytes: usize) -> Self {
+        self.min_split = bytes;
+        return self;
+    }
+
+    const fn configure(self) -> LockedLinkedList {
+        let mut alloc = LockedLinkedList::new();
+        alloc.coalesce = self.coalesce;
+        alloc.stack_mode = self.stack_mode;
+        alloc.strategy = self.strategy;
+        alloc.min_align = self.min_align;
+        alloc.guard_bytes = self.guard_bytes;
+        alloc.zero_on_alloc = self.zero_on_alloc;
+        alloc.min_split = self.min_split;
+        return alloc;
+    }
+
+    /// Uninitialized allocator with this configuration, `init` it as usual.
+    pub const fn build(self) -> Alloc<Mutex<LockedLinkedList>> {
+        return Alloc::wrap(Mutex::new(self.configure()));
+    }
+
+    pub const fn build_rwlocked(self) -> Alloc<RwLock<LockedLinkedList>> {
+        return Alloc::wrap(RwLock::new(self.configure()));
+    }
 }
 
 unsafe impl Sync for Alloc<Mutex<LockedLinkedList>> {}
 unsafe impl Send for Alloc<Mutex<LockedLinkedList>> {}
 
+/// Stored directly in front of blocks handed out by `try_allocate_tracked` so
+/// `free_by_ptr` can recover the layout without the caller providing it.
+#[derive(Debug, Clone, Copy)]
+struct SizeHeader {
+    layout: Layout,
+    offset: usize,
+}
+
 impl Alloc<Mutex<LockedLinkedList>> {
     pub const fn new() -> Self {
-        Alloc {
-            alloc: Mutex::new(LockedLinkedList::new()),
+        Alloc::wrap(Mutex::new(LockedLinkedList::new()))
+    }
+
+    /// # Safety
+    pub unsafe fn try_allocate_tracked(
+        &self,
+        layout: Layout,
+    ) -> Result<NonNull<u8>, BAllocatorError> {
+        let (block_layout, offset) = Layout::new::<SizeHeader>()
+            .extend(layout)
+            .map_err(BAllocatorError::Layout)?;
+
+        unsafe {
+            let block = self.try_allocate(block_layout)?;
+            let ptr = block.add(offset);
+            ptr.cast::<SizeHeader>().sub(1).write_unaligned(SizeHeader {
+                layout: block_layout,
+                offset,
+            });
+            return Ok(ptr);
+        }
+    }
+
+    /// Allocates the block at exactly `addr`. Zero sized requests get a
+    /// dangling pointer like `try_allocate`, wherever `addr` is.
+    ///
+    /// # Safety
+    pub unsafe fn try_allocate_at(
+        &self,
+        addr: usize,
+        layout: Layout,
+    ) -> Result<NonNull<u8>, BAllocatorError> {
+        if layout.size() == 0 {
+            return self.count_alloc(Ok(dangling(layout)));
+        }
+        return self.count_alloc(self.record(unsafe { self.lock().allocate_at(addr, layout) }));
+    }
+
+    /// # Safety
+    /// `ptr` must have been returned by `try_allocate_tracked` on this allocator.
+    pub unsafe fn free_by_ptr(&self, ptr: NonNull<u8>) -> Result<(), BAllocatorError> {
+        unsafe {
+            let header = ptr.cast::<SizeHeader>().sub(1).read_unaligned();
+            let block = ptr.sub(header.offset);
+            return self.try_deallocate(block, header.layout);
+        }
+    }
+
+    /// Like `init`, but keeps the free list in the `table_size` bytes at
+    /// `table` instead of in the heap, so the heap is only ever written to
+    /// by its users. The table must hold at least
+    /// `LockedLinkedList::side_table_size(size)` bytes.
+    ///
+    /// # Safety
+    pub unsafe fn init_with_side_table(
+        &self,
+        start: usize,
+        size: usize,
+        table: usize,
+        table_size: usize,
+    ) {
+        unsafe {
+            #[cfg(debug_assertions)]
+            debug!(
+                "Initialized locked linked list alloc with side table; start: {start:#X}, size: {size}, table: {table:#X}"
+            );
+            self.lock()
+                .init_with_side_table(start, size, table, table_size);
+        }
+    }
+
+    /// Reclaims the whole heap at once, e.g. when tearing down a subsystem
+    /// that owns this allocator.
+    ///
+    /// # Safety
+    /// No block allocated before the call may be used after it.
+    pub unsafe fn dealloc_all(&self) {
+        unsafe { self.lock().dealloc_all() };
+    }
+
+    pub fn free_region_count(&self) -> usize {
+        return self.lock().free_region_count();
+    }
+
+    pub fn coalesce_all(&self) -> usize {
+        return self.lock().coalesce_all();
+    }
+
+    pub fn set_coalesce_policy(&self, policy: CoalescePolicy) {
+        self.lock().set_coalesce_policy(policy);
+    }
+
+    pub fn set_stack_mode(&self, enabled: bool) {
+        self.lock().set_stack_mode(enabled);
+    }
+
+    pub fn set_min_split(&self, bytes: usize) {
+        self.lock().set_min_split(bytes);
+    }
+
+    pub fn combine_steps(&self) -> usize {
+        return self.lock().combine_steps();
+    }
+
+    pub fn freeze_and_checksum(&self) -> usize {
+        return self.lock().freeze_and_checksum();
+    }
+
+    pub fn thaw(&self) {
+        self.lock().thaw();
+    }
+
+    pub fn is_frozen(&self) -> bool {
+        return self.lock().is_frozen();
+    }
+
+    pub fn verify_checksum(&self) -> Result<(), IntegrityError> {
+        return self.lock().verify_checksum();
+    }
+
+    /// Like `init`, but rounds `start` up to the alignment the free list
+    /// needs and shrinks `size` by the padding, returning the region
+    /// actually managed. Fails if too little is left to hold a free region.
+    ///
+    /// # Safety
+    /// The heap must be valid and unused, as for `init`.
+    pub unsafe fn init_aligned(
+        &self,
+        start: usize,
+        size: usize,
+    ) -> Result<(usize, usize), BAllocatorError> {
+        unsafe {
+            #[cfg(debug_assertions)]
+            debug!("Initialized locked linked list alloc aligned; start: {start:#X}, size: {size}");
+            return self.lock().init_aligned(start, size);
+        }
+    }
+
+    pub fn fragmentation(&self) -> f32 {
+        return self.lock().fragmentation();
+    }
+
+    pub fn max_alloc_size(&self) -> usize {
+        return self.lock().max_alloc_size();
+    }
+
+    pub fn self_test(&self) -> Result<(), IntegrityError> {
+        return self.lock().self_test();
+    }
+
+    /// See `LockedLinkedList::prefault`.
+    ///
+    /// # Safety
+    /// As for `LockedLinkedList::prefault`.
+    pub unsafe fn prefault(&self, page_size: usize) {
+        unsafe { self.lock().prefault(page_size) };
+    }
+}
+
+#[cfg(test)]
+impl Alloc<Mutex<LockedLinkedList>> {
+    /// Calls `f` with the start and size of every free region, front to
+    /// back.
+    pub(crate) fn debug_free_list(&self, mut f: impl FnMut(usize, usize)) {
+        let alloc = self.lock();
+        let mut current = &alloc.head.next;
+        while let Some(region) = current {
+            f(alloc.meta.region_start(region), region.size);
+            current = &region.next;
+        }
+    }
+
+    pub(crate) fn corrupt_front_size(&self, size: usize) {
+        if let Some(front) = self.lock().head.next.as_mut() {
+            front.size = size;
         }
     }
+
+    /// Puts `[addr, addr + size)` on the free list without any checks.
+    pub(crate) unsafe fn push_free_region(&self, addr: usize, size: usize) {
+        unsafe { self.lock().add_free_region(addr, size) };
+    }
+
+    #[cfg(feature = "checked")]
+    pub(crate) fn corrupt_end(&self, end: usize) {
+        self.lock().end = end;
+    }
 }
 
 impl Default for Alloc<Mutex<LockedLinkedList>> {
@@ -205,4 +1296,152 @@ impl AllocInit for Mutex<LockedLinkedList> {
             self.lock().init(start, size);
         }
     }
+
+    unsafe fn deinit(&self) -> Result<(), BAllocatorError> {
+        #[cfg(debug_assertions)]
+        debug!("Deinitialized locked linked list alloc");
+        self.lock().deinit();
+        return Ok(());
+    }
+
+    fn is_initialized(&self) -> bool {
+        return self.lock().is_initialized();
+    }
+}
+
+unsafe impl Sync for Alloc<RwLock<LockedLinkedList>> {}
+unsafe impl Send for Alloc<RwLock<LockedLinkedList>> {}
+
+impl Alloc<RwLock<LockedLinkedList>> {
+    pub const fn new() -> Self {
+        Alloc::wrap(RwLock::new(LockedLinkedList::new()))
+    }
+
+    /// # Safety
+    /// See `Alloc<Mutex<LockedLinkedList>>::init_with_side_table`.
+    pub unsafe fn init_with_side_table(
+        &self,
+        start: usize,
+        size: usize,
+        table: usize,
+        table_size: usize,
+    ) {
+        unsafe {
+            #[cfg(debug_assertions)]
+            debug!(
+                "Initialized rwlocked linked list alloc with side table; start: {start:#X}, size: {size}, table: {table:#X}"
+            );
+            self.write()
+                .init_with_side_table(start, size, table, table_size);
+        }
+    }
+
+    /// # Safety
+    /// See `Alloc<Mutex<LockedLinkedList>>::dealloc_all`.
+    pub unsafe fn dealloc_all(&self) {
+        unsafe { self.write().dealloc_all() };
+    }
+
+    pub fn free_region_count(&self) -> usize {
+        return self.read().free_region_count();
+    }
+
+    pub fn coalesce_all(&self) -> usize {
+        return self.write().coalesce_all();
+    }
+
+    pub fn set_coalesce_policy(&self, policy: CoalescePolicy) {
+        self.write().set_coalesce_policy(policy);
+    }
+
+    pub fn set_stack_mode(&self, enabled: bool) {
+        self.write().set_stack_mode(enabled);
+    }
+
+    pub fn set_min_split(&self, bytes: usize) {
+        self.write().set_min_split(bytes);
+    }
+
+    pub fn combine_steps(&self) -> usize {
+        return self.read().combine_steps();
+    }
+
+    pub fn freeze_and_checksum(&self) -> usize {
+        return self.write().freeze_and_checksum();
+    }
+
+    pub fn thaw(&self) {
+        self.write().thaw();
+    }
+
+    pub fn is_frozen(&self) -> bool {
+        return self.read().is_frozen();
+    }
+
+    pub fn verify_checksum(&self) -> Result<(), IntegrityError> {
+        return self.read().verify_checksum();
+    }
+
+    /// # Safety
+    /// See `Alloc<Mutex<LockedLinkedList>>::init_aligned`.
+    pub unsafe fn init_aligned(
+        &self,
+        start: usize,
+        size: usize,
+    ) -> Result<(usize, usize), BAllocatorError> {
+        unsafe {
+            #[cfg(debug_assertions)]
+            debug!(
+                "Initialized rwlocked linked list alloc aligned; start: {start:#X}, size: {size}"
+            );
+            return self.write().init_aligned(start, size);
+        }
+    }
+
+    pub fn fragmentation(&self) -> f32 {
+        return self.read().fragmentation();
+    }
+
+    pub fn max_alloc_size(&self) -> usize {
+        return self.read().max_alloc_size();
+    }
+
+    pub fn self_test(&self) -> Result<(), IntegrityError> {
+        return self.read().self_test();
+    }
+
+    /// See `LockedLinkedList::prefault`.
+    ///
+    /// # Safety
+    /// As for `LockedLinkedList::prefault`.
+    pub unsafe fn prefault(&self, page_size: usize) {
+        unsafe { self.read().prefault(page_size) };
+    }
+}
+
+impl Default for Alloc<RwLock<LockedLinkedList>> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl AllocInit for RwLock<LockedLinkedList> {
+    unsafe fn init(&self, start: usize, size: usize) {
+        unsafe {
+            #[cfg(debug_assertions)]
+            debug!("Initialized rwlocked linked list alloc; start: {start:#X}, size: {size}");
+            self.write().init(start, size);
+        }
+    }
+
+    unsafe fn deinit(&self) -> Result<(), BAllocatorError> {
+        #[cfg(debug_assertions)]
+        debug!("Deinitialized rwlocked linked list alloc");
+        self.write().deinit();
+        return Ok(());
+    }
+
+    fn is_initialized(&self) -> bool {
+        return self.read().is_initialized();
+    }
 }
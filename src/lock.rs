@@ -0,0 +1,84 @@
+#[cfg(not(feature = "critical-section"))]
+pub use spin::Mutex;
+
+#[cfg(feature = "critical-section")]
+pub use self::critical_section_mutex::Mutex;
+
+/*
+ * Spinning on single core targets can deadlock when an interrupt handler
+ * allocates while the lock is held, so this lock masks interrupts for as long
+ * as it is held instead.
+ */
+#[cfg(feature = "critical-section")]
+mod critical_section_mutex {
+    use core::{
+        cell::{Cell, UnsafeCell},
+        ops::{Deref, DerefMut},
+    };
+
+    use critical_section::RestoreState;
+
+    pub struct Mutex<T> {
+        locked: Cell<bool>,
+        data: UnsafeCell<T>,
+    }
+
+    unsafe impl<T: Send> Sync for Mutex<T> {}
+    unsafe impl<T: Send> Send for Mutex<T> {}
+
+    impl<T> Mutex<T> {
+        pub const fn new(data: T) -> Self {
+            Mutex {
+                locked: Cell::new(false),
+                data: UnsafeCell::new(data),
+            }
+        }
+
+        pub fn lock(&self) -> MutexGuard<'_, T> {
+            let restore = unsafe { critical_section::acquire() };
+            assert!(!self.locked.replace(true), "Mutex locked re-entrantly");
+            return MutexGuard {
+                mutex: self,
+                restore,
+            };
+        }
+
+        pub fn try_lock(&self) -> Option<MutexGuard<'_, T>> {
+            let restore = unsafe { critical_section::acquire() };
+            if self.locked.replace(true) {
+                unsafe { critical_section::release(restore) };
+                return None;
+            }
+            return Some(MutexGuard {
+                mutex: self,
+                restore,
+            });
+        }
+    }
+
+    pub struct MutexGuard<'a, T> {
+        mutex: &'a Mutex<T>,
+        restore: RestoreState,
+    }
+
+    impl<T> Deref for MutexGuard<'_, T> {
+        type Target = T;
+
+        fn deref(&self) -> &T {
+            return unsafe { &*self.mutex.data.get() };
+        }
+    }
+
+    impl<T> DerefMut for MutexGuard<'_, T> {
+        fn deref_mut(&mut self) -> &mut T {
+            return unsafe { &mut *self.mutex.data.get() };
+        }
+    }
+
+    impl<T> Drop for MutexGuard<'_, T> {
+        fn drop(&mut self) {
+            self.mutex.locked.set(false);
+            unsafe { critical_section::release(self.restore) };
+        }
+    }
+}
@@ -0,0 +1,308 @@
+use core::{alloc::Layout, ptr::NonNull};
+
+use crate::common::{AllocBounds, BAllocator, BAllocatorError};
+
+#[derive(Debug, Clone, Copy)]
+pub enum AllocOp {
+    Alloc { size: usize, align: usize },
+    Dealloc { index: usize },
+    Realloc { index: usize, new_size: usize },
+}
+
+/// `AllocOp` packed small enough to keep a long history of in a fixed
+/// buffer. Ops with sizes or indices too large to pack are not logged.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum LoggedOp {
+    Alloc { size: u32, align: u32 },
+    Dealloc { index: u16 },
+    Realloc { index: u16, new_size: u32 },
+}
+
+impl LoggedOp {
+    fn pack(op: AllocOp) -> Option<LoggedOp> {
+        return Some(match op {
+            AllocOp::Alloc { size, align } => LoggedOp::Alloc {
+                size: size.try_into().ok()?,
+                align: align.try_into().ok()?,
+            },
+            AllocOp::Dealloc { index } => LoggedOp::Dealloc {
+                index: index.try_into().ok()?,
+            },
+            AllocOp::Realloc { index, new_size } => LoggedOp::Realloc {
+                index: index.try_into().ok()?,
+                new_size: new_size.try_into().ok()?,
+            },
+        });
+    }
+}
+
+impl From<LoggedOp> for AllocOp {
+    fn from(op: LoggedOp) -> Self {
+        return match op {
+            LoggedOp::Alloc { size, align } => AllocOp::Alloc {
+                size: size as usize,
+                align: align as usize,
+            },
+            LoggedOp::Dealloc { index } => AllocOp::Dealloc {
+                index: index as usize,
+            },
+            LoggedOp::Realloc { index, new_size } => AllocOp::Realloc {
+                index: index as usize,
+                new_size: new_size as usize,
+            },
+        };
+    }
+}
+
+/// Ring buffer of the most recent ops applied through
+/// `OpDriver::apply_op_logged`, overwriting the oldest once full.
+pub struct OpLog<'a> {
+    buf: &'a mut [LoggedOp],
+    head: usize,
+    len: usize,
+}
+
+impl<'a> OpLog<'a> {
+    pub const fn new(buf: &'a mut [LoggedOp]) -> Self {
+        OpLog {
+            buf,
+            head: 0,
+            len: 0,
+        }
+    }
+
+    pub fn len(&self) -> usize {
+        return self.len;
+    }
+
+    pub fn is_empty(&self) -> bool {
+        return self.len == 0;
+    }
+
+    fn record(&mut self, op: LoggedOp) {
+        if self.buf.is_empty() {
+            return;
+        }
+        let tail = (self.head + self.len) % self.buf.len();
+        self.buf[tail] = op;
+        if self.len == self.buf.len() {
+            self.head = (self.head + 1) % self.buf.len();
+        } else {
+            self.len += 1;
+        }
+    }
+
+    /// The logged ops, oldest first.
+    pub fn ops(&self) -> impl Iterator<Item = LoggedOp> + '_ {
+        return (0..self.len).map(|i| self.buf[(self.head + i) % self.buf.len()]);
+    }
+
+    /// Copies the logged ops, oldest first, into `out`, returning how many
+    /// were copied.
+    pub fn copy_to(&self, out: &mut [LoggedOp]) -> usize {
+        let mut copied = 0;
+        for (slot, op) in out.iter_mut().zip(self.ops()) {
+            *slot = op;
+            copied += 1;
+        }
+        return copied;
+    }
+}
+
+#[derive(Debug)]
+pub enum OpResult {
+    Allocated {
+        index: usize,
+    },
+    Deallocated,
+    Reallocated,
+    /// The op did not make sense, e.g. an invalid layout, an empty slot or a
+    /// full table, and was ignored.
+    Skipped,
+    Failed(BAllocatorError),
+}
+
+#[derive(Clone, Copy)]
+struct Live {
+    ptr: NonNull<u8>,
+    layout: Layout,
+    fill: u8,
+}
+
+/// Drives an allocator through an arbitrary stream of `AllocOp`s, keeping up
+/// to `N` live blocks and panicking as soon as one is out of bounds,
+/// overlaps another or has been written to by someone else.
+pub struct OpDriver<'a, A: BAllocator + AllocBounds, const N: usize> {
+    allocator: &'a A,
+    live: [Option<Live>; N],
+    fills: u8,
+}
+
+impl<'a, A: BAllocator + AllocBounds, const N: usize> OpDriver<'a, A, N> {
+    pub const fn new(allocator: &'a A) -> Self {
+        OpDriver {
+            allocator,
+            live: [None; N],
+            fills: 0,
+        }
+    }
+
+    pub fn live(&self) -> usize {
+        return self.live.iter().flatten().count();
+    }
+
+    /// # Safety
+    /// The allocator must not be used by anything else while driven.
+    pub unsafe fn apply_op(&mut self, op: AllocOp) -> OpResult {
+        unsafe {
+            return match op {
+                AllocOp::Alloc { size, align } => self.alloc(size, align),
+                AllocOp::Dealloc { index } => self.dealloc(index),
+                AllocOp::Realloc { index, new_size } => self.realloc(index, new_size),
+            };
+        }
+    }
+
+    /// Like `apply_op`, also recording `op` in `log` so the sequence can be
+    /// replayed later.
+    ///
+    /// # Safety
+    /// As for `apply_op`.
+    pub unsafe fn apply_op_logged(&mut self, op: AllocOp, log: &mut OpLog) -> OpResult {
+        if let Some(logged) = LoggedOp::pack(op) {
+            log.record(logged);
+        }
+        unsafe {
+            return self.apply_op(op);
+        }
+    }
+
+    unsafe fn alloc(&mut self, size: usize, align: usize) -> OpResult {
+        let Ok(layout) = Layout::from_size_align(size, align) else {
+            return OpResult::Skipped;
+        };
+        let Some(index) = self.live.iter().position(Option::is_none) else {
+            return OpResult::Skipped;
+        };
+
+        unsafe {
+            return match self.allocator.try_allocate(layout) {
+                Ok(ptr) => {
+                    self.track(index, ptr, layout);
+                    OpResult::Allocated { index }
+                }
+                Err(e) => OpResult::Failed(e),
+            };
+        }
+    }
+
+    unsafe fn dealloc(&mut self, index: usize) -> OpResult {
+        let Some(live) = self.take(index) else {
+            return OpResult::Skipped;
+        };
+
+        unsafe {
+            return match self.allocator.try_deallocate(live.ptr, live.layout) {
+                Ok(()) => OpResult::Deallocated,
+                Err(e) => OpResult::Failed(e),
+            };
+        }
+    }
+
+    unsafe fn realloc(&mut self, index: usize, new_size: usize) -> OpResult {
+        let Some(Some(old)) = self.live.get(index).copied() else {
+            return OpResult::Skipped;
+        };
+        let Ok(layout) = Layout::from_size_align(new_size, old.layout.align()) else {
+            return OpResult::Skipped;
+        };
+
+        unsafe {
+            let ptr = match self.allocator.try_allocate(layout) {
+                Ok(ptr) => ptr,
+                Err(e) => return OpResult::Failed(e),
+            };
+            let old = self.take(index).unwrap();
+            ptr.copy_from_nonoverlapping(old.ptr, old.layout.size().min(new_size));
+            self.track(index, ptr, layout);
+
+            return match self.allocator.try_deallocate(old.ptr, old.layout) {
+                Ok(()) => OpResult::Reallocated,
+                Err(e) => OpResult::Failed(e),
+            };
+        }
+    }
+
+    /// Checks `ptr` against the invariants and fills it with a pattern
+    /// unique to the block before recording it.
+    unsafe fn track(&mut self, index: usize, ptr: NonNull<u8>, layout: Layout) {
+        assert!(
+            (ptr.as_ptr() as usize).is_multiple_of(layout.align()),
+            "{ptr:?} not aligned to {layout:?}"
+        );
+        if layout.size() > 0 {
+            let end = ptr.as_ptr() as usize + layout.size() - 1;
+            assert!(
+                self.allocator.contains(ptr)
+                    && self
+                        .allocator
+                        .contains(NonNull::new(end as *mut u8).unwrap()),
+                "{ptr:?} with {layout:?} is out of bounds"
+            );
+        }
+        for other in self.live.iter().flatten() {
+            assert!(
+                !Self::overlaps(ptr, layout, other.ptr, other.layout),
+                "{ptr:?} with {layout:?} overlaps {:?} with {:?}",
+                other.ptr,
+                other.layout
+            );
+        }
+
+        self.fills = self.fills.wrapping_add(1);
+        unsafe { ptr.write_bytes(self.fills, layout.size()) };
+        self.live[index] = Some(Live {
+            ptr,
+            layout,
+            fill: self.fills,
+        });
+    }
+
+    /// Removes the block at `index`, checking its pattern is intact.
+    fn take(&mut self, index: usize) -> Option<Live> {
+        let live = self.live.get_mut(index)?.take()?;
+        let bytes = unsafe { core::slice::from_raw_parts(live.ptr.as_ptr(), live.layout.size()) };
+        assert!(
+            bytes.iter().all(|&byte| byte == live.fill),
+            "{:?} with {:?} was overwritten",
+            live.ptr,
+            live.layout
+        );
+        return Some(live);
+    }
+
+    fn overlaps(a: NonNull<u8>, a_layout: Layout, b: NonNull<u8>, b_layout: Layout) -> bool {
+        let (a, b) = (a.as_ptr() as usize, b.as_ptr() as usize);
+        return a_layout.size() > 0
+            && b_layout.size() > 0
+            && a < b + b_layout.size()
+            && b < a + a_layout.size();
+    }
+}
+
+/// Drives `allocator` through a captured log, e.g. to reproduce a heap
+/// corruption report as a test. The driver is returned so the blocks left
+/// live can be inspected.
+///
+/// # Safety
+/// As for `OpDriver::apply_op`.
+pub unsafe fn replay<'a, A: BAllocator + AllocBounds, const N: usize>(
+    log: &[LoggedOp],
+    allocator: &'a A,
+) -> OpDriver<'a, A, N> {
+    let mut driver = OpDriver::new(allocator);
+    for &op in log {
+        unsafe { driver.apply_op(op.into()) };
+    }
+    return driver;
+}
@@ -5,17 +5,41 @@ extern crate std;
 use core::{
     alloc::{GlobalAlloc, Layout},
     mem::MaybeUninit,
+    ptr::NonNull,
+    sync::atomic::{AtomicBool, AtomicUsize, Ordering},
 };
-use std::{print, println, sync::Arc};
+use std::{format, print, println, sync::Arc, vec, vec::Vec};
 
+#[cfg(not(feature = "oom_panic"))]
+use log::{Level, LevelFilter, Log, Metadata, Record};
 use loom::thread;
 
+#[cfg(not(feature = "oom_panic"))]
+use crate::common::GlobalAllocExt;
 use crate::{
-    buddy_alloc::LockedBuddyAlloc,
-    bump_alloc::{ConstBumpAlloc, LockedBumpAlloc, LocklessBumpAlloc},
-    common::{AllocInit, BAllocator},
+    bench::run_workload,
+    buddy_alloc::{
+        BuddyBuilder, LockedBuddy, LockedBuddyAlloc, MAX_ORDER, MIN_ORDER, NR_MAX_ORDER, PAGE_SIZE,
+        TINY_ORDER, TINY_SLOT_SIZE,
+    },
+    bump_alloc::{
+        BumpBuilder, ConstBump, ConstBumpAlloc, LockedBumpAlloc, LocklessBumpAlloc,
+        RwLockedBumpAlloc,
+    },
+    common::{
+        AllocBounds, AllocInit, AllocMetrics, AllocState, BAllocator, BAllocatorError,
+        BAllocatorExt, IntegrityError, InterruptGuard, align_up, dangling,
+    },
+    fallback::FallbackAlloc,
+    heap_box::HeapBox,
+    linked_list_alloc::{
+        CoalescePolicy, FitStrategy, GUARD_FILL, LinkedListBuilder, LockedLinkedListAlloc,
+    },
+    ops::{AllocOp, LoggedOp, OpDriver, OpLog, OpResult, replay},
+    tlsf_alloc::LockedTlsfAlloc,
 };
 
+#[allow(dead_code)]
 pub unsafe fn print_mem(heap: *const u8, len: usize) {
     unsafe {
         for i in 0..len {
@@ -31,260 +55,4823 @@ pub unsafe fn print_mem(heap: *const u8, len: usize) {
 #[repr(align(8))]
 struct Heap8Byte<const S: usize>([MaybeUninit<u8>; S]);
 
+#[repr(align(4096))]
+struct Heap4096Byte<const S: usize>([MaybeUninit<u8>; S]);
+
+#[cfg(not(feature = "oom_panic"))]
+static ALLOC_ERROR_LOGGED: AtomicBool = AtomicBool::new(false);
+
+#[cfg(not(feature = "oom_panic"))]
+struct AllocErrorLogger;
+
+#[cfg(not(feature = "oom_panic"))]
+impl Log for AllocErrorLogger {
+    fn enabled(&self, metadata: &Metadata) -> bool {
+        return metadata.level() <= Level::Error;
+    }
+
+    fn log(&self, record: &Record) {
+        if record.level() == Level::Error
+            && format!("{}", record.args()).starts_with("GlobalAlloc, Allocation error")
+        {
+            ALLOC_ERROR_LOGGED.store(true, Ordering::SeqCst);
+        }
+    }
+
+    fn flush(&self) {}
+}
+
+/// Allocates `layout` until the allocator runs out, frees everything and
+/// checks the allocator can serve the same amount again.
+#[cfg(not(feature = "oom_panic"))]
+unsafe fn exhaust_and_release<A: GlobalAlloc>(allocator: &A, layout: Layout) -> usize {
+    let mut ptrs = Vec::new();
+
+    unsafe {
+        loop {
+            let ptr = allocator.alloc(layout);
+            if ptr.is_null() {
+                break;
+            }
+            assert_eq!(ptr as usize % layout.align(), 0);
+            ptrs.push(ptr);
+        }
+        assert!(!ptrs.is_empty());
+        assert!(allocator.alloc(layout).is_null());
+
+        for ptr in ptrs.iter().rev() {
+            allocator.dealloc(*ptr, layout);
+        }
+    }
+    return ptrs.len();
+}
+
+#[cfg(not(feature = "oom_panic"))]
+#[test]
+fn bump_locked_boundary_conditions() {
+    const HEAP_SIZE: usize = 100;
+    static mut HEAP_MEM: Heap8Byte<HEAP_SIZE> = Heap8Byte([MaybeUninit::uninit(); HEAP_SIZE]);
+
+    let allocator = LockedBumpAlloc::new();
+    unsafe { allocator.init(&raw mut HEAP_MEM.0 as usize, HEAP_SIZE) };
+
+    let layout = Layout::from_size_align(10, 1).unwrap();
+    unsafe {
+        assert_eq!(exhaust_and_release(&allocator, layout), 10);
+        assert_eq!(allocator.allocations(), 0);
+        assert_eq!(allocator.remaining(), HEAP_SIZE);
+        assert_eq!(exhaust_and_release(&allocator, layout), 10);
+    }
+}
+
+#[cfg(not(feature = "oom_panic"))]
+#[test]
+fn linked_list_locked_boundary_conditions() {
+    const HEAP_SIZE: usize = 64;
+    static mut HEAP_MEM: Heap8Byte<HEAP_SIZE> = Heap8Byte([MaybeUninit::uninit(); HEAP_SIZE]);
+
+    let allocator = LockedLinkedListAlloc::new();
+    unsafe { allocator.init(&raw mut HEAP_MEM.0 as usize, HEAP_SIZE) };
+
+    let layout = Layout::from_size_align(10, 1).unwrap();
+    unsafe {
+        let count = exhaust_and_release(&allocator, layout);
+        assert_eq!(exhaust_and_release(&allocator, layout), count);
+    }
+}
+
+#[cfg(not(feature = "oom_panic"))]
+#[test]
+fn buddy_locked_boundary_conditions() {
+    const HEAP_SIZE: usize = 512;
+    static mut HEAP_MEM: Heap8Byte<HEAP_SIZE> = Heap8Byte([MaybeUninit::uninit(); HEAP_SIZE]);
+
+    let allocator = LockedBuddyAlloc::new();
+    unsafe { allocator.init(&raw mut HEAP_MEM.0 as usize, HEAP_SIZE) };
+
+    let layout = Layout::from_size_align(64, 8).unwrap();
+    unsafe {
+        assert_eq!(exhaust_and_release(&allocator, layout), HEAP_SIZE / 64);
+    }
+}
+
+#[cfg(debug_assertions)]
+#[test]
+#[should_panic(expected = "Free list of order 8 holds 1 blocks but nr_free is 2")]
+fn buddy_free_list_consistency() {
+    const HEAP_SIZE: usize = 4096;
+    static mut HEAP_MEM: Heap4096Byte<HEAP_SIZE> = Heap4096Byte([MaybeUninit::uninit(); HEAP_SIZE]);
+
+    let allocator = LockedBuddyAlloc::new();
+    unsafe { allocator.init(&raw mut HEAP_MEM.0 as usize, HEAP_SIZE) };
+
+    let layout = Layout::from_size_align(64, 8).unwrap();
+    assert!(!unsafe { allocator.alloc(layout) }.is_null());
+
+    allocator.desync_free_count(8);
+    unsafe { allocator.alloc(layout) };
+}
+
+#[test]
+fn new_with_static_heap() {
+    const HEAP_SIZE: usize = 4096;
+    static mut BUMP_MEM: Heap8Byte<HEAP_SIZE> = Heap8Byte([MaybeUninit::uninit(); HEAP_SIZE]);
+    static mut BUDDY_MEM: Heap4096Byte<HEAP_SIZE> =
+        Heap4096Byte([MaybeUninit::uninit(); HEAP_SIZE]);
+
+    // Heaps at a fixed address, as on embedded targets, can be set up entirely
+    // at compile time.
+    static _FIXED_BUMP: LockedBumpAlloc =
+        unsafe { LockedBumpAlloc::new_with_static_heap(0x2000_0000, HEAP_SIZE) };
+    static _FIXED_BUDDY: LockedBuddyAlloc =
+        unsafe { LockedBuddyAlloc::new_with_static_heap(0x2000_0000, HEAP_SIZE) };
+
+    let layout = Layout::from_size_align(64, 8).unwrap();
+    unsafe {
+        let bump = LockedBumpAlloc::new_with_static_heap(&raw mut BUMP_MEM.0 as usize, HEAP_SIZE);
+        assert!(bump.is_initialized());
+        assert!(!bump.alloc(layout).is_null());
+        assert_eq!(bump.remaining(), HEAP_SIZE - 64);
+
+        let buddy =
+            LockedBuddyAlloc::new_with_static_heap(&raw mut BUDDY_MEM.0 as usize, HEAP_SIZE);
+        assert!(buddy.is_initialized());
+        assert!(buddy.can_allocate(Layout::from_size_align(HEAP_SIZE, 8).unwrap()));
+        assert_eq!(buddy.free_counts()[9], 1);
+        let ptr = buddy.alloc(layout);
+        assert!(!ptr.is_null());
+        buddy.dealloc(ptr, layout);
+        assert_eq!(buddy.free_counts()[9], 1);
+    }
+}
+
+unsafe fn alloc_typed<A: BAllocator>(allocator: &A) {
+    unsafe {
+        let mut value = allocator.try_allocate_typed::<u64>().unwrap();
+        assert!(value.as_ptr().is_aligned());
+        value.as_mut().write(0xDEAD_BEEF);
+        assert_eq!(value.as_ref().assume_init(), 0xDEAD_BEEF);
+        allocator.try_deallocate_typed(value).unwrap();
+
+        let array = allocator.try_allocate_typed::<[u32; 16]>().unwrap();
+        array.cast::<[u32; 16]>().write([7; 16]);
+        assert_eq!(array.as_ref().assume_init_ref().iter().sum::<u32>(), 7 * 16);
+        allocator.try_deallocate_typed(array).unwrap();
+    }
+}
+
+#[test]
+fn dyn_ballocator() {
+    const HEAP_SIZE: usize = 256;
+    static mut BUMP_MEM: Heap8Byte<HEAP_SIZE> = Heap8Byte([MaybeUninit::uninit(); HEAP_SIZE]);
+    static mut BUDDY_MEM: Heap4096Byte<HEAP_SIZE> =
+        Heap4096Byte([MaybeUninit::uninit(); HEAP_SIZE]);
+    static mut LIST_MEM: Heap8Byte<HEAP_SIZE> = Heap8Byte([MaybeUninit::uninit(); HEAP_SIZE]);
+
+    let bump = LockedBumpAlloc::new();
+    let buddy = LockedBuddyAlloc::new();
+    let list = LockedLinkedListAlloc::new();
+    unsafe {
+        bump.init(&raw mut BUMP_MEM.0 as usize, HEAP_SIZE);
+        buddy.init(&raw mut BUDDY_MEM.0 as usize, HEAP_SIZE);
+        list.init(&raw mut LIST_MEM.0 as usize, HEAP_SIZE);
+    }
+    let allocators: [&dyn BAllocator; 3] = [&bump, &buddy, &list];
+
+    let layout = Layout::from_size_align(16, 8).unwrap();
+    for allocator in allocators {
+        unsafe {
+            let ptr = allocator.try_allocate(layout).unwrap();
+            ptr.write_bytes(0x42, 16);
+            let grown = allocator.try_reallocate(ptr, layout, 64).unwrap();
+            assert!(
+                core::slice::from_raw_parts(grown.as_ptr(), 16)
+                    .iter()
+                    .all(|&b| b == 0x42)
+            );
+            allocator
+                .try_deallocate(grown, Layout::from_size_align(64, 8).unwrap())
+                .unwrap();
+
+            // The typed helpers still work through the trait object.
+            let value = allocator.try_allocate_typed::<u64>().unwrap();
+            allocator.try_deallocate_typed(value).unwrap();
+        }
+    }
+}
+
+#[test]
+fn linked_list_impossible_alignment() {
+    const HEAP_SIZE: usize = 64;
+    static mut HEAP_MEM: Heap4096Byte<4096> = Heap4096Byte([MaybeUninit::uninit(); 4096]);
+
+    // Offset so no address in the heap is 4096 aligned.
+    let start = unsafe { &raw mut HEAP_MEM.0 as usize } + 8;
+    let allocator = LockedLinkedListAlloc::new();
+    unsafe { allocator.init(start, HEAP_SIZE) };
+
+    unsafe {
+        let layout = Layout::from_size_align(8, 4096).unwrap();
+        assert!(matches!(
+            allocator.try_allocate(layout),
+            Err(BAllocatorError::Alignment(l)) if l == layout
+        ));
+
+        // Genuine exhaustion is still reported as such.
+        let layout = Layout::from_size_align(HEAP_SIZE * 2, 8).unwrap();
+        assert!(allocator.try_allocate(layout).unwrap_err().is_oom());
+        let layout = Layout::from_size_align(HEAP_SIZE, 8).unwrap();
+        let ptr = allocator.try_allocate(layout).unwrap();
+        assert!(allocator.try_allocate(layout).unwrap_err().is_oom());
+        allocator.try_deallocate(ptr, layout).unwrap();
+    }
+}
+
+/// Replays a fixed op stream, `OpDriver` panics on any overlapping, out of
+/// bounds or corrupted block.
+unsafe fn replay_ops<A: BAllocator + AllocBounds>(allocator: &A) {
+    let ops = [
+        AllocOp::Alloc { size: 24, align: 8 },
+        AllocOp::Alloc {
+            size: 100,
+            align: 16,
+        },
+        AllocOp::Alloc { size: 0, align: 4 },
+        AllocOp::Alloc { size: 7, align: 1 },
+        AllocOp::Dealloc { index: 1 },
+        AllocOp::Alloc {
+            size: 64,
+            align: 64,
+        },
+        AllocOp::Realloc {
+            index: 0,
+            new_size: 200,
+        },
+        AllocOp::Realloc {
+            index: 3,
+            new_size: 2,
+        },
+        AllocOp::Dealloc { index: 5 },
+        AllocOp::Alloc { size: 3, align: 3 },
+        AllocOp::Alloc { size: 48, align: 8 },
+        AllocOp::Dealloc { index: 2 },
+        AllocOp::Realloc {
+            index: 1,
+            new_size: 512,
+        },
+        AllocOp::Dealloc { index: 0 },
+    ];
+    let mut driver = OpDriver::<A, 8>::new(allocator);
+
+    unsafe {
+        for op in ops {
+            if let OpResult::Failed(e) = driver.apply_op(op) {
+                panic!("{op:?} failed: {e:?}");
+            }
+        }
+        assert_eq!(driver.live(), 3);
+        assert!(matches!(
+            driver.apply_op(AllocOp::Dealloc { index: 0 }),
+            OpResult::Skipped
+        ));
+        assert!(matches!(
+            driver.apply_op(AllocOp::Alloc { size: 8, align: 3 }),
+            OpResult::Skipped
+        ));
+    }
+}
+
+#[test]
+fn op_log_replay() {
+    const HEAP_SIZE: usize = 4096;
+    static mut RECORD_MEM: Heap8Byte<HEAP_SIZE> = Heap8Byte([MaybeUninit::uninit(); HEAP_SIZE]);
+    static mut REPLAY_MEM: Heap8Byte<HEAP_SIZE> = Heap8Byte([MaybeUninit::uninit(); HEAP_SIZE]);
+
+    let recorded = LockedBuddyAlloc::new();
+    let replayed = LockedBuddyAlloc::new();
+    let mut buf = [LoggedOp::Dealloc { index: 0 }; 64];
+    let mut log = OpLog::new(&mut buf);
+
+    unsafe {
+        recorded.init(&raw mut RECORD_MEM.0 as usize, HEAP_SIZE);
+        replayed.init(&raw mut REPLAY_MEM.0 as usize, HEAP_SIZE);
+
+        let mut driver = OpDriver::<_, 8>::new(&recorded);
+        let mut seed: u32 = 0xC0FF_EE00;
+        for _ in 0..40 {
+            seed = seed.wrapping_mul(1_103_515_245).wrapping_add(12_345);
+            let index = (seed >> 16) as usize % 8;
+            let op = match seed % 3 {
+                0 => AllocOp::Dealloc { index },
+                1 => AllocOp::Realloc {
+                    index,
+                    new_size: (seed >> 8) as usize % 300,
+                },
+                _ => AllocOp::Alloc {
+                    size: 1 + (seed >> 8) as usize % 300,
+                    align: 8,
+                },
+            };
+            driver.apply_op_logged(op, &mut log);
+        }
+        assert_eq!(log.len(), 40);
+
+        let mut ops = [LoggedOp::Dealloc { index: 0 }; 64];
+        let len = log.copy_to(&mut ops);
+        let replay = replay::<_, 8>(&ops[..len], &replayed);
+
+        assert_eq!(replay.live(), driver.live());
+        assert_eq!(replayed.free_counts(), recorded.free_counts());
+    }
+
+    // Once full, the oldest ops are dropped first.
+    let mut small = [LoggedOp::Dealloc { index: 0 }; 2];
+    let mut log = OpLog::new(&mut small);
+    let mut driver = OpDriver::<_, 1>::new(&recorded);
+    for index in 1..=3 {
+        unsafe { driver.apply_op_logged(AllocOp::Dealloc { index }, &mut log) };
+    }
+    let mut ops = log.ops();
+    assert_eq!(ops.next(), Some(LoggedOp::Dealloc { index: 2 }));
+    assert_eq!(ops.next(), Some(LoggedOp::Dealloc { index: 3 }));
+    assert_eq!(ops.next(), None);
+}
+
+#[test]
+fn allocators_replay_ops() {
+    const HEAP_SIZE: usize = 4096;
+    static mut BUMP_MEM: Heap8Byte<HEAP_SIZE> = Heap8Byte([MaybeUninit::uninit(); HEAP_SIZE]);
+    static mut BUDDY_MEM: Heap4096Byte<HEAP_SIZE> =
+        Heap4096Byte([MaybeUninit::uninit(); HEAP_SIZE]);
+    static mut LIST_MEM: Heap8Byte<HEAP_SIZE> = Heap8Byte([MaybeUninit::uninit(); HEAP_SIZE]);
+    static mut TLSF_MEM: Heap8Byte<HEAP_SIZE> = Heap8Byte([MaybeUninit::uninit(); HEAP_SIZE]);
+
+    unsafe {
+        let bump = LockedBumpAlloc::new();
+        bump.init(&raw mut BUMP_MEM.0 as usize, HEAP_SIZE);
+        replay_ops(&bump);
+
+        let buddy = LockedBuddyAlloc::new();
+        buddy.init(&raw mut BUDDY_MEM.0 as usize, HEAP_SIZE);
+        replay_ops(&buddy);
+
+        let list = LockedLinkedListAlloc::new();
+        list.init(&raw mut LIST_MEM.0 as usize, HEAP_SIZE);
+        replay_ops(&list);
+
+        let tlsf = LockedTlsfAlloc::new();
+        tlsf.init(&raw mut TLSF_MEM.0 as usize, HEAP_SIZE);
+        replay_ops(&tlsf);
+    }
+}
+
+#[cfg(not(feature = "oom_panic"))]
+#[test]
+fn allocators_uninitialized() {
+    let layout = Layout::from_size_align(8, 8).unwrap();
+    unsafe {
+        assert!(matches!(
+            LockedBumpAlloc::new().try_allocate(layout),
+            Err(BAllocatorError::Uninitialized)
+        ));
+        assert!(matches!(
+            RwLockedBumpAlloc::new().try_allocate(layout),
+            Err(BAllocatorError::Uninitialized)
+        ));
+        assert!(matches!(
+            LockedBuddyAlloc::new().try_allocate(layout),
+            Err(BAllocatorError::Uninitialized)
+        ));
+        assert!(matches!(
+            LockedLinkedListAlloc::new().try_allocate(layout),
+            Err(BAllocatorError::Uninitialized)
+        ));
+        assert!(matches!(
+            LockedTlsfAlloc::new().try_allocate(layout),
+            Err(BAllocatorError::Uninitialized)
+        ));
+        assert!(LockedBumpAlloc::new().alloc(layout).is_null());
+    }
+}
+
+#[test]
+fn allocators_typed() {
+    const HEAP_SIZE: usize = 4096;
+    static mut BUMP_MEM: Heap8Byte<HEAP_SIZE> = Heap8Byte([MaybeUninit::uninit(); HEAP_SIZE]);
+    static mut BUDDY_MEM: Heap4096Byte<HEAP_SIZE> =
+        Heap4096Byte([MaybeUninit::uninit(); HEAP_SIZE]);
+    static mut LIST_MEM: Heap8Byte<HEAP_SIZE> = Heap8Byte([MaybeUninit::uninit(); HEAP_SIZE]);
+    static mut TLSF_MEM: Heap8Byte<HEAP_SIZE> = Heap8Byte([MaybeUninit::uninit(); HEAP_SIZE]);
+
+    unsafe {
+        let bump = LockedBumpAlloc::new();
+        bump.init(&raw mut BUMP_MEM.0 as usize, HEAP_SIZE);
+        alloc_typed(&bump);
+        assert_eq!(bump.allocations(), 0);
+
+        let buddy = LockedBuddyAlloc::new();
+        buddy.init(&raw mut BUDDY_MEM.0 as usize, HEAP_SIZE);
+        alloc_typed(&buddy);
+
+        let list = LockedLinkedListAlloc::new();
+        list.init(&raw mut LIST_MEM.0 as usize, HEAP_SIZE);
+        alloc_typed(&list);
+
+        let tlsf = LockedTlsfAlloc::new();
+        tlsf.init(&raw mut TLSF_MEM.0 as usize, HEAP_SIZE);
+        alloc_typed(&tlsf);
+        assert_eq!(tlsf.allocations(), 0);
+    }
+}
+
+/// Checks `reserve` succeeds for `bytes` but not a byte more, and that the
+/// reservation can then actually be allocated.
+unsafe fn assert_reserve_boundary<A: GlobalAlloc + BAllocator>(allocator: &A, bytes: usize) {
+    assert!(allocator.reserve(bytes).is_ok());
+    assert!(allocator.reserve(bytes + 1).unwrap_err().is_oom());
+
+    let layout = Layout::from_size_align(bytes, 1).unwrap();
+    unsafe {
+        let ptr = allocator.alloc(layout);
+        assert!(!ptr.is_null());
+        allocator.dealloc(ptr, layout);
+    }
+}
+
+#[test]
+fn allocators_reserve() {
+    const HEAP_SIZE: usize = 4096;
+    static mut BUMP_MEM: Heap8Byte<HEAP_SIZE> = Heap8Byte([MaybeUninit::uninit(); HEAP_SIZE]);
+    static mut BUDDY_MEM: Heap4096Byte<HEAP_SIZE> =
+        Heap4096Byte([MaybeUninit::uninit(); HEAP_SIZE]);
+    static mut LIST_MEM: Heap8Byte<HEAP_SIZE> = Heap8Byte([MaybeUninit::uninit(); HEAP_SIZE]);
+    static mut TLSF_MEM: Heap8Byte<HEAP_SIZE> = Heap8Byte([MaybeUninit::uninit(); HEAP_SIZE]);
+
+    unsafe {
+        let bump = LockedBumpAlloc::new();
+        bump.init(&raw mut BUMP_MEM.0 as usize, HEAP_SIZE);
+        let ptr = bump.alloc(Layout::from_size_align(96, 1).unwrap());
+        assert_reserve_boundary(&bump, HEAP_SIZE - 96);
+        bump.dealloc(ptr, Layout::from_size_align(96, 1).unwrap());
+
+        assert_reserve_boundary(&ConstBumpAlloc::<HEAP_SIZE>::new(), HEAP_SIZE);
+
+        let buddy = LockedBuddyAlloc::new();
+        buddy.init(&raw mut BUDDY_MEM.0 as usize, HEAP_SIZE);
+        assert_reserve_boundary(&buddy, HEAP_SIZE);
+        // Once split, only half the heap is available in one piece.
+        let ptr = buddy.alloc(Layout::new::<u64>());
+        assert_reserve_boundary(&buddy, HEAP_SIZE / 2);
+        buddy.dealloc(ptr, Layout::new::<u64>());
+
+        let list = LockedLinkedListAlloc::new();
+        list.init(&raw mut LIST_MEM.0 as usize, HEAP_SIZE);
+        assert_reserve_boundary(&list, HEAP_SIZE);
+
+        // TLSF block overhead and size classes make the boundary less obvious.
+        let tlsf = LockedTlsfAlloc::new();
+        tlsf.init(&raw mut TLSF_MEM.0 as usize, HEAP_SIZE);
+        let largest = (1..=HEAP_SIZE)
+            .rev()
+            .find(|&bytes| tlsf.reserve(bytes).is_ok())
+            .unwrap();
+        assert_reserve_boundary(&tlsf, largest);
+    }
+}
+
+#[test]
+fn rwlocked_stats_exclude_allocation() {
+    const HEAP_SIZE: usize = 128;
+    static mut HEAP_MEM: Heap8Byte<HEAP_SIZE> = Heap8Byte([MaybeUninit::uninit(); HEAP_SIZE]);
+
+    let allocator = RwLockedBumpAlloc::new();
+    unsafe { allocator.init(&raw mut HEAP_MEM.0 as usize, HEAP_SIZE) };
+
+    {
+        let stats = allocator.alloc.read();
+        // Other readers can still query stats while a read guard is held...
+        assert_eq!(allocator.remaining(), HEAP_SIZE);
+        assert_eq!(stats.allocations(), 0);
+        // ...but an allocation needs the write lock and cannot proceed.
+        assert!(allocator.alloc.try_write().is_none());
+    }
+
+    let ptr = unsafe { allocator.alloc(Layout::from_size_align(16, 8).unwrap()) };
+    assert!(!ptr.is_null());
+
+    {
+        let _allocating = allocator.alloc.write();
+        assert!(allocator.alloc.try_read().is_none());
+    }
+
+    assert_eq!(allocator.allocations(), 1);
+    assert_eq!(allocator.remaining(), HEAP_SIZE - 16);
+}
+
+#[cfg(not(feature = "oom_panic"))]
+#[test]
+fn tlsf_fragmentation() {
+    const HEAP_SIZE: usize = 4096;
+    static mut HEAP_MEM: Heap8Byte<HEAP_SIZE> = Heap8Byte([MaybeUninit::uninit(); HEAP_SIZE]);
+
+    let allocator = LockedTlsfAlloc::new();
+    unsafe { allocator.init(&raw mut HEAP_MEM.0 as usize, HEAP_SIZE) };
+    let initial = allocator.remaining();
+
+    let small = Layout::from_size_align(32, 8).unwrap();
+    let large = Layout::from_size_align(64, 8).unwrap();
+    let mut ptrs = Vec::new();
+
+    unsafe {
+        loop {
+            let ptr = allocator.alloc(small);
+            if ptr.is_null() {
+                break;
+            }
+            ptrs.push(ptr);
+        }
+        let count = ptrs.len();
+        assert_eq!(allocator.allocations(), count);
+
+        // Free every other block, leaving the heap as isolated 32 byte holes.
+        for ptr in ptrs.iter().step_by(2) {
+            allocator.dealloc(*ptr, small);
+        }
+        let holes = count.div_ceil(2);
+        assert!(allocator.remaining() >= holes * small.size());
+        assert!(allocator.alloc(large).is_null());
+
+        // Every hole is found again and nothing more.
+        for i in (0..count).step_by(2) {
+            ptrs[i] = allocator.alloc(small);
+            assert!(!ptrs[i].is_null());
+        }
+        assert!(allocator.alloc(small).is_null());
+
+        for ptr in ptrs.iter() {
+            allocator.dealloc(*ptr, small);
+        }
+    }
+
+    assert_eq!(allocator.allocations(), 0);
+    assert_eq!(allocator.remaining(), initial);
+
+    // Only possible once the holes have been merged back together.
+    unsafe {
+        let half = Layout::from_size_align(HEAP_SIZE / 2, 8).unwrap();
+        let ptr = allocator.alloc(half);
+        assert!(!ptr.is_null());
+        allocator.dealloc(ptr, half);
+    }
+}
+
+#[cfg(not(feature = "oom_panic"))]
+#[test]
+fn tlsf_over_aligned() {
+    const HEAP_SIZE: usize = 4096;
+    static mut HEAP_MEM: Heap8Byte<HEAP_SIZE> = Heap8Byte([MaybeUninit::uninit(); HEAP_SIZE]);
+
+    let allocator = LockedTlsfAlloc::new();
+    unsafe { allocator.init(&raw mut HEAP_MEM.0 as usize, HEAP_SIZE) };
+    let initial = allocator.remaining();
+
+    unsafe {
+        let count = exhaust_and_release(&allocator, Layout::from_size_align(24, 256).unwrap());
+        assert!(count > 1);
+        assert_eq!(allocator.remaining(), initial);
+        exhaust_and_release(&allocator, Layout::from_size_align(100, 64).unwrap());
+        assert_eq!(allocator.remaining(), initial);
+    }
+}
+
+#[test]
+#[should_panic(expected = "Heap end address overflowed")]
+fn bump_locked_init_overflow() {
+    let allocator = LockedBumpAlloc::new();
+    unsafe { allocator.init(usize::MAX - 10, 100) };
+}
+
+#[test]
+#[should_panic(expected = "Heap end address overflowed")]
+fn bump_lockless_init_overflow() {
+    let allocator = LocklessBumpAlloc::new();
+    unsafe { allocator.init(usize::MAX - 10, 100) };
+}
+
+/// Checks `contains` against a pointer handed out by the allocator and the
+/// addresses directly around `[start, start + size)`.
+unsafe fn assert_bounds<A: GlobalAlloc + AllocBounds>(allocator: &A, start: usize, size: usize) {
+    let inside = unsafe { allocator.alloc(Layout::from_size_align(8, 8).unwrap()) };
+    assert!(allocator.contains(NonNull::new(inside).unwrap()));
+    assert!(allocator.contains(NonNull::new(start as *mut u8).unwrap()));
+    assert!(allocator.contains(NonNull::new((start + size - 1) as *mut u8).unwrap()));
+    assert!(!allocator.contains(NonNull::new((start - 1) as *mut u8).unwrap()));
+    assert!(!allocator.contains(NonNull::new((start + size) as *mut u8).unwrap()));
+}
+
+#[test]
+fn allocators_contains() {
+    const HEAP_SIZE: usize = 256;
+    static mut BUMP_MEM: Heap8Byte<HEAP_SIZE> = Heap8Byte([MaybeUninit::uninit(); HEAP_SIZE]);
+    static mut LOCKLESS_MEM: Heap8Byte<HEAP_SIZE> = Heap8Byte([MaybeUninit::uninit(); HEAP_SIZE]);
+    static mut BUDDY_MEM: Heap8Byte<HEAP_SIZE> = Heap8Byte([MaybeUninit::uninit(); HEAP_SIZE]);
+    static mut LIST_MEM: Heap8Byte<HEAP_SIZE> = Heap8Byte([MaybeUninit::uninit(); HEAP_SIZE]);
+    static mut TLSF_MEM: Heap8Byte<HEAP_SIZE> = Heap8Byte([MaybeUninit::uninit(); HEAP_SIZE]);
+
+    unsafe {
+        let bump = LockedBumpAlloc::new();
+        let start = &raw mut BUMP_MEM.0 as usize;
+        assert!(!bump.contains(NonNull::new(start as *mut u8).unwrap()));
+        bump.init(start, HEAP_SIZE);
+        assert_bounds(&bump, start, HEAP_SIZE);
+
+        let lockless = LocklessBumpAlloc::new();
+        let start = &raw mut LOCKLESS_MEM.0 as usize;
+        assert!(!lockless.contains(NonNull::new(start as *mut u8).unwrap()));
+        lockless.init(start, HEAP_SIZE);
+        assert_bounds(&lockless, start, HEAP_SIZE);
+
+        let buddy = LockedBuddyAlloc::new();
+        let start = &raw mut BUDDY_MEM.0 as usize;
+        buddy.init(start, HEAP_SIZE);
+        assert_bounds(&buddy, start, HEAP_SIZE);
+
+        let list = LockedLinkedListAlloc::new();
+        let start = &raw mut LIST_MEM.0 as usize;
+        list.init(start, HEAP_SIZE);
+        assert_bounds(&list, start, HEAP_SIZE);
+
+        let tlsf = LockedTlsfAlloc::new();
+        let start = &raw mut TLSF_MEM.0 as usize;
+        tlsf.init(start, HEAP_SIZE);
+        assert_bounds(&tlsf, start, HEAP_SIZE);
+
+        let bconst = ConstBumpAlloc::<HEAP_SIZE>::new();
+        let inside = bconst.alloc(Layout::from_size_align(8, 1).unwrap());
+        assert!(bconst.contains(NonNull::new(inside).unwrap()));
+        assert!(!bconst.contains(NonNull::new(&raw mut BUMP_MEM.0 as *mut u8).unwrap()));
+    }
+}
+
+/// Interleaves small unaligned allocations with over-aligned ones so the
+/// debug alignment assertion in `try_allocate` trips on any regression.
+unsafe fn alloc_over_aligned<A: GlobalAlloc>(allocator: &A) {
+    for align in [16, 64, 256] {
+        unsafe {
+            let filler = allocator.alloc(Layout::from_size_align(1, 1).unwrap());
+            assert!(!filler.is_null());
+            let ptr = allocator.alloc(Layout::from_size_align(1, align).unwrap());
+            assert!(!ptr.is_null());
+            assert_eq!(ptr as usize % align, 0);
+        }
+    }
+}
+
+#[test]
+fn allocators_over_aligned() {
+    const HEAP_SIZE: usize = 4096;
+    static mut BUMP_MEM: Heap8Byte<HEAP_SIZE> = Heap8Byte([MaybeUninit::uninit(); HEAP_SIZE]);
+    static mut LOCKLESS_MEM: Heap8Byte<HEAP_SIZE> = Heap8Byte([MaybeUninit::uninit(); HEAP_SIZE]);
+    static mut BUDDY_MEM: Heap4096Byte<HEAP_SIZE> =
+        Heap4096Byte([MaybeUninit::uninit(); HEAP_SIZE]);
+    static mut LIST_MEM: Heap8Byte<HEAP_SIZE> = Heap8Byte([MaybeUninit::uninit(); HEAP_SIZE]);
+    static mut TLSF_MEM: Heap8Byte<HEAP_SIZE> = Heap8Byte([MaybeUninit::uninit(); HEAP_SIZE]);
+
+    unsafe {
+        let bump = LockedBumpAlloc::new();
+        bump.init(&raw mut BUMP_MEM.0 as usize, HEAP_SIZE);
+        alloc_over_aligned(&bump);
+
+        let lockless = LocklessBumpAlloc::new();
+        lockless.init(&raw mut LOCKLESS_MEM.0 as usize, HEAP_SIZE);
+        alloc_over_aligned(&lockless);
+
+        let buddy = LockedBuddyAlloc::new();
+        buddy.init(&raw mut BUDDY_MEM.0 as usize, HEAP_SIZE);
+        alloc_over_aligned(&buddy);
+
+        let list = LockedLinkedListAlloc::new();
+        list.init(&raw mut LIST_MEM.0 as usize, HEAP_SIZE);
+        alloc_over_aligned(&list);
+
+        let tlsf = LockedTlsfAlloc::new();
+        tlsf.init(&raw mut TLSF_MEM.0 as usize, HEAP_SIZE);
+        alloc_over_aligned(&tlsf);
+
+        alloc_over_aligned(&ConstBumpAlloc::<HEAP_SIZE>::new());
+    }
+}
+
+#[test]
+fn buddy_merge_threshold() {
+    const HEAP_SIZE: usize = 512;
+    static mut EAGER_MEM: Heap4096Byte<HEAP_SIZE> =
+        Heap4096Byte([MaybeUninit::uninit(); HEAP_SIZE]);
+    static mut LAZY_MEM: Heap4096Byte<HEAP_SIZE> = Heap4096Byte([MaybeUninit::uninit(); HEAP_SIZE]);
+
+    let layout = Layout::from_size_align(8, 8).unwrap();
+    let ping_pong = |allocator: &LockedBuddyAlloc| unsafe {
+        for _ in 0..10 {
+            let ptr = allocator.alloc(layout);
+            assert!(!ptr.is_null());
+            allocator.dealloc(ptr, layout);
+        }
+    };
+
+    let eager = LockedBuddyAlloc::new();
+    unsafe { eager.init(&raw mut EAGER_MEM.0 as usize, HEAP_SIZE) };
+    ping_pong(&eager);
+    // Every free merges all the way back up to the order 6 block.
+    assert_eq!(eager.merges(), 10 * 6);
+
+    let lazy = LockedBuddyAlloc::new();
+    lazy.set_merge_threshold(2);
+    unsafe { lazy.init(&raw mut LAZY_MEM.0 as usize, HEAP_SIZE) };
+    ping_pong(&lazy);
+    assert_eq!(lazy.merges(), 0);
+
+    // The deferred merges happen once a larger block is actually needed.
+    unsafe {
+        let whole = Layout::from_size_align(HEAP_SIZE, 8).unwrap();
+        let ptr = lazy.alloc(whole);
+        assert!(!ptr.is_null());
+        assert_eq!(lazy.merges(), 6);
+        lazy.dealloc(ptr, whole);
+    }
+}
+
+#[cfg(not(feature = "oom_panic"))]
+#[test]
+fn linked_list_allocate_at() {
+    const HEAP_SIZE: usize = 256;
+    static mut HEAP_MEM: Heap8Byte<HEAP_SIZE> = Heap8Byte([MaybeUninit::uninit(); HEAP_SIZE]);
+
+    let allocator = LockedLinkedListAlloc::new();
+    let start = unsafe { &raw mut HEAP_MEM.0 as usize };
+    unsafe { allocator.init(start, HEAP_SIZE) };
+
+    unsafe {
+        let layout = Layout::from_size_align(32, 8).unwrap();
+        let ptr = allocator.try_allocate_at(start + 64, layout).unwrap();
+        assert_eq!(ptr.as_ptr() as usize, start + 64);
+        assert!(allocator.try_allocate_at(start + 64, layout).is_err());
+        assert!(allocator.try_allocate_at(start + 80, layout).is_err());
+
+        // Both sides of the placed block are still free.
+        let lead = Layout::from_size_align(64, 8).unwrap();
+        let trail = Layout::from_size_align(HEAP_SIZE - 96, 8).unwrap();
+        allocator.try_allocate_at(start, lead).unwrap();
+        allocator.try_allocate_at(start + 96, trail).unwrap();
+        assert!(
+            allocator
+                .alloc(Layout::from_size_align(8, 8).unwrap())
+                .is_null()
+        );
+
+        // Zero sized requests take nothing, even with the heap full.
+        let zst = Layout::new::<()>();
+        assert_eq!(
+            allocator.try_allocate_at(start, zst).unwrap(),
+            dangling(zst)
+        );
+    }
+    // The two failed placements and the final `alloc` are counted.
+    assert!(allocator.last_error().is_some_and(|e| e.is_oom()));
+    assert_eq!(allocator.metrics().total_alloc_calls, 7);
+    assert_eq!(allocator.metrics().total_alloc_failures, 3);
+}
+
+#[test]
+fn linked_list_debug_free_chain() {
+    const HEAP_SIZE: usize = 256;
+    static mut HEAP_MEM: Heap8Byte<HEAP_SIZE> = Heap8Byte([MaybeUninit::uninit(); HEAP_SIZE]);
+
+    let allocator = LockedLinkedListAlloc::new();
+    let start = unsafe { &raw mut HEAP_MEM.0 as usize };
+    unsafe { allocator.init(start, HEAP_SIZE) };
+
+    let layout = Layout::from_size_align(32, 8).unwrap();
+    unsafe {
+        let _a = allocator.alloc(layout);
+        let b = allocator.alloc(layout);
+        let _c = allocator.alloc(layout);
+        allocator.dealloc(b, layout);
+    }
+
+    let expected = format!(
+        "LockedLinkedList {{\n    start: {:#X}\n    end: {:#X}\n    free: [\n    {:#X}: 32\n    {:#X}: 160\n    ]\n    total: 192\n}}\n",
+        start,
+        start + HEAP_SIZE,
+        start + 32,
+        start + 96
+    );
+    assert_eq!(format!("{:?}", allocator), expected);
+}
+
+#[test]
+fn const_bump_capacity() {
+    const CAPACITY: usize = ConstBump::<512>::CAPACITY;
+    const _: () = assert!(ConstBump::<512>::capacity() == 512);
+    assert_eq!(CAPACITY, 512);
+
+    let bconst = ConstBumpAlloc::<CAPACITY>::new();
+    assert_eq!(bconst.remaining(), ConstBump::<CAPACITY>::CAPACITY);
+}
+
+#[test]
+fn const_bump_grow_into() {
+    let small = ConstBumpAlloc::<128>::new();
+    unsafe {
+        let layout = Layout::from_size_align(16, 8).unwrap();
+        let ptr = small.try_allocate(layout).unwrap();
+        // Moving it now would move the live block too.
+        assert!(!small.can_grow());
+        small.try_deallocate(ptr, layout).unwrap();
+        assert!(small.can_grow());
+
+        let large = small.grow_into::<256>();
+        assert_eq!(large.remaining(), ConstBump::<256>::capacity());
+        assert!(
+            large
+                .try_allocate(Layout::from_size_align(200, 8).unwrap())
+                .is_ok()
+        );
+    }
+}
+
+#[test]
+#[should_panic(expected = "ConstBump grown with live allocations")]
+fn const_bump_grow_into_live() {
+    let small = ConstBumpAlloc::<128>::new();
+    unsafe { small.try_allocate(Layout::new::<u64>()).unwrap() };
+    let _ = small.grow_into::<256>();
+}
+
+#[test]
+#[cfg(not(feature = "fill_on_free"))]
+fn linked_list_side_table_keeps_heap_pristine() {
+    const HEAP_SIZE: usize = 256;
+    const TABLE_SIZE: usize =
+        crate::linked_list_alloc::LockedLinkedList::side_table_size(HEAP_SIZE);
+    static mut HEAP_MEM: Heap8Byte<HEAP_SIZE> = Heap8Byte([MaybeUninit::new(0xAB); HEAP_SIZE]);
+    static mut TABLE_MEM: Heap8Byte<TABLE_SIZE> = Heap8Byte([MaybeUninit::new(0); TABLE_SIZE]);
+
+    let allocator = LockedLinkedListAlloc::new();
+    let start = unsafe { &raw mut HEAP_MEM.0 as usize };
+    let table = unsafe { &raw mut TABLE_MEM.0 as usize };
+    unsafe { allocator.init_with_side_table(start, HEAP_SIZE, table, TABLE_SIZE) };
+
+    let heap = || unsafe { core::slice::from_raw_parts(start as *const u8, HEAP_SIZE) };
+    let table_bytes = || unsafe { core::slice::from_raw_parts(table as *const u8, TABLE_SIZE) };
+    assert!(heap().iter().all(|&byte| byte == 0xAB));
+    assert!(table_bytes().iter().any(|&byte| byte != 0));
+
+    let layout = Layout::from_size_align(32, 8).unwrap();
+    unsafe {
+        let a = allocator.alloc(layout);
+        let b = allocator.alloc(layout);
+        let c = allocator.alloc(layout);
+        assert_eq!(a as usize, start);
+        b.write_bytes(0x5A, layout.size());
+        allocator.dealloc(c, layout);
+        allocator.dealloc(b, layout);
+        allocator.dealloc(a, layout);
+    }
+
+    let expected: [u8; HEAP_SIZE] = core::array::from_fn(|i| match i {
+        32..64 => 0x5A,
+        _ => 0xAB,
+    });
+    assert_eq!(heap(), &expected);
+    assert_eq!(allocator.free_region_count(), 1);
+    assert!(format!("{:?}", allocator).contains(&format!("{:#X}: {}", start, HEAP_SIZE)));
+}
+
+#[test]
+fn linked_list_deferred_coalescing() {
+    const HEAP_SIZE: usize = 256;
+    static mut HEAP_MEM: Heap8Byte<HEAP_SIZE> = Heap8Byte([MaybeUninit::uninit(); HEAP_SIZE]);
+
+    let allocator = LockedLinkedListAlloc::new();
+    unsafe { allocator.init(&raw mut HEAP_MEM.0 as usize, HEAP_SIZE) };
+    allocator.set_coalesce_policy(CoalescePolicy::Deferred);
+
+    let layout = Layout::from_size_align(32, 8).unwrap();
+    unsafe {
+        let blocks: [_; HEAP_SIZE / 32] =
+            core::array::from_fn(|_| allocator.try_allocate(layout).unwrap());
+        let steps = allocator.combine_steps();
+        for block in blocks {
+            allocator.try_deallocate(block, layout).unwrap();
+            assert_eq!(allocator.combine_steps(), steps);
+        }
+        assert_eq!(allocator.free_region_count(), HEAP_SIZE / 32);
+
+        // Nothing is big enough until the allocation merges the regions.
+        let whole = Layout::from_size_align(HEAP_SIZE, 8).unwrap();
+        let ptr = allocator.try_allocate(whole).unwrap();
+        assert!(allocator.combine_steps() > steps);
+        allocator.try_deallocate(ptr, whole).unwrap();
+
+        for block in core::array::from_fn::<_, 4, _>(|_| allocator.try_allocate(layout).unwrap()) {
+            allocator.try_deallocate(block, layout).unwrap();
+        }
+        assert!(allocator.free_region_count() > 1);
+        allocator.coalesce_all();
+    }
+    assert_eq!(allocator.free_region_count(), 1);
+}
+
+#[test]
+fn linked_list_stack_mode() {
+    const HEAP_SIZE: usize = 512;
+    static mut HEAP_MEM: Heap8Byte<HEAP_SIZE> = Heap8Byte([MaybeUninit::uninit(); HEAP_SIZE]);
+
+    let allocator = LockedLinkedListAlloc::new();
+    unsafe { allocator.init(&raw mut HEAP_MEM.0 as usize, HEAP_SIZE) };
+    allocator.set_stack_mode(true);
+
+    let layout = Layout::from_size_align(32, 8).unwrap();
+    let whole = Layout::from_size_align(HEAP_SIZE, 8).unwrap();
+    unsafe {
+        let blocks: [_; 3] = core::array::from_fn(|_| allocator.try_allocate(layout).unwrap());
+        let steps = allocator.combine_steps();
+        for block in blocks.into_iter().rev() {
+            allocator.try_deallocate(block, layout).unwrap();
+            assert_eq!(allocator.free_region_count(), 1);
+        }
+        assert_eq!(allocator.combine_steps(), steps);
+        let ptr = allocator.try_allocate(whole).unwrap();
+        allocator.try_deallocate(ptr, whole).unwrap();
+
+        // Freeing out of order walks the list as usual.
+        let [a, b, c]: [_; 3] = core::array::from_fn(|_| allocator.try_allocate(layout).unwrap());
+        let steps = allocator.combine_steps();
+        allocator.try_deallocate(a, layout).unwrap();
+        assert!(allocator.combine_steps() > steps);
+        allocator.try_deallocate(c, layout).unwrap();
+        allocator.try_deallocate(b, layout).unwrap();
+        allocator.coalesce_all();
+        assert_eq!(allocator.free_region_count(), 1);
+    }
+}
+
+#[test]
+fn linked_list_dealloc_all() {
+    const HEAP_SIZE: usize = 512;
+    static mut HEAP_MEM: Heap8Byte<HEAP_SIZE> = Heap8Byte([MaybeUninit::uninit(); HEAP_SIZE]);
+
+    let allocator = LockedLinkedListAlloc::new();
+    unsafe { allocator.init(&raw mut HEAP_MEM.0 as usize, HEAP_SIZE) };
+    assert_eq!(allocator.remaining(), HEAP_SIZE);
+
+    let layout = Layout::from_size_align(32, 8).unwrap();
+    unsafe {
+        let blocks: [_; 8] = core::array::from_fn(|_| allocator.try_allocate(layout).unwrap());
+        for block in blocks.iter().step_by(2) {
+            allocator.try_deallocate(*block, layout).unwrap();
+        }
+        assert!(allocator.free_region_count() > 1);
+        assert_eq!(allocator.allocations(), 4);
+
+        allocator.dealloc_all();
+    }
+
+    assert_eq!(allocator.free_region_count(), 1);
+    assert_eq!(allocator.remaining(), HEAP_SIZE);
+    assert_eq!(allocator.allocations(), 0);
+    assert!(allocator.can_allocate(Layout::from_size_align(HEAP_SIZE, 8).unwrap()));
+}
+
+#[test]
+fn init_aligned_misaligned_start() {
+    const HEAP_SIZE: usize = 256;
+    static mut LIST_MEM: Heap8Byte<HEAP_SIZE> = Heap8Byte([MaybeUninit::uninit(); HEAP_SIZE]);
+    static mut BUDDY_MEM: Heap8Byte<HEAP_SIZE> = Heap8Byte([MaybeUninit::uninit(); HEAP_SIZE]);
+
+    let layout = Layout::from_size_align(32, 8).unwrap();
+    unsafe {
+        let base = &raw mut LIST_MEM.0 as usize;
+        assert!(matches!(
+            LockedLinkedListAlloc::new().init_aligned(base + 1, 8),
+            Err(BAllocatorError::Oom(None))
+        ));
+
+        let list = LockedLinkedListAlloc::new();
+        assert_eq!(
+            list.init_aligned(base + 3, HEAP_SIZE - 3).unwrap(),
+            (base + 8, HEAP_SIZE - 8)
+        );
+        let ptr = list.try_allocate(layout).unwrap();
+        assert_eq!(ptr.as_ptr() as usize, base + 8);
+        list.try_deallocate(ptr, layout).unwrap();
+
+        let base = &raw mut BUDDY_MEM.0 as usize;
+        let buddy = LockedBuddyAlloc::new();
+        assert_eq!(
+            buddy.init_aligned(base + 3, HEAP_SIZE - 3).unwrap(),
+            (base + 8, HEAP_SIZE / 2)
+        );
+        let ptr = buddy.try_allocate(layout).unwrap();
+        assert!(ptr.as_ptr() as usize >= base + 8);
+        assert!(buddy.contains(ptr));
+    }
+}
+
+#[cfg(not(feature = "oom_panic"))]
+#[test]
+fn buddy_trim() {
+    const HEAP_SIZE: usize = 512;
+    static mut UNUSED_MEM: Heap4096Byte<HEAP_SIZE> =
+        Heap4096Byte([MaybeUninit::uninit(); HEAP_SIZE]);
+    static mut PARTIAL_MEM: Heap4096Byte<HEAP_SIZE> =
+        Heap4096Byte([MaybeUninit::uninit(); HEAP_SIZE]);
+
+    let unused = LockedBuddyAlloc::new();
+    unsafe { unused.init(&raw mut UNUSED_MEM.0 as usize, HEAP_SIZE) };
+    assert_eq!(unused.trim(), HEAP_SIZE);
+    assert_eq!(unused.free_counts(), [0; NR_MAX_ORDER]);
+
+    let partial = LockedBuddyAlloc::new();
+    let start = unsafe { &raw mut PARTIAL_MEM.0 as usize };
+    unsafe { partial.init(start, HEAP_SIZE) };
+
+    let half = Layout::from_size_align(HEAP_SIZE / 2, 8).unwrap();
+    unsafe {
+        let high = partial.alloc(half);
+        let low = partial.alloc(half);
+        assert_eq!(high as usize, start + HEAP_SIZE / 2);
+        assert_eq!(low as usize, start);
+
+        partial.dealloc(high, half);
+        assert_eq!(partial.trim(), HEAP_SIZE / 2);
+        // The trimmed half is gone, so nothing else fits.
+        assert!(partial.alloc(half).is_null());
+        assert!(!partial.contains(NonNull::new(high).unwrap()));
+
+        // The heap shrank with the trim, so freeing the rest leaves nothing
+        // live as far as deinit can tell.
+        partial.dealloc(low, half);
+        partial.deinit().unwrap();
+        unused.deinit().unwrap();
+    }
+}
+
+#[test]
+fn linked_list_reclaims_alignment_padding() {
+    const HEAP_SIZE: usize = 512;
+    static mut HEAP_MEM: Heap4096Byte<HEAP_SIZE> = Heap4096Byte([MaybeUninit::uninit(); HEAP_SIZE]);
+
+    let allocator = LockedLinkedListAlloc::new();
+    // Only 8 byte aligned, so a 64 byte aligned object leaves a leading gap.
+    let start = unsafe { &raw mut HEAP_MEM.0 as usize } + 8;
+    unsafe { allocator.init(start, HEAP_SIZE - 8) };
+
+    unsafe {
+        let ptr = allocator.alloc(Layout::from_size_align(64, 64).unwrap());
+        assert_eq!(ptr as usize, start + 56);
+
+        let padding = Layout::from_size_align(56, 8).unwrap();
+        let reused = allocator.try_allocate_at(start, padding).unwrap();
+        assert_eq!(reused.as_ptr() as usize, start);
+    }
+}
+
+#[test]
+fn linked_list_aligned_fill_to_region_end() {
+    const HEAP_SIZE: usize = 512;
+    static mut HEAP_MEM: Heap4096Byte<HEAP_SIZE> = Heap4096Byte([MaybeUninit::uninit(); HEAP_SIZE]);
+
+    let allocator = LockedLinkedListAlloc::new();
+    let start = unsafe { &raw mut HEAP_MEM.0 as usize } + 8;
+    unsafe { allocator.init(start, HEAP_SIZE - 8) };
+
+    // The leading gap goes back on the list and the block ends exactly at the
+    // end of the region, with no excess left over.
+    let layout = Layout::from_size_align(HEAP_SIZE - 64, 64).unwrap();
+    unsafe {
+        let ptr = allocator.try_allocate(layout).unwrap();
+        assert_eq!(ptr.as_ptr() as usize, start + 56);
+        assert_eq!(allocator.free_region_count(), 1);
+        allocator.try_deallocate(ptr, layout).unwrap();
+
+        let again = allocator.try_allocate(layout).unwrap();
+        assert_eq!(again, ptr);
+    }
+}
+
+#[test]
+fn linked_list_exact_fit_leaves_no_remainder() {
+    const HEAP_SIZE: usize = 256;
+    static mut HEAP_MEM: Heap4096Byte<HEAP_SIZE> = Heap4096Byte([MaybeUninit::uninit(); HEAP_SIZE]);
+
+    let allocator = LockedLinkedListAlloc::new();
+    let start = unsafe { &raw mut HEAP_MEM.0 as usize };
+    unsafe { allocator.init(start, HEAP_SIZE) };
+
+    // Taking the whole region leaves neither a leading gap nor an excess,
+    // and nothing empty goes back on the list.
+    let whole = Layout::from_size_align(HEAP_SIZE, 8).unwrap();
+    unsafe {
+        let ptr = allocator.try_allocate(whole).unwrap();
+        assert_eq!(ptr.as_ptr() as usize, start);
+        assert_eq!(allocator.free_region_count(), 0);
+        allocator.try_deallocate(ptr, whole).unwrap();
+        assert_eq!(allocator.free_region_count(), 1);
+
+        // The same for a region freed back to the list.
+        let half = Layout::from_size_align(HEAP_SIZE / 2, 8).unwrap();
+        let first = allocator.try_allocate(half).unwrap();
+        let second = allocator.try_allocate(half).unwrap();
+        allocator.try_deallocate(first, half).unwrap();
+        assert_eq!(allocator.free_region_count(), 1);
+        assert_eq!(allocator.try_allocate(half).unwrap(), first);
+        assert_eq!(allocator.free_region_count(), 0);
+        allocator.try_deallocate(first, half).unwrap();
+        allocator.try_deallocate(second, half).unwrap();
+    }
+}
+
+/// Churns `allocator` so its free lists leave metadata all over a heap that
+/// started out as `0xFF`, then checks a zeroed allocation is zero up to its
+/// size and no further. Past the size is `0xFF`, or `FREE_FILL` with
+/// `fill_on_free`.
+unsafe fn assert_allocate_zeroed<A: BAllocator>(allocator: &A) {
+    let block = Layout::from_size_align(32, 8).unwrap();
+    unsafe {
+        let blocks: [_; 4] = core::array::from_fn(|_| allocator.try_allocate(block).unwrap());
+        for ptr in blocks {
+            ptr.write_bytes(0xFF, 32);
+        }
+        for ptr in blocks.into_iter().rev() {
+            allocator.try_deallocate(ptr, block).unwrap();
+        }
+
+        let layout = Layout::from_size_align(20, 8).unwrap();
+        let ptr = allocator.try_allocate_zeroed(layout).unwrap();
+        let bytes = core::slice::from_raw_parts(ptr.as_ptr(), 21);
+        assert!(bytes[..20].iter().all(|&b| b == 0), "{bytes:X?}");
+        assert_ne!(bytes[20], 0);
+        allocator.try_deallocate(ptr, layout).unwrap();
+    }
+}
+
+#[test]
+fn allocators_allocate_zeroed() {
+    const HEAP_SIZE: usize = 256;
+    static mut BUMP_MEM: Heap8Byte<HEAP_SIZE> = Heap8Byte([MaybeUninit::new(0xFF); HEAP_SIZE]);
+    static mut BUDDY_MEM: Heap4096Byte<HEAP_SIZE> =
+        Heap4096Byte([MaybeUninit::new(0xFF); HEAP_SIZE]);
+    static mut LIST_MEM: Heap8Byte<HEAP_SIZE> = Heap8Byte([MaybeUninit::new(0xFF); HEAP_SIZE]);
+    static mut TLSF_MEM: Heap8Byte<HEAP_SIZE> = Heap8Byte([MaybeUninit::new(0xFF); HEAP_SIZE]);
+
+    unsafe {
+        let bump = LockedBumpAlloc::new();
+        bump.init(&raw mut BUMP_MEM.0 as usize, HEAP_SIZE);
+        assert_allocate_zeroed(&bump);
+
+        let buddy = LockedBuddyAlloc::new();
+        buddy.init(&raw mut BUDDY_MEM.0 as usize, HEAP_SIZE);
+        assert_allocate_zeroed(&buddy);
+
+        let list = LockedLinkedListAlloc::new();
+        list.init(&raw mut LIST_MEM.0 as usize, HEAP_SIZE);
+        assert_allocate_zeroed(&list);
+
+        let tlsf = LockedTlsfAlloc::new();
+        tlsf.init(&raw mut TLSF_MEM.0 as usize, HEAP_SIZE);
+        assert_allocate_zeroed(&tlsf);
+    }
+}
+
+/// Grows a block of `0xFF` with `try_reallocate_zeroed`, checking the old
+/// bytes survive and only the grown tail is zeroed, then shrinks it back.
+unsafe fn assert_reallocate_zeroed<A: BAllocator>(allocator: &A) {
+    let layout = Layout::from_size_align(16, 8).unwrap();
+    unsafe {
+        let ptr = allocator.try_allocate(layout).unwrap();
+        ptr.write_bytes(0xFF, 16);
+
+        let grown = allocator.try_reallocate_zeroed(ptr, layout, 64).unwrap();
+        let bytes = core::slice::from_raw_parts(grown.as_ptr(), 64);
+        assert!(bytes[..16].iter().all(|&b| b == 0xFF));
+        assert!(bytes[16..].iter().all(|&b| b == 0));
+
+        let grown_layout = Layout::from_size_align(64, 8).unwrap();
+        grown.write_bytes(0xAA, 64);
+        let shrunk = allocator
+            .try_reallocate_zeroed(grown, grown_layout, 8)
+            .unwrap();
+        let bytes = core::slice::from_raw_parts(shrunk.as_ptr(), 8);
+        assert!(bytes.iter().all(|&b| b == 0xAA));
+        allocator
+            .try_deallocate(shrunk, Layout::from_size_align(8, 8).unwrap())
+            .unwrap();
+    }
+}
+
+#[test]
+fn allocators_reallocate_zeroed() {
+    const HEAP_SIZE: usize = 256;
+    static mut BUMP_MEM: Heap8Byte<HEAP_SIZE> = Heap8Byte([MaybeUninit::new(0xFF); HEAP_SIZE]);
+    static mut BUDDY_MEM: Heap4096Byte<HEAP_SIZE> =
+        Heap4096Byte([MaybeUninit::new(0xFF); HEAP_SIZE]);
+    static mut LIST_MEM: Heap8Byte<HEAP_SIZE> = Heap8Byte([MaybeUninit::new(0xFF); HEAP_SIZE]);
+    static mut TLSF_MEM: Heap8Byte<HEAP_SIZE> = Heap8Byte([MaybeUninit::new(0xFF); HEAP_SIZE]);
+
+    unsafe {
+        let bump = LockedBumpAlloc::new();
+        bump.init(&raw mut BUMP_MEM.0 as usize, HEAP_SIZE);
+        assert_reallocate_zeroed(&bump);
+
+        let buddy = LockedBuddyAlloc::new();
+        buddy.init(&raw mut BUDDY_MEM.0 as usize, HEAP_SIZE);
+        assert_reallocate_zeroed(&buddy);
+
+        let list = LockedLinkedListAlloc::new();
+        list.init(&raw mut LIST_MEM.0 as usize, HEAP_SIZE);
+        assert_reallocate_zeroed(&list);
+
+        let tlsf = LockedTlsfAlloc::new();
+        tlsf.init(&raw mut TLSF_MEM.0 as usize, HEAP_SIZE);
+        assert_reallocate_zeroed(&tlsf);
+    }
+}
+
+/// Moves `allocator` from `first` to `second` with `deinit` once everything
+/// on `first` has been freed.
+unsafe fn assert_deinit_reinit<A: BAllocator + AllocInit>(
+    allocator: &A,
+    first: usize,
+    second: usize,
+    size: usize,
+) {
+    let layout = Layout::from_size_align(32, 8).unwrap();
+    unsafe {
+        allocator.init(first, size);
+        let ptr = allocator.try_allocate(layout).unwrap();
+        assert!((first..first + size).contains(&(ptr.as_ptr() as usize)));
+        allocator.try_deallocate(ptr, layout).unwrap();
+
+        allocator.deinit().unwrap();
+        assert!(!allocator.is_initialized());
+        assert!(allocator.try_allocate(layout).is_err());
+
+        allocator.init(second, size);
+        let ptr = allocator.try_allocate(layout).unwrap();
+        assert!((second..second + size).contains(&(ptr.as_ptr() as usize)));
+        allocator.try_deallocate(ptr, layout).unwrap();
+    }
+}
+
+#[test]
+fn allocators_deinit() {
+    const HEAP_SIZE: usize = 256;
+    static mut HEAP_MEM: Heap4096Byte<{ 2 * HEAP_SIZE }> =
+        Heap4096Byte([MaybeUninit::uninit(); 2 * HEAP_SIZE]);
+
+    let first = unsafe { &raw mut HEAP_MEM.0 as usize };
+    let second = first + HEAP_SIZE;
+    unsafe {
+        assert_deinit_reinit(&LockedBumpAlloc::new(), first, second, HEAP_SIZE);
+        assert_deinit_reinit(&LockedBuddyAlloc::new(), first, second, HEAP_SIZE);
+        assert_deinit_reinit(&LockedLinkedListAlloc::new(), first, second, HEAP_SIZE);
+        assert_deinit_reinit(&LockedTlsfAlloc::new(), first, second, HEAP_SIZE);
+
+        // Its heap is set once, so the lockless bump cannot be reset.
+        let lockless = LocklessBumpAlloc::new();
+        lockless.init(first, HEAP_SIZE);
+        assert!(matches!(
+            lockless.deinit(),
+            Err(BAllocatorError::Unsupported)
+        ));
+        assert!(lockless.is_initialized());
+    }
+}
+
+/// Checks `can_allocate` agrees with an actual allocation attempt for every
+/// size up to `max`, without leaving anything allocated.
+#[cfg(not(feature = "oom_panic"))]
+unsafe fn assert_can_allocate_matches<A: GlobalAlloc + BAllocator>(allocator: &A, max: usize) {
+    for size in 1..=max {
+        let layout = Layout::from_size_align(size, 8).unwrap();
+        let predicted = allocator.can_allocate(layout);
+        unsafe {
+            let ptr = allocator.alloc(layout);
+            assert_eq!(predicted, !ptr.is_null(), "size: {size}");
+            if !ptr.is_null() {
+                allocator.dealloc(ptr, layout);
+            }
+        }
+    }
+}
+
+#[test]
+fn bench_same_workload() {
+    const HEAP_SIZE: usize = 256;
+    static mut BUMP_MEM: Heap8Byte<HEAP_SIZE> = Heap8Byte([MaybeUninit::uninit(); HEAP_SIZE]);
+    static mut BUDDY_MEM: Heap4096Byte<HEAP_SIZE> =
+        Heap4096Byte([MaybeUninit::uninit(); HEAP_SIZE]);
+    static mut LIST_MEM: Heap8Byte<HEAP_SIZE> = Heap8Byte([MaybeUninit::uninit(); HEAP_SIZE]);
+
+    let block = AllocOp::Alloc { size: 32, align: 8 };
+    let ops = [
+        block,
+        block,
+        block,
+        block,
+        AllocOp::Dealloc { index: 1 },
+        AllocOp::Dealloc { index: 3 },
+        // Too large for what is left in any of them.
+        AllocOp::Alloc {
+            size: 200,
+            align: 8,
+        },
+        AllocOp::Alloc { size: 64, align: 8 },
+    ];
+    let whole = Layout::from_size_align(HEAP_SIZE, 8).unwrap();
+
+    unsafe {
+        let bump = LockedBumpAlloc::new();
+        bump.init(&raw mut BUMP_MEM.0 as usize, HEAP_SIZE);
+        let result = run_workload::<_, 8>(&bump, &ops);
+        assert_eq!((result.allocations, result.failures), (5, 1));
+        // Everything past the bump pointer is one block.
+        assert_eq!(result.largest_free, HEAP_SIZE - 192);
+        assert_eq!(result.fragmentation, 0.0);
+        assert!(bump.can_allocate(whole));
+
+        let buddy = LockedBuddyAlloc::new();
+        buddy.init(&raw mut BUDDY_MEM.0 as usize, HEAP_SIZE);
+        let result = run_workload::<_, 8>(&buddy, &ops);
+        assert_eq!((result.allocations, result.failures), (5, 1));
+        assert_eq!(result.free_bytes, 128);
+        assert!(result.fragmentation > 0.0);
+        assert!(buddy.can_allocate(whole));
+
+        let list = LockedLinkedListAlloc::new();
+        list.init(&raw mut LIST_MEM.0 as usize, HEAP_SIZE);
+        let result = run_workload::<_, 8>(&list, &ops);
+        assert_eq!((result.allocations, result.failures), (5, 1));
+        assert_eq!(result.free_bytes, 128);
+        assert!(result.fragmentation > 0.0);
+        list.coalesce_all();
+        assert!(list.can_allocate(whole));
+    }
+}
+
+#[cfg(not(feature = "oom_panic"))]
+#[test]
+fn allocators_can_allocate() {
+    const HEAP_SIZE: usize = 128;
+    static mut BUMP_MEM: Heap8Byte<HEAP_SIZE> = Heap8Byte([MaybeUninit::uninit(); HEAP_SIZE]);
+    static mut BUDDY_MEM: Heap4096Byte<HEAP_SIZE> =
+        Heap4096Byte([MaybeUninit::uninit(); HEAP_SIZE]);
+    static mut LIST_MEM: Heap8Byte<HEAP_SIZE> = Heap8Byte([MaybeUninit::uninit(); HEAP_SIZE]);
+    static mut TLSF_MEM: Heap8Byte<HEAP_SIZE> = Heap8Byte([MaybeUninit::uninit(); HEAP_SIZE]);
+
+    unsafe {
+        let bump = LockedBumpAlloc::new();
+        bump.init(&raw mut BUMP_MEM.0 as usize, HEAP_SIZE);
+        bump.alloc(Layout::from_size_align(60, 1).unwrap());
+        assert!(bump.can_allocate(Layout::from_size_align(68, 1).unwrap()));
+        assert!(!bump.can_allocate(Layout::from_size_align(69, 1).unwrap()));
+        // Aligning up from offset 60 costs 4 bytes.
+        assert!(bump.can_allocate(Layout::from_size_align(64, 8).unwrap()));
+        assert!(!bump.can_allocate(Layout::from_size_align(65, 8).unwrap()));
+        assert_eq!(bump.remaining(), 68);
+
+        let buddy = LockedBuddyAlloc::new();
+        buddy.init(&raw mut BUDDY_MEM.0 as usize, HEAP_SIZE);
+        buddy.alloc(Layout::from_size_align(HEAP_SIZE / 2, 8).unwrap());
+        assert_can_allocate_matches(&buddy, HEAP_SIZE);
+
+        let list = LockedLinkedListAlloc::new();
+        list.init(&raw mut LIST_MEM.0 as usize, HEAP_SIZE);
+        list.alloc(Layout::from_size_align(HEAP_SIZE / 2, 8).unwrap());
+        assert_can_allocate_matches(&list, HEAP_SIZE);
+
+        let tlsf = LockedTlsfAlloc::new();
+        tlsf.init(&raw mut TLSF_MEM.0 as usize, HEAP_SIZE);
+        tlsf.alloc(Layout::from_size_align(HEAP_SIZE / 4, 8).unwrap());
+        assert_can_allocate_matches(&tlsf, HEAP_SIZE);
+    }
+}
+
+#[test]
+fn allocators_is_initialized() {
+    const HEAP_SIZE: usize = 256;
+    static mut HEAP_MEM: [Heap8Byte<HEAP_SIZE>; 5] =
+        [const { Heap8Byte([MaybeUninit::uninit(); HEAP_SIZE]) }; 5];
+
+    fn check<A: AllocInit>(allocator: &A, heap: usize) {
+        assert!(!allocator.is_initialized());
+        unsafe { allocator.init(&raw mut HEAP_MEM[heap].0 as usize, HEAP_SIZE) };
+        assert!(allocator.is_initialized());
+    }
+
+    check(&LockedBumpAlloc::new(), 0);
+    check(&LocklessBumpAlloc::new(), 1);
+    check(&LockedBuddyAlloc::new(), 2);
+    check(&LockedLinkedListAlloc::new(), 3);
+    check(&LockedTlsfAlloc::new(), 4);
+    assert!(ConstBumpAlloc::<HEAP_SIZE>::new().is_initialized());
+}
+
+#[test]
+fn bump_deallocate_lifo() {
+    const HEAP_SIZE: usize = 128;
+    static mut HEAP_MEM: Heap8Byte<HEAP_SIZE> = Heap8Byte([MaybeUninit::uninit(); HEAP_SIZE]);
+
+    let allocator = LockedBumpAlloc::new();
+    unsafe { allocator.init(&raw mut HEAP_MEM.0 as usize, HEAP_SIZE) };
+    let layout = Layout::from_size_align(16, 8).unwrap();
+
+    unsafe {
+        let keep = allocator.alloc(layout);
+        let ptrs: Vec<_> = (0..4).map(|_| allocator.alloc(layout)).collect();
+
+        // Out of order frees cannot move `next` back.
+        let before = allocator.remaining();
+        allocator
+            .try_deallocate_lifo(NonNull::new(ptrs[1]).unwrap(), layout)
+            .unwrap();
+        assert_eq!(allocator.remaining(), before);
+        let reused = allocator.alloc(layout);
+
+        // Strictly reverse order frees give the space back one by one.
+        let mut remaining = allocator.remaining();
+        for ptr in [reused, ptrs[3], ptrs[2]] {
+            allocator
+                .try_deallocate_lifo(NonNull::new(ptr).unwrap(), layout)
+                .unwrap();
+            assert_eq!(allocator.remaining(), remaining + layout.size());
+            remaining = allocator.remaining();
+        }
+        // ptrs[1] was already freed, so ptrs[0] is not the most recent block.
+        allocator
+            .try_deallocate_lifo(NonNull::new(ptrs[0]).unwrap(), layout)
+            .unwrap();
+        assert_eq!(allocator.remaining(), remaining);
+        assert_eq!(allocator.allocations(), 1);
+
+        allocator
+            .try_deallocate_lifo(NonNull::new(keep).unwrap(), layout)
+            .unwrap();
+        assert_eq!(allocator.remaining(), HEAP_SIZE);
+    }
+}
+
+/// LIFO frees are counted and recorded like `try_deallocate`, on every bump
+/// allocator that has them.
+#[test]
+fn bump_deallocate_lifo_bookkeeping() {
+    const HEAP_SIZE: usize = 64;
+    static mut LOCKED_MEM: Heap8Byte<HEAP_SIZE> = Heap8Byte([MaybeUninit::uninit(); HEAP_SIZE]);
+    static mut LOCKLESS_MEM: Heap8Byte<HEAP_SIZE> = Heap8Byte([MaybeUninit::uninit(); HEAP_SIZE]);
+    static CONST_BUMP: ConstBumpAlloc<HEAP_SIZE> = ConstBumpAlloc::new();
+    static LOCKLESS_BUMP: LocklessBumpAlloc = LocklessBumpAlloc::new();
+
+    let locked = LockedBumpAlloc::new();
+    let locked_start = unsafe { &raw mut LOCKED_MEM.0 as usize };
+    let lockless_start = unsafe { &raw mut LOCKLESS_MEM.0 as usize };
+    unsafe {
+        locked.init(locked_start, HEAP_SIZE);
+        LOCKLESS_BUMP.init(lockless_start, HEAP_SIZE);
+    }
+    let layout = Layout::from_size_align(16, 8).unwrap();
+    let zst = Layout::new::<()>();
+
+    macro_rules! check {
+        ($bump:expr, $foreign:expr) => {
+            unsafe {
+                let ptr = $bump.try_allocate(layout).unwrap();
+                $bump.try_deallocate_lifo(ptr, layout).unwrap();
+                $bump.try_deallocate_lifo(dangling(zst), zst).unwrap();
+                assert!(matches!(
+                    $bump.try_deallocate_lifo($foreign, layout),
+                    Err(BAllocatorError::InvalidFree)
+                ));
+            }
+            assert!(matches!(
+                $bump.last_error(),
+                Some(BAllocatorError::InvalidFree)
+            ));
+            assert_eq!($bump.metrics().total_dealloc_calls, 3);
+            assert_eq!($bump.remaining(), HEAP_SIZE);
+        };
+    }
+    // Each is handed a block of another heap.
+    let locked_block = NonNull::new(locked_start as *mut u8).unwrap();
+    let lockless_block = NonNull::new(lockless_start as *mut u8).unwrap();
+    check!(locked, lockless_block);
+    check!(CONST_BUMP, lockless_block);
+    check!(LOCKLESS_BUMP, locked_block);
+}
+
+/// `next` never passes the end of the heap, however a bump allocator is
+/// filled, so `remaining` bottoms out at zero.
+#[test]
+fn bump_remaining_at_full_heap() {
+    const HEAP_SIZE: usize = 64;
+    static mut LOCKED_MEM: Heap8Byte<HEAP_SIZE> = Heap8Byte([MaybeUninit::uninit(); HEAP_SIZE]);
+    static mut LOCKLESS_MEM: Heap8Byte<HEAP_SIZE> = Heap8Byte([MaybeUninit::uninit(); HEAP_SIZE]);
+    static CONST_BUMP: ConstBumpAlloc<HEAP_SIZE> = ConstBumpAlloc::new();
+    static LOCKLESS_BUMP: LocklessBumpAlloc = LocklessBumpAlloc::new();
+
+    let locked = LockedBumpAlloc::new();
+    unsafe {
+        locked.init(&raw mut LOCKED_MEM.0 as usize, HEAP_SIZE);
+        LOCKLESS_BUMP.init(&raw mut LOCKLESS_MEM.0 as usize, HEAP_SIZE);
+    }
+    let most = Layout::from_size_align(HEAP_SIZE - 4, 4).unwrap();
+    let aligned = Layout::from_size_align(4, 16).unwrap();
+    let rest = Layout::from_size_align(HEAP_SIZE, 1).unwrap();
+
+    macro_rules! check {
+        ($bump:expr) => {
+            unsafe {
+                let first = $bump.try_allocate(most).unwrap();
+                assert_eq!($bump.remaining(), 4);
+                // Aligning up would start past the end of the heap.
+                assert!($bump.try_allocate(aligned).is_err_and(|e| e.is_oom()));
+                assert_eq!($bump.remaining(), 4);
+                let (last, size) = $bump.try_allocate_partial(rest).unwrap();
+                assert_eq!(size, 4);
+                assert_eq!($bump.remaining(), 0);
+                assert!($bump.try_allocate_partial(rest).is_err_and(|e| e.is_oom()));
+                assert_eq!($bump.remaining(), 0);
+                $bump
+                    .try_deallocate(last, Layout::from_size_align(size, 1).unwrap())
+                    .unwrap();
+                $bump.try_deallocate(first, most).unwrap();
+            }
+            assert_eq!($bump.remaining(), HEAP_SIZE);
+        };
+    }
+    check!(locked);
+    check!(CONST_BUMP);
+    check!(LOCKLESS_BUMP);
+}
+
+#[test]
+fn bump_deallocate_lifo_reclaims_padding() {
+    const HEAP_SIZE: usize = 256;
+    static mut HEAP_MEM: Heap4096Byte<HEAP_SIZE> = Heap4096Byte([MaybeUninit::uninit(); HEAP_SIZE]);
+
+    let allocator = LockedBumpAlloc::new();
+    unsafe { allocator.init(&raw mut HEAP_MEM.0 as usize, HEAP_SIZE) };
+
+    let small = Layout::from_size_align(4, 1).unwrap();
+    let aligned = Layout::from_size_align(16, 64).unwrap();
+    unsafe {
+        // Keeps the heap from being reset when the aligned block is freed.
+        let keep = allocator.try_allocate(small).unwrap();
+        let before = allocator.remaining();
+
+        let ptr = allocator.try_allocate(aligned).unwrap();
+        assert_eq!(allocator.remaining(), before - 60 - 16);
+        allocator.try_deallocate_lifo(ptr, aligned).unwrap();
+        assert_eq!(allocator.remaining(), before);
+
+        // Every padded block of a nested run gives its padding back, not
+        // just the most recent one.
+        let outer = allocator.try_allocate(aligned).unwrap();
+        let middle = allocator.try_allocate(small).unwrap();
+        let inner = allocator.try_allocate(aligned).unwrap();
+        allocator.try_deallocate_lifo(inner, aligned).unwrap();
+        allocator.try_deallocate_lifo(middle, small).unwrap();
+        allocator.try_deallocate_lifo(outer, aligned).unwrap();
+        assert_eq!(allocator.remaining(), before);
+
+        // A block freed out of order keeps its padding and space, LIFO frees
+        // above it stop at its end.
+        let first = allocator.try_allocate(aligned).unwrap();
+        allocator.try_deallocate(first, aligned).unwrap();
+        let filler = allocator.try_allocate(small).unwrap();
+        allocator.try_deallocate_lifo(filler, small).unwrap();
+        assert_eq!(allocator.remaining(), before - 60 - 16);
+
+        allocator.try_deallocate_lifo(keep, small).unwrap();
+    }
+    assert_eq!(allocator.remaining(), HEAP_SIZE);
+}
+
+#[cfg(not(feature = "oom_panic"))]
+#[test]
+fn bump_merge_with() {
+    const HEAP_SIZE: usize = 256;
+    static mut HEAP_MEM: Heap8Byte<HEAP_SIZE> = Heap8Byte([MaybeUninit::uninit(); HEAP_SIZE]);
+
+    let start = unsafe { &raw mut HEAP_MEM.0 as usize };
+    let low = LockedBumpAlloc::new();
+    let high = LockedBumpAlloc::new();
+    let gap = LockedBumpAlloc::new();
+    unsafe {
+        low.init(start, 64);
+        high.init(start + 64, 128);
+        gap.init(start + 192 + 8, 56);
+    }
+    let layout = Layout::from_size_align(32, 8).unwrap();
+
+    // Not adjacent.
+    assert!(high.merge_with(&gap).is_err());
+    assert!(low.merge_with(&low).is_err());
+
+    // Adjacent but in use.
+    let ptr = unsafe { high.alloc(layout) };
+    assert!(low.merge_with(&high).is_err());
+    unsafe { high.dealloc(ptr, layout) };
+
+    low.merge_with(&high).unwrap();
+    assert_eq!(low.remaining(), 192);
+    assert!(!high.is_initialized());
+    assert!(low.contains(NonNull::new((start + 191) as *mut u8).unwrap()));
+
+    let ptrs: Vec<_> = (0..6).map(|_| unsafe { low.alloc(layout) }).collect();
+    assert!(ptrs.iter().all(|ptr| !ptr.is_null()));
+    assert!(unsafe { low.alloc(layout) }.is_null());
+}
+
+/// Merges two allocators into each other from two threads at once, which
+/// must not deadlock whichever lock each thread takes first.
+#[test]
+fn bump_merge_with_opposite_orders() {
+    const HEAP_SIZE: usize = 256;
+    const ROUNDS: usize = 2000;
+    static mut HEAP_MEM: Heap8Byte<HEAP_SIZE> = Heap8Byte([MaybeUninit::uninit(); HEAP_SIZE]);
+    static FIRST: LockedBumpAlloc = LockedBumpAlloc::new();
+    static SECOND: LockedBumpAlloc = LockedBumpAlloc::new();
+
+    // Not adjacent, so every merge fails and leaves both as they were.
+    let start = unsafe { &raw mut HEAP_MEM.0 as usize };
+    unsafe {
+        FIRST.init(start, 64);
+        SECOND.init(start + 128, 64);
+    }
+    let forward = std::thread::spawn(|| {
+        for _ in 0..ROUNDS {
+            assert!(FIRST.merge_with(&SECOND).is_err());
+        }
+    });
+    for _ in 0..ROUNDS {
+        assert!(SECOND.merge_with(&FIRST).is_err());
+    }
+    forward.join().unwrap();
+}
+
+#[test]
+fn const_bump_remaining_for() {
+    const HEAP_SIZE: usize = 64;
+    static ALLOCATOR: ConstBumpAlloc<HEAP_SIZE> = ConstBumpAlloc::new();
+
+    let byte = Layout::new::<u8>();
+    let aligned = Layout::from_size_align(1, 16).unwrap();
+    let start = unsafe { ALLOCATOR.alloc(byte) } as usize;
+
+    // Leave 10 bytes, or 11 if that happens to put `next` on a 16 byte boundary.
+    let free = if (start + HEAP_SIZE - 10).is_multiple_of(16) {
+        11
+    } else {
+        10
+    };
+    let fill = Layout::from_size_align(HEAP_SIZE - 1 - free, 1).unwrap();
+    unsafe { ALLOCATOR.alloc(fill) };
+
+    assert_eq!(ALLOCATOR.remaining(), free);
+    assert_eq!(ALLOCATOR.remaining_for(byte), free);
+    let next = start + HEAP_SIZE - free;
+    assert_eq!(
+        ALLOCATOR.remaining_for(aligned),
+        (start + HEAP_SIZE).saturating_sub(align_up(next, 16))
+    );
+    assert!(ALLOCATOR.remaining_for(aligned) < free);
+    assert_eq!(
+        ALLOCATOR.can_allocate(aligned),
+        ALLOCATOR.remaining_for(aligned) > 0
+    );
+}
+
+#[test]
+fn bump_allocate_partial() {
+    const HEAP_SIZE: usize = 64;
+    static mut HEAP_MEM: Heap8Byte<HEAP_SIZE> = Heap8Byte([MaybeUninit::uninit(); HEAP_SIZE]);
+
+    let start = unsafe { &raw mut HEAP_MEM.0 as usize };
+    let end = start + HEAP_SIZE;
+    let allocator = LockedBumpAlloc::new();
+    unsafe { allocator.init(start, HEAP_SIZE) };
+
+    unsafe {
+        // Fits entirely.
+        let layout = Layout::from_size_align(20, 1).unwrap();
+        let (_, granted) = allocator.try_allocate_partial(layout).unwrap();
+        assert_eq!(granted, 20);
+
+        // Only part of the request fits once `next` is aligned.
+        let layout = Layout::from_size_align(64, 8).unwrap();
+        let next = start + 20;
+        let (ptr, granted) = allocator.try_allocate_partial(layout).unwrap();
+        assert_eq!(ptr.as_ptr() as usize, align_up(next, 8));
+        assert_eq!(granted, end - align_up(next, 8));
+        assert_eq!(allocator.remaining(), 0);
+
+        // Nothing left to hand out.
+        assert!(
+            allocator
+                .try_allocate_partial(Layout::new::<u8>())
+                .unwrap_err()
+                .is_oom()
+        );
+    }
+    assert!(allocator.last_error().is_some_and(|e| e.is_oom()));
+    assert_eq!(allocator.metrics().total_alloc_calls, 3);
+    assert_eq!(allocator.metrics().total_alloc_failures, 1);
+
+    // The const and lockless heaps count theirs the same way.
+    static CONST_BUMP: ConstBumpAlloc<HEAP_SIZE> = ConstBumpAlloc::new();
+    static LOCKLESS_BUMP: LocklessBumpAlloc = LocklessBumpAlloc::new();
+    static mut LOCKLESS_MEM: Heap8Byte<HEAP_SIZE> = Heap8Byte([MaybeUninit::uninit(); HEAP_SIZE]);
+    unsafe { LOCKLESS_BUMP.init(&raw mut LOCKLESS_MEM.0 as usize, HEAP_SIZE) };
+    let whole = Layout::from_size_align(HEAP_SIZE * 2, 8).unwrap();
+    unsafe {
+        assert_eq!(CONST_BUMP.try_allocate_partial(whole).unwrap().1, HEAP_SIZE);
+        assert!(CONST_BUMP.try_allocate_partial(whole).unwrap_err().is_oom());
+        assert_eq!(
+            LOCKLESS_BUMP.try_allocate_partial(whole).unwrap().1,
+            HEAP_SIZE
+        );
+        assert!(
+            LOCKLESS_BUMP
+                .try_allocate_partial(whole)
+                .unwrap_err()
+                .is_oom()
+        );
+    }
+    assert!(CONST_BUMP.last_error().is_some_and(|e| e.is_oom()));
+    assert_eq!(CONST_BUMP.metrics().total_alloc_calls, 2);
+    assert_eq!(CONST_BUMP.metrics().total_alloc_failures, 1);
+    assert!(LOCKLESS_BUMP.last_error().is_some_and(|e| e.is_oom()));
+    assert_eq!(LOCKLESS_BUMP.metrics().total_alloc_calls, 2);
+    assert_eq!(LOCKLESS_BUMP.metrics().total_alloc_failures, 1);
+}
+
+#[test]
+fn buddy_prewarm() {
+    const HEAP_SIZE: usize = 4096;
+    static mut HEAP_MEM: Heap4096Byte<HEAP_SIZE> = Heap4096Byte([MaybeUninit::uninit(); HEAP_SIZE]);
+
+    let allocator = LockedBuddyAlloc::new();
+    unsafe { allocator.init(&raw mut HEAP_MEM.0 as usize, HEAP_SIZE) };
+
+    assert_eq!(allocator.prewarm(2, 4), 4);
+    assert!(allocator.free_counts()[2] >= 4);
+    assert_eq!(allocator.prewarm(2, 4), 0);
+
+    let splits = allocator.splits();
+    let layout = Layout::from_size_align(PAGE_SIZE << 2, 8).unwrap();
+    for _ in 0..4 {
+        assert!(!unsafe { allocator.alloc(layout) }.is_null());
+    }
+    assert_eq!(allocator.splits(), splits);
+
+    // Asking for more than the heap holds creates as many as it can, here
+    // by splitting the one free order 8 block left.
+    assert_eq!(allocator.free_counts()[7], 1);
+    assert_eq!(allocator.prewarm(7, 4), 2);
+    assert_eq!(allocator.free_counts()[7], 3);
+}
+
+#[test]
+fn buddy_exact_order_skips_split() {
+    const HEAP_SIZE: usize = 4096;
+    static mut HEAP_MEM: Heap4096Byte<HEAP_SIZE> = Heap4096Byte([MaybeUninit::uninit(); HEAP_SIZE]);
+
+    let allocator = LockedBuddyAlloc::new();
+    unsafe { allocator.init(&raw mut HEAP_MEM.0 as usize, HEAP_SIZE) };
+
+    let layout = Layout::from_size_align(PAGE_SIZE << 3, 8).unwrap();
+    let first = unsafe { allocator.alloc(layout) };
+    let splits = allocator.splits();
+    assert!(splits > 0);
+
+    // The buddy left over from splitting is handed out as is.
+    let second = unsafe { allocator.alloc(layout) };
+    assert_eq!(allocator.splits(), splits);
+    assert_eq!(second as usize, first as usize - layout.size());
+}
+
+#[test]
+fn buddy_rejects_invalid_free() {
+    const HEAP_SIZE: usize = 4096;
+    static mut HEAP_MEM: Heap4096Byte<HEAP_SIZE> = Heap4096Byte([MaybeUninit::uninit(); HEAP_SIZE]);
+
+    let start = unsafe { &raw mut HEAP_MEM.0 as usize };
+    let allocator = LockedBuddyAlloc::new();
+    unsafe { allocator.init(start, HEAP_SIZE) };
+
+    let layout = Layout::from_size_align(64, 8).unwrap();
+    let ptr = NonNull::new(unsafe { allocator.alloc(layout) }).unwrap();
+    let free_counts = allocator.free_counts();
+
+    unsafe {
+        let outside = NonNull::new((start + HEAP_SIZE) as *mut u8).unwrap();
+        assert!(matches!(
+            allocator.try_deallocate(outside, layout),
+            Err(BAllocatorError::InvalidFree)
+        ));
+
+        let misaligned = ptr.byte_add(8);
+        assert!(matches!(
+            allocator.try_deallocate(misaligned, layout),
+            Err(BAllocatorError::InvalidFree)
+        ));
+        assert_eq!(allocator.free_counts(), free_counts);
+
+        allocator.try_deallocate(ptr, layout).unwrap();
+    }
+}
+
+#[test]
+fn linked_list_compatible_layout_free() {
+    const HEAP_SIZE: usize = 256;
+    static mut HEAP_MEM: Heap8Byte<HEAP_SIZE> = Heap8Byte([MaybeUninit::uninit(); HEAP_SIZE]);
+
+    let allocator = LockedLinkedListAlloc::new();
+    unsafe { allocator.init(&raw mut HEAP_MEM.0 as usize, HEAP_SIZE) };
+    let free_chain = format!("{allocator:?}");
+
+    // All of these round up to the same 16 byte block.
+    let allocated = Layout::new::<[u8; 10]>();
+    let compatible = [
+        Layout::from_size_align(13, 1).unwrap(),
+        Layout::from_size_align(16, 8).unwrap(),
+        Layout::new::<u64>(),
+    ];
+    unsafe {
+        for layout in compatible {
+            let ptr = NonNull::new(allocator.alloc(allocated)).unwrap();
+            allocator.try_deallocate(ptr, layout).unwrap();
+            assert_eq!(format!("{allocator:?}"), free_chain);
+        }
+    }
+
+    // A layout reaching into free memory is caught in debug builds.
+    #[cfg(debug_assertions)]
+    unsafe {
+        let ptr = NonNull::new(allocator.alloc(allocated)).unwrap();
+        let larger = Layout::from_size_align(64, 8).unwrap();
+        assert!(matches!(
+            allocator.try_deallocate(ptr, larger),
+            Err(BAllocatorError::InvalidFree)
+        ));
+        allocator.try_deallocate(ptr, allocated).unwrap();
+        assert_eq!(format!("{allocator:?}"), free_chain);
+    }
+}
+
+#[test]
+fn bump_encode_stats() {
+    const HEAP_SIZE: usize = 256;
+    static mut HEAP_MEM: Heap8Byte<HEAP_SIZE> = Heap8Byte([MaybeUninit::uninit(); HEAP_SIZE]);
+
+    let allocator = LockedBumpAlloc::new();
+    unsafe { allocator.init(&raw mut HEAP_MEM.0 as usize, HEAP_SIZE) };
+
+    let layout = Layout::from_size_align(16, 8).unwrap();
+    unsafe {
+        let ptrs: Vec<_> = (0..3).map(|_| allocator.alloc(layout)).collect();
+        allocator.dealloc(ptrs[2], layout);
+    }
+
+    let mut buf = [0xFF; 24];
+    assert_eq!(allocator.encode_stats(&mut buf), 24);
+    assert_eq!(&buf[0..8], &[0, 1, 0, 0, 0, 0, 0, 0]); // total: 256
+    assert_eq!(&buf[8..16], &[48, 0, 0, 0, 0, 0, 0, 0]); // used: 48
+    assert_eq!(&buf[16..20], &[2, 0, 0, 0]); // allocations: 2
+    assert_eq!(&buf[20..24], &[3, 0, 0, 0]); // peak allocations: 3
+}
+
+#[test]
+fn bump_swap_heap() {
+    const HEAP_SIZE: usize = 128;
+    static mut HEAP_A: Heap8Byte<HEAP_SIZE> = Heap8Byte([MaybeUninit::uninit(); HEAP_SIZE]);
+    static mut HEAP_B: Heap8Byte<HEAP_SIZE> = Heap8Byte([MaybeUninit::uninit(); HEAP_SIZE]);
+
+    let (start_a, start_b) = unsafe { (&raw mut HEAP_A.0 as usize, &raw mut HEAP_B.0 as usize) };
+    let allocator = LockedBumpAlloc::new();
+    unsafe { allocator.init(start_a, HEAP_SIZE) };
+    let layout = Layout::from_size_align(32, 8).unwrap();
+
+    unsafe {
+        let a = allocator.alloc(layout);
+        assert!((start_a..start_a + HEAP_SIZE).contains(&(a as usize)));
+        a.write_bytes(0xAA, layout.size());
+
+        assert_eq!(
+            allocator.swap_heap(start_b, HEAP_SIZE),
+            (start_a, HEAP_SIZE)
+        );
+        assert_eq!(allocator.allocations(), 0);
+        assert_eq!(allocator.remaining(), HEAP_SIZE);
+
+        let b = allocator.alloc(layout);
+        assert!((start_b..start_b + HEAP_SIZE).contains(&(b as usize)));
+        b.write_bytes(0xBB, layout.size());
+
+        // The old buffer is left untouched.
+        assert!(
+            core::slice::from_raw_parts(a, layout.size())
+                .iter()
+                .all(|&byte| byte == 0xAA)
+        );
+    }
+}
+
+#[test]
+fn linked_list_fragmentation() {
+    const HEAP_SIZE: usize = 4096;
+    static mut HEAP_MEM: Heap8Byte<HEAP_SIZE> = Heap8Byte([MaybeUninit::uninit(); HEAP_SIZE]);
+
+    let allocator = LockedLinkedListAlloc::new();
+    unsafe { allocator.init(&raw mut HEAP_MEM.0 as usize, HEAP_SIZE) };
+    assert_eq!(allocator.free_region_count(), 1);
+    assert_eq!(allocator.fragmentation(), 0.0);
+
+    let layout = Layout::from_size_align(64, 8).unwrap();
+    let ptrs: Vec<_> = (0..16)
+        .map(|_| unsafe { allocator.alloc(layout) })
+        .collect();
+    assert_eq!(allocator.free_region_count(), 1);
+
+    // Punch 8 holes of 64 bytes in front of the 3072 byte tail.
+    for (i, ptr) in ptrs.iter().step_by(2).enumerate() {
+        unsafe { allocator.dealloc(*ptr, layout) };
+        assert_eq!(allocator.free_region_count(), i + 2);
+    }
+    let expected = 1.0 - 3072.0 / (8.0 * 64.0 + 3072.0);
+    assert!((allocator.fragmentation() - expected).abs() < 1e-6);
+}
+
+/// The cached largest region must match a walk of the list after every kind
+/// of change to it.
+#[test]
+fn linked_list_largest_free_tracks_list() {
+    const HEAP_SIZE: usize = 4096;
+    static mut HEAP_MEM: Heap8Byte<HEAP_SIZE> = Heap8Byte([MaybeUninit::uninit(); HEAP_SIZE]);
+
+    let allocator = LinkedListBuilder::new()
+        .coalesce(CoalescePolicy::Deferred)
+        .build();
+    let start = unsafe { &raw mut HEAP_MEM.0 as usize };
+    unsafe { allocator.init(start, HEAP_SIZE) };
+    let check = || {
+        let mut largest = 0;
+        allocator.debug_free_list(|_, size| largest = largest.max(size));
+        assert_eq!(allocator.max_alloc_size(), largest);
+    };
+
+    let layout = Layout::from_size_align(256, 8).unwrap();
+    let ptrs: Vec<_> = (0..16)
+        .map(|_| {
+            let ptr = unsafe { allocator.alloc(layout) };
+            check();
+            ptr
+        })
+        .collect();
+    assert_eq!(allocator.max_alloc_size(), 0);
+
+    // Uncoalesced frees leave many equal regions; taking one keeps the max.
+    for ptr in ptrs.iter().step_by(2) {
+        unsafe { allocator.dealloc(*ptr, layout) };
+        check();
+    }
+    unsafe { allocator.alloc(layout) };
+    check();
+    assert_eq!(allocator.max_alloc_size(), 256);
+
+    // Merging raises it, taking from the merged region lowers it to the
+    // larger of the two pieces left.
+    unsafe { allocator.dealloc(ptrs[1], layout) };
+    assert_eq!(allocator.coalesce_all(), 2);
+    check();
+    assert_eq!(allocator.max_alloc_size(), 768);
+    let small = Layout::from_size_align(64, 8).unwrap();
+    unsafe { allocator.try_allocate_at(ptrs[1] as usize, small).unwrap() };
+    check();
+    assert_eq!(allocator.max_alloc_size(), 448);
+    unsafe { allocator.alloc(Layout::from_size_align(448, 8).unwrap()) };
+    check();
+    assert_eq!(allocator.max_alloc_size(), 256);
+
+    unsafe { allocator.dealloc_all() };
+    check();
+    assert_eq!(allocator.max_alloc_size(), HEAP_SIZE);
+}
+
+#[test]
+fn bump_aligned_and_unaligned_next() {
+    const HEAP_SIZE: usize = 256;
+    static mut HEAP_MEM: Heap8Byte<HEAP_SIZE> = Heap8Byte([MaybeUninit::uninit(); HEAP_SIZE]);
+
+    let allocator = LockedBumpAlloc::new();
+    unsafe { allocator.init(&raw mut HEAP_MEM.0 as usize, HEAP_SIZE) };
+
+    for align in [1, 2, 8, 16] {
+        let layout = Layout::from_size_align(align, align).unwrap();
+        unsafe {
+            // `next` is left aligned...
+            let ptr = allocator.alloc(layout);
+            assert_eq!(ptr as usize % align, 0);
+            let ptr = allocator.alloc(layout);
+            assert_eq!(ptr as usize % align, 0);
+
+            // ...and then unaligned.
+            assert!(!allocator.alloc(Layout::new::<u8>()).is_null());
+            let ptr = allocator.alloc(layout);
+            assert_eq!(ptr as usize % align, 0);
+        }
+    }
+
+    for addr in 1..64 {
+        for align in [1, 2, 4, 8, 16, 32] {
+            assert_eq!(
+                align_up(addr, align),
+                addr + (addr as *const u8).align_offset(align)
+            );
+        }
+    }
+}
+
+/// Compares `align_up` against always computing the offset, run with
+/// `cargo test --release -- --ignored --nocapture align_up_throughput`.
+#[test]
+#[ignore]
+fn align_up_throughput() {
+    use core::hint::black_box;
+    use std::time::Instant;
+
+    const ITERATIONS: usize = 10_000_000;
+
+    let now = Instant::now();
+    for i in 0..ITERATIONS {
+        black_box(align_up(black_box(i * 8), black_box(8)));
+    }
+    let fast = now.elapsed();
+
+    let now = Instant::now();
+    for i in 0..ITERATIONS {
+        let addr = black_box(i * 8);
+        black_box(addr + (addr as *const u8).align_offset(black_box(8)));
+    }
+    let slow = now.elapsed();
+
+    println!("align_up: {fast:?}, align_offset: {slow:?} for {ITERATIONS} aligned addresses");
+}
+
+#[cfg(not(feature = "oom_panic"))]
+#[test]
+fn buddy_too_large() {
+    const HEAP_SIZE: usize = 4096;
+    static mut HEAP_MEM: Heap4096Byte<HEAP_SIZE> = Heap4096Byte([MaybeUninit::uninit(); HEAP_SIZE]);
+
+    let allocator = LockedBuddyAlloc::new();
+    unsafe { allocator.init(&raw mut HEAP_MEM.0 as usize, HEAP_SIZE) };
+
+    let layout = Layout::from_size_align((PAGE_SIZE << MAX_ORDER) + 1, 8).unwrap();
+    assert!(matches!(
+        unsafe { allocator.try_allocate(layout) },
+        Err(BAllocatorError::TooLarge(l)) if l == layout
+    ));
+    assert!(!allocator.can_allocate(layout));
+    assert!(unsafe { allocator.alloc(layout) }.is_null());
+}
+
+#[test]
+fn max_alloc_size() {
+    const HEAP_SIZE: usize = 256;
+    static mut BUDDY_MEM: Heap4096Byte<HEAP_SIZE> =
+        Heap4096Byte([MaybeUninit::uninit(); HEAP_SIZE]);
+    static mut BUMP_MEM: Heap8Byte<HEAP_SIZE> = Heap8Byte([MaybeUninit::uninit(); HEAP_SIZE]);
+    static mut LIST_MEM: Heap8Byte<HEAP_SIZE> = Heap8Byte([MaybeUninit::uninit(); HEAP_SIZE]);
+
+    let buddy = LockedBuddyAlloc::new();
+    unsafe { buddy.init(&raw mut BUDDY_MEM.0 as usize, HEAP_SIZE) };
+    assert_eq!(buddy.max_alloc_size(), PAGE_SIZE << MAX_ORDER);
+    let over = Layout::from_size_align(buddy.max_alloc_size() + 1, 8).unwrap();
+    assert!(matches!(
+        unsafe { buddy.try_allocate(over) },
+        Err(BAllocatorError::TooLarge(l)) if l == over
+    ));
+
+    const { assert!(ConstBump::<HEAP_SIZE>::MAX_ALLOC_SIZE == HEAP_SIZE) };
+    assert_eq!(
+        ConstBumpAlloc::<HEAP_SIZE>::new().max_alloc_size(),
+        HEAP_SIZE
+    );
+
+    let bump = LockedBumpAlloc::new();
+    unsafe { bump.init(&raw mut BUMP_MEM.0 as usize, HEAP_SIZE) };
+    assert_eq!(bump.max_alloc_size(), HEAP_SIZE);
+
+    // The linked list only reports what is free right now.
+    let list = LockedLinkedListAlloc::new();
+    unsafe { list.init(&raw mut LIST_MEM.0 as usize, HEAP_SIZE) };
+    assert_eq!(list.max_alloc_size(), HEAP_SIZE);
+    let layout = Layout::from_size_align(64, 8).unwrap();
+    unsafe {
+        let ptr = list.try_allocate(layout).unwrap();
+        assert_eq!(list.max_alloc_size(), HEAP_SIZE - 64);
+        let fits = Layout::from_size_align(list.max_alloc_size(), 8).unwrap();
+        let rest = list.try_allocate(fits).unwrap();
+        list.try_deallocate(rest, fits).unwrap();
+        list.try_deallocate(ptr, layout).unwrap();
+    }
+}
+
+#[test]
+fn allocate_with_usable() {
+    const HEAP_SIZE: usize = 256;
+    static mut BUDDY_MEM: Heap4096Byte<HEAP_SIZE> =
+        Heap4096Byte([MaybeUninit::uninit(); HEAP_SIZE]);
+    static mut BUMP_MEM: Heap8Byte<HEAP_SIZE> = Heap8Byte([MaybeUninit::uninit(); HEAP_SIZE]);
+    static mut LIST_MEM: Heap8Byte<HEAP_SIZE> = Heap8Byte([MaybeUninit::uninit(); HEAP_SIZE]);
+
+    let buddy = LockedBuddyAlloc::new();
+    let bump = LockedBumpAlloc::new();
+    let list = LockedLinkedListAlloc::new();
+    unsafe {
+        buddy.init(&raw mut BUDDY_MEM.0 as usize, HEAP_SIZE);
+        bump.init(&raw mut BUMP_MEM.0 as usize, HEAP_SIZE);
+        list.init(&raw mut LIST_MEM.0 as usize, HEAP_SIZE);
+    }
+
+    unsafe {
+        // 20 bytes round up to an order 2 block of 32.
+        let layout = Layout::from_size_align(20, 4).unwrap();
+        let (ptr, usable) = buddy.try_allocate_with_usable(layout).unwrap();
+        assert_eq!(usable, PAGE_SIZE << 2);
+        ptr.write_bytes(0x42, usable);
+        buddy.try_deallocate(ptr, layout).unwrap();
+
+        let layout = Layout::from_size_align(13, 1).unwrap();
+        let (ptr, usable) = bump.try_allocate_with_usable(layout).unwrap();
+        assert_eq!(usable, 13);
+        bump.try_deallocate(ptr, layout).unwrap();
+
+        // Too small to hold a node once freed, so padded up to one.
+        let layout = Layout::from_size_align(1, 1).unwrap();
+        let (ptr, usable) = list.try_allocate_with_usable(layout).unwrap();
+        assert!(usable >= 16 && usable.is_multiple_of(8));
+        ptr.write_bytes(0x42, usable);
+        list.try_deallocate(ptr, layout).unwrap();
+        assert_eq!(list.max_alloc_size(), HEAP_SIZE);
+
+        let (ptr, usable) = bump.try_allocate_with_usable(Layout::new::<()>()).unwrap();
+        assert_eq!(usable, 0);
+        bump.try_deallocate(ptr, Layout::new::<()>()).unwrap();
+    }
+}
+
+#[test]
+fn self_test_detects_corruption() {
+    const HEAP_SIZE: usize = 256;
+    static mut BUMP_MEM: Heap8Byte<HEAP_SIZE> = Heap8Byte([MaybeUninit::uninit(); HEAP_SIZE]);
+    static mut LIST_MEM: Heap8Byte<HEAP_SIZE> = Heap8Byte([MaybeUninit::uninit(); HEAP_SIZE]);
+
+    let bump = LockedBumpAlloc::new();
+    assert_eq!(bump.self_test(), Ok(()));
+    let start = unsafe { &raw mut BUMP_MEM.0 as usize };
+    unsafe {
+        bump.init(start, HEAP_SIZE);
+        bump.try_allocate(Layout::from_size_align(24, 8).unwrap())
+            .unwrap();
+    }
+    assert_eq!(bump.debug_next(), start + 24);
+    assert_eq!(bump.self_test(), Ok(()));
+    for next in [start - 8, start + HEAP_SIZE + 1] {
+        bump.corrupt_next(next);
+        assert_eq!(
+            bump.self_test(),
+            Err(IntegrityError::NextOutOfRange {
+                start,
+                next,
+                end: start + HEAP_SIZE
+            })
+        );
+    }
+
+    let list = LockedLinkedListAlloc::new();
+    let start = unsafe { &raw mut LIST_MEM.0 as usize };
+    unsafe { list.init(start, HEAP_SIZE) };
+    assert_eq!(list.self_test(), Ok(()));
+
+    list.corrupt_front_size(HEAP_SIZE + 8);
+    assert_eq!(
+        list.self_test(),
+        Err(IntegrityError::OutOfBounds {
+            addr: start,
+            size: HEAP_SIZE + 8
+        })
+    );
+    list.corrupt_front_size(8);
+    assert_eq!(
+        list.self_test(),
+        Err(IntegrityError::Undersized {
+            addr: start,
+            size: 8
+        })
+    );
+    list.corrupt_front_size(HEAP_SIZE);
+    assert_eq!(list.self_test(), Ok(()));
+
+    unsafe { list.push_free_region(start + 64, 32) };
+    assert_eq!(
+        list.self_test(),
+        Err(IntegrityError::Overlap {
+            first: start + 64,
+            second: start
+        })
+    );
+}
+
+#[cfg(feature = "checked")]
+#[test]
+#[should_panic(expected = "Allocated object outside the heap")]
+fn checked_bump_alloc_outside_heap() {
+    const HEAP_SIZE: usize = 64;
+    static mut HEAP_MEM: Heap8Byte<HEAP_SIZE> = Heap8Byte([MaybeUninit::uninit(); HEAP_SIZE]);
+
+    let bump = LockedBumpAlloc::new();
+    let start = unsafe { &raw mut HEAP_MEM.0 as usize };
+    unsafe { bump.init(start, HEAP_SIZE) };
+    bump.corrupt_next(start - 16);
+    let _ = unsafe { bump.try_allocate(Layout::from_size_align(8, 8).unwrap()) };
+}
+
+#[cfg(feature = "checked")]
+#[test]
+#[should_panic(expected = "Metadata write outside the heap")]
+fn checked_linked_list_write_outside_heap() {
+    const HEAP_SIZE: usize = 256;
+    static mut HEAP_MEM: Heap8Byte<HEAP_SIZE> = Heap8Byte([MaybeUninit::uninit(); HEAP_SIZE]);
+
+    let list = LockedLinkedListAlloc::new();
+    let start = unsafe { &raw mut HEAP_MEM.0 as usize };
+    unsafe { list.init(start, HEAP_SIZE) };
+    // The free region still reaches past the new end, so splitting it writes
+    // the remainder's node out of bounds.
+    list.corrupt_end(start + 32);
+    let _ = unsafe { list.try_allocate(Layout::from_size_align(32, 8).unwrap()) };
+}
+
+#[cfg(feature = "checked")]
+#[test]
+#[should_panic(expected = "Metadata write outside the heap")]
+fn checked_linked_list_write_outside_side_table() {
+    const HEAP_SIZE: usize = 256;
+    const TABLE_SIZE: usize =
+        crate::linked_list_alloc::LockedLinkedList::side_table_size(HEAP_SIZE);
+    static mut HEAP_MEM: Heap8Byte<HEAP_SIZE> = Heap8Byte([MaybeUninit::uninit(); HEAP_SIZE]);
+    static mut TABLE_MEM: Heap8Byte<TABLE_SIZE> = Heap8Byte([MaybeUninit::uninit(); TABLE_SIZE]);
+
+    let list = LockedLinkedListAlloc::new();
+    let start = unsafe { &raw mut HEAP_MEM.0 as usize };
+    unsafe {
+        list.init_with_side_table(start, HEAP_SIZE, &raw mut TABLE_MEM.0 as usize, TABLE_SIZE)
+    };
+    // The remainder's slot lies past the table sized for the new end.
+    list.corrupt_end(start + 32);
+    let _ = unsafe { list.try_allocate(Layout::from_size_align(32, 8).unwrap()) };
+}
+
+#[cfg(feature = "checked")]
+#[test]
+#[should_panic(expected = "Metadata write outside the heap")]
+fn checked_buddy_write_outside_heap() {
+    const HEAP_SIZE: usize = 512;
+    static mut HEAP_MEM: Heap4096Byte<HEAP_SIZE> = Heap4096Byte([MaybeUninit::uninit(); HEAP_SIZE]);
+
+    let buddy = LockedBuddyAlloc::new();
+    unsafe { buddy.init(&raw mut HEAP_MEM.0 as usize, HEAP_SIZE) };
+    buddy.corrupt_size(HEAP_SIZE / 2);
+    let _ = unsafe { buddy.try_allocate(Layout::from_size_align(64, 8).unwrap()) };
+}
+
+#[test]
+fn tlsf_free_outside_heap() {
+    const HEAP_SIZE: usize = 512;
+    static mut HEAP_MEM: Heap8Byte<HEAP_SIZE> = Heap8Byte([MaybeUninit::uninit(); HEAP_SIZE]);
+
+    let tlsf = LockedTlsfAlloc::new();
+    let start = unsafe { &raw mut HEAP_MEM.0 as usize };
+    let layout = Layout::from_size_align(32, 8).unwrap();
+    unsafe {
+        tlsf.init(start, HEAP_SIZE);
+        let ptr = tlsf.try_allocate(layout).unwrap();
+        tlsf.corrupt_base(ptr.as_ptr() as usize);
+        assert!(matches!(
+            tlsf.try_deallocate(ptr, layout),
+            Err(BAllocatorError::InvalidFree)
+        ));
+    }
+}
+
+#[test]
+fn bad_frees_are_invalid() {
+    const HEAP_SIZE: usize = 256;
+    static mut BUMP_MEM: Heap8Byte<HEAP_SIZE> = Heap8Byte([MaybeUninit::uninit(); HEAP_SIZE]);
+    static mut BUDDY_MEM: Heap4096Byte<HEAP_SIZE> =
+        Heap4096Byte([MaybeUninit::uninit(); HEAP_SIZE]);
+
+    let layout = Layout::from_size_align(8, 8).unwrap();
+    let bump = LockedBumpAlloc::new();
+    let start = unsafe { &raw mut BUMP_MEM.0 as usize };
+    unsafe {
+        bump.init(start, HEAP_SIZE);
+        let outside = NonNull::new((start + HEAP_SIZE) as *mut u8).unwrap();
+        let ptr = bump.try_allocate(layout).unwrap();
+        assert!(matches!(
+            bump.try_deallocate(outside, layout),
+            Err(BAllocatorError::InvalidFree)
+        ));
+        bump.try_deallocate(ptr, layout).unwrap();
+        assert!(matches!(
+            bump.try_deallocate(ptr, layout),
+            Err(BAllocatorError::InvalidFree)
+        ));
+    }
+    assert_eq!(bump.remaining(), HEAP_SIZE);
+
+    static CONST_BUMP: ConstBumpAlloc<HEAP_SIZE> = ConstBumpAlloc::new();
+    static LOCKLESS_BUMP: LocklessBumpAlloc = LocklessBumpAlloc::new();
+    static mut LOCKLESS_MEM: Heap8Byte<HEAP_SIZE> = Heap8Byte([MaybeUninit::uninit(); HEAP_SIZE]);
+    unsafe { LOCKLESS_BUMP.init(&raw mut LOCKLESS_MEM.0 as usize, HEAP_SIZE) };
+    let foreign = NonNull::new(start as *mut u8).unwrap();
+    for bump in [&CONST_BUMP as &dyn BAllocator, &LOCKLESS_BUMP] {
+        unsafe {
+            assert!(matches!(
+                bump.try_deallocate(foreign, layout),
+                Err(BAllocatorError::InvalidFree)
+            ));
+            let ptr = bump.try_allocate(layout).unwrap();
+            bump.try_deallocate(ptr, layout).unwrap();
+            assert!(matches!(
+                bump.try_deallocate(ptr, layout),
+                Err(BAllocatorError::InvalidFree)
+            ));
+        }
+    }
+    assert_eq!(CONST_BUMP.allocations(), 0);
+    assert_eq!(CONST_BUMP.used_bytes_exact(), 0);
+    assert_eq!(LOCKLESS_BUMP.allocations(), 0);
+
+    let buddy = BuddyBuilder::new().tiny_slab(true).build();
+    let tiny = Layout::new::<u8>();
+    unsafe {
+        buddy.init(&raw mut BUDDY_MEM.0 as usize, HEAP_SIZE);
+        let first = buddy.try_allocate(tiny).unwrap();
+        let second = buddy.try_allocate(tiny).unwrap();
+        buddy.try_deallocate(first, tiny).unwrap();
+        assert!(matches!(
+            buddy.try_deallocate(first, tiny),
+            Err(BAllocatorError::InvalidFree)
+        ));
+        assert!(matches!(
+            buddy.try_deallocate(second.add(1), tiny),
+            Err(BAllocatorError::InvalidFree)
+        ));
+        buddy.try_deallocate(second, tiny).unwrap();
+    }
+    assert!(buddy.validate().is_ok());
+}
+
+#[test]
+fn buddy_rejects_oversized_free() {
+    const HEAP_SIZE: usize = PAGE_SIZE * 64;
+    static mut HEAP_MEM: Heap4096Byte<HEAP_SIZE> = Heap4096Byte([MaybeUninit::uninit(); HEAP_SIZE]);
+
+    let allocator = LockedBuddyAlloc::new();
+    let base = unsafe { &raw mut HEAP_MEM.0 as usize };
+    unsafe { allocator.init(base, HEAP_SIZE) };
+
+    let whole = Layout::from_size_align(HEAP_SIZE, 8).unwrap();
+    let half = Layout::from_size_align(PAGE_SIZE * 32, 8).unwrap();
+    let quarter = Layout::from_size_align(PAGE_SIZE * 16, 8).unwrap();
+    let eighth = Layout::from_size_align(PAGE_SIZE * 8, 8).unwrap();
+    unsafe {
+        // Blocks come off the top of the heap, so this keeps pages 32..40
+        // and frees the 24 pages above them to be trimmed.
+        let tail = allocator.try_allocate(quarter).unwrap();
+        let upper = allocator.try_allocate(eighth).unwrap();
+        let kept = allocator.try_allocate(eighth).unwrap();
+        assert_eq!(kept.as_ptr() as usize, base + PAGE_SIZE * 32);
+        allocator.try_deallocate(tail, quarter).unwrap();
+        allocator.try_deallocate(upper, eighth).unwrap();
+        // 40 pages are left, so the top order is still that of `half`.
+        assert_eq!(allocator.trim(), PAGE_SIZE * 24);
+        let counts = allocator.free_counts();
+
+        // Larger than any block the heap still has.
+        let start = NonNull::new(base as *mut u8).unwrap();
+        assert!(matches!(
+            allocator.try_deallocate(start, whole),
+            Err(BAllocatorError::InvalidFree)
+        ));
+        // Aligned and of the top order, but running past the end of the heap.
+        assert!(matches!(
+            allocator.try_deallocate(kept, half),
+            Err(BAllocatorError::InvalidFree)
+        ));
+        assert_eq!(allocator.free_counts(), counts);
+
+        allocator.try_deallocate(kept, eighth).unwrap();
+    }
+    assert!(allocator.validate().is_ok());
+}
+
+#[test]
+fn linked_list_random_fit() {
+    const HEAP_SIZE: usize = 256;
+    static mut HEAP_MEM: Heap8Byte<HEAP_SIZE> = Heap8Byte([MaybeUninit::uninit(); HEAP_SIZE]);
+    static ENTROPY: AtomicUsize = AtomicUsize::new(0);
+    static LIST: LockedLinkedListAlloc = LinkedListBuilder::new()
+        .strategy(FitStrategy::RandomFit(|| {
+            ENTROPY.load(Ordering::Relaxed) as u64
+        }))
+        .build();
+
+    let start = unsafe { &raw mut HEAP_MEM.0 as usize };
+    unsafe { LIST.init(start, HEAP_SIZE) };
+    let layout = Layout::from_size_align(32, 8).unwrap();
+    let large = Layout::from_size_align(64, 8).unwrap();
+    unsafe {
+        // With a single free region there is nothing to choose from.
+        let ptrs = [0; 5].map(|_| LIST.try_allocate(layout).unwrap());
+        for (i, ptr) in ptrs.iter().enumerate() {
+            assert_eq!(ptr.as_ptr() as usize, start + i * 32);
+        }
+        LIST.try_deallocate(ptrs[1], layout).unwrap();
+        LIST.try_deallocate(ptrs[3], layout).unwrap();
+
+        // Two holes and the tail fit, one for each draw.
+        let fitting = [start + 32, start + 96, start + 160];
+        let mut chosen = Vec::new();
+        for entropy in 0..6 {
+            ENTROPY.store(entropy, Ordering::Relaxed);
+            let ptr = LIST.try_allocate(layout).unwrap();
+            assert!(fitting.contains(&(ptr.as_ptr() as usize)));
+            chosen.push(ptr.as_ptr() as usize);
+            LIST.try_deallocate(ptr, layout).unwrap();
+        }
+        chosen.sort_unstable();
+        chosen.dedup();
+        assert_eq!(chosen, fitting);
+
+        // Only the tail fits the larger block, whatever the draw.
+        let ptr = LIST.try_allocate(large).unwrap();
+        assert_eq!(ptr.as_ptr() as usize, start + 160);
+        LIST.try_deallocate(ptr, large).unwrap();
+
+        for ptr in [ptrs[4], ptrs[2], ptrs[0]] {
+            LIST.try_deallocate(ptr, layout).unwrap();
+        }
+    }
+    assert_eq!(LIST.remaining(), HEAP_SIZE);
+}
+
+#[test]
+fn linked_list_builder() {
+    const HEAP_SIZE: usize = 256;
+    static mut HEAP_MEM: Heap8Byte<HEAP_SIZE> = Heap8Byte([MaybeUninit::uninit(); HEAP_SIZE]);
+    static LIST: LockedLinkedListAlloc = LinkedListBuilder::new()
+        .strategy(FitStrategy::BestFit)
+        .guard_bytes(true)
+        .build();
+
+    let start = unsafe { &raw mut HEAP_MEM.0 as usize };
+    unsafe { LIST.init(start, HEAP_SIZE) };
+    // With the guard bytes these take 64, 16, 32 and 16 bytes.
+    let layouts = [56, 8, 24, 8].map(|size| Layout::from_size_align(size, 8).unwrap());
+    let ptrs = layouts.map(|layout| unsafe { LIST.try_allocate(layout).unwrap() });
+    assert_eq!(ptrs[2].as_ptr() as usize, start + 80);
+
+    unsafe {
+        // The 64 byte hole ends up first in the list, but the 32 byte one
+        // fits exactly.
+        LIST.try_deallocate(ptrs[2], layouts[2]).unwrap();
+        LIST.try_deallocate(ptrs[0], layouts[0]).unwrap();
+        let (ptr, usable) = LIST.try_allocate_with_usable(layouts[2]).unwrap();
+        assert_eq!(ptr, ptrs[2]);
+        assert_eq!(usable, 24);
+
+        // One byte past the end lands on the guard.
+        ptr.write_bytes(0, 25);
+        assert!(matches!(
+            LIST.try_deallocate(ptr, layouts[2]),
+            Err(BAllocatorError::GuardOverwritten)
+        ));
+        ptr.add(24).write(GUARD_FILL);
+        LIST.try_deallocate(ptr, layouts[2]).unwrap();
+        LIST.try_deallocate(ptrs[1], layouts[1]).unwrap();
+        LIST.try_deallocate(ptrs[3], layouts[3]).unwrap();
+    }
+    assert_eq!(LIST.self_test(), Ok(()));
+
+    static mut ZEROED_MEM: Heap4096Byte<HEAP_SIZE> =
+        Heap4096Byte([MaybeUninit::new(0xFF); HEAP_SIZE]);
+    let zeroed = LinkedListBuilder::new()
+        .min_align(64)
+        .zero_on_alloc(true)
+        .build();
+    unsafe {
+        zeroed.init(&raw mut ZEROED_MEM.0 as usize, HEAP_SIZE);
+        let layout = Layout::from_size_align(8, 1).unwrap();
+        let first = zeroed.try_allocate(layout).unwrap();
+        let second = zeroed.try_allocate(layout).unwrap();
+        assert_eq!(second.as_ptr() as usize - first.as_ptr() as usize, 64);
+        assert_eq!(second.cast::<u64>().read(), 0);
+        zeroed.try_deallocate(first, layout).unwrap();
+        zeroed.try_deallocate(second, layout).unwrap();
+    }
+}
+
+#[test]
+fn buddy_and_bump_builders() {
+    const HEAP_SIZE: usize = 256;
+    static mut BUDDY_MEM: Heap4096Byte<HEAP_SIZE> =
+        Heap4096Byte([MaybeUninit::uninit(); HEAP_SIZE]);
+    static mut BUMP_MEM: Heap4096Byte<HEAP_SIZE> = Heap4096Byte([MaybeUninit::uninit(); HEAP_SIZE]);
+    static TOP_FREED: AtomicUsize = AtomicUsize::new(0);
+    static GROWN_TO: AtomicUsize = AtomicUsize::new(0);
+    static BUDDY: LockedBuddyAlloc = BuddyBuilder::new()
+        .tiny_slab(true)
+        .on_top_free(|addr| TOP_FREED.store(addr, Ordering::SeqCst))
+        .build();
+    static BUMP: LockedBumpAlloc = BumpBuilder::new()
+        .on_grow(64, |end| GROWN_TO.store(end, Ordering::SeqCst))
+        .build();
+
+    let buddy_start = unsafe { &raw mut BUDDY_MEM.0 as usize };
+    let bump_start = unsafe { &raw mut BUMP_MEM.0 as usize };
+    let tiny = Layout::new::<u8>();
+    unsafe {
+        BUDDY.init(buddy_start, HEAP_SIZE);
+        assert_eq!(BUDDY.base_alignment(), TINY_SLOT_SIZE);
+        let first = BUDDY.try_allocate(tiny).unwrap();
+        let second = BUDDY.try_allocate(tiny).unwrap();
+        assert_eq!(
+            second.as_ptr() as usize,
+            first.as_ptr() as usize + TINY_SLOT_SIZE
+        );
+        BUDDY.try_deallocate(first, tiny).unwrap();
+        BUDDY.try_deallocate(second, tiny).unwrap();
+        assert_eq!(TOP_FREED.load(Ordering::SeqCst), buddy_start);
+
+        BUMP.init(bump_start, HEAP_SIZE);
+        BUMP.try_allocate(Layout::from_size_align(65, 1).unwrap())
+            .unwrap();
+        assert_eq!(GROWN_TO.load(Ordering::SeqCst), bump_start + 128);
+    }
+
+    let rwlocked = BuddyBuilder::new().merge_threshold(2).build_rwlocked();
+    assert!(!rwlocked.is_initialized());
+    let rwlocked = BumpBuilder::new().build_rwlocked();
+    assert!(!rwlocked.is_initialized());
+}
+
+#[test]
+fn linked_list_min_split_avoids_shards() {
+    const HEAP_SIZE: usize = 256;
+    static mut HEAP_MEM: Heap8Byte<HEAP_SIZE> = Heap8Byte([MaybeUninit::uninit(); HEAP_SIZE]);
+
+    let start = unsafe { &raw mut HEAP_MEM.0 as usize };
+    let large = Layout::from_size_align(48, 8).unwrap();
+    let small = Layout::from_size_align(32, 8).unwrap();
+    for (min_split, shards) in [(0, 2), (32, 0)] {
+        let allocator = LinkedListBuilder::new().min_split(min_split).build();
+        unsafe { allocator.init(start, HEAP_SIZE) };
+
+        // Freeing every other block leaves 48 byte holes, and each 32 byte
+        // block placed in one would leave a 16 byte shard behind.
+        unsafe {
+            let blocks: Vec<_> = (0..4)
+                .map(|_| allocator.try_allocate(large).unwrap())
+                .collect();
+            allocator.try_deallocate(blocks[0], large).unwrap();
+            allocator.try_deallocate(blocks[2], large).unwrap();
+            let first = allocator.try_allocate(small).unwrap();
+            let second = allocator.try_allocate(small).unwrap();
+
+            let mut tiny = 0;
+            allocator.debug_free_list(|_, size| tiny += (size < 32) as usize);
+            assert_eq!(tiny, shards);
+            assert!(allocator.self_test().is_ok());
+
+            // Absorbed tails are given back with their blocks.
+            allocator.try_deallocate(first, small).unwrap();
+            allocator.try_deallocate(second, small).unwrap();
+            allocator.try_deallocate(blocks[1], large).unwrap();
+            allocator.try_deallocate(blocks[3], large).unwrap();
+        }
+        allocator.coalesce_all();
+        let mut regions = Vec::new();
+        allocator.debug_free_list(|addr, size| regions.push((addr, size)));
+        assert_eq!(regions, [(start, HEAP_SIZE)]);
+    }
+}
+
+#[test]
+fn linked_list_freeze_and_checksum() {
+    const HEAP_SIZE: usize = 256;
+    static mut HEAP_MEM: Heap8Byte<HEAP_SIZE> = Heap8Byte([MaybeUninit::uninit(); HEAP_SIZE]);
+
+    let list = LockedLinkedListAlloc::new();
+    unsafe { list.init(&raw mut HEAP_MEM.0 as usize, HEAP_SIZE) };
+    let layout = Layout::from_size_align(32, 8).unwrap();
+    let ptr = unsafe { list.try_allocate(layout).unwrap() };
+
+    let checksum = list.freeze_and_checksum();
+    assert!(list.is_frozen());
+    assert_eq!(list.verify_checksum(), Ok(()));
+    assert!(matches!(
+        unsafe { list.try_allocate(layout) },
+        Err(BAllocatorError::Frozen)
+    ));
+    assert!(matches!(
+        unsafe { list.try_deallocate(ptr, layout) },
+        Err(BAllocatorError::Frozen)
+    ));
+    assert_eq!(list.verify_checksum(), Ok(()));
+
+    // A wild write over the free region's node.
+    list.corrupt_front_size(HEAP_SIZE - 64);
+    assert!(matches!(
+        list.verify_checksum(),
+        Err(IntegrityError::ChecksumMismatch { expected, found })
+            if expected == checksum && found != checksum
+    ));
+    list.corrupt_front_size(HEAP_SIZE - 32);
+    assert_eq!(list.verify_checksum(), Ok(()));
+
+    list.thaw();
+    assert!(!list.is_frozen());
+    unsafe { list.try_deallocate(ptr, layout).unwrap() };
+}
+
+#[test]
+fn fallback_on_oom() {
+    const ARENA_SIZE: usize = 64;
+    const HEAP_SIZE: usize = 256;
+    static mut ARENA_MEM: Heap8Byte<ARENA_SIZE> = Heap8Byte([MaybeUninit::uninit(); ARENA_SIZE]);
+    static mut HEAP_MEM: Heap8Byte<HEAP_SIZE> = Heap8Byte([MaybeUninit::uninit(); HEAP_SIZE]);
+
+    let fallback = FallbackAlloc::new(LockedBumpAlloc::new(), LockedLinkedListAlloc::new());
+    unsafe {
+        fallback
+            .primary()
+            .init(&raw mut ARENA_MEM.0 as usize, ARENA_SIZE);
+        fallback
+            .secondary()
+            .init(&raw mut HEAP_MEM.0 as usize, HEAP_SIZE);
+    }
+
+    let layout = Layout::from_size_align(32, 8).unwrap();
+    unsafe {
+        let first = fallback.try_allocate(layout).unwrap();
+        let second = fallback.try_allocate(layout).unwrap();
+        assert!(fallback.primary().contains(first));
+        assert!(fallback.primary().contains(second));
+        assert_eq!(fallback.primary().remaining(), 0);
+
+        // The arena is full, so these come from the heap.
+        let third = fallback.try_allocate(layout).unwrap();
+        assert!(fallback.secondary().contains(third));
+        second.write_bytes(0x42, 32);
+        let grown = fallback.try_reallocate(second, layout, 64).unwrap();
+        assert!(fallback.secondary().contains(grown));
+        assert!(
+            core::slice::from_raw_parts(grown.as_ptr(), 32)
+                .iter()
+                .all(|&b| b == 0x42)
+        );
+
+        fallback.try_deallocate(third, layout).unwrap();
+        fallback
+            .try_deallocate(grown, Layout::from_size_align(64, 8).unwrap())
+            .unwrap();
+        fallback.try_deallocate(first, layout).unwrap();
+    }
+    assert_eq!(fallback.primary().allocations(), 0);
+    assert_eq!(fallback.secondary().allocations(), 0);
+
+    let too_big = Layout::from_size_align(HEAP_SIZE + 8, 8).unwrap();
+    assert!(!fallback.can_allocate(too_big));
+    assert!(unsafe { fallback.try_allocate(too_big) }.is_err_and(|e| e.is_oom()));
+}
+
+/// An allocator that only forwards allocations and frees, as one written
+/// outside this crate might, keeping the default `can_allocate`.
+struct Forwarding<'a>(&'a LockedBumpAlloc);
+
+unsafe impl BAllocator for Forwarding<'_> {
+    unsafe fn try_allocate(&self, layout: Layout) -> Result<NonNull<u8>, BAllocatorError> {
+        return unsafe { self.0.try_allocate(layout) };
+    }
+
+    unsafe fn try_deallocate(
+        &self,
+        ptr: NonNull<u8>,
+        layout: Layout,
+    ) -> Result<(), BAllocatorError> {
+        return unsafe { self.0.try_deallocate(ptr, layout) };
+    }
+}
+
+#[test]
+fn default_can_allocate_does_not_refuse() {
+    const HEAP_SIZE: usize = 64;
+    static mut PRIMARY_MEM: Heap8Byte<HEAP_SIZE> = Heap8Byte([MaybeUninit::uninit(); HEAP_SIZE]);
+    static mut SECONDARY_MEM: Heap8Byte<HEAP_SIZE> = Heap8Byte([MaybeUninit::uninit(); HEAP_SIZE]);
+
+    let primary = LockedBumpAlloc::new();
+    let secondary = LockedBumpAlloc::new();
+    unsafe {
+        primary.init(&raw mut PRIMARY_MEM.0 as usize, HEAP_SIZE);
+        secondary.init(&raw mut SECONDARY_MEM.0 as usize, HEAP_SIZE);
+    }
+    let forwarding = Forwarding(&secondary);
+    assert!(forwarding.reserve(HEAP_SIZE).is_ok());
+
+    // A full primary leaves the answer to the forwarding secondary.
+    let whole = Layout::from_size_align(HEAP_SIZE, 8).unwrap();
+    let fallback = FallbackAlloc::new(primary, forwarding);
+    unsafe {
+        let first = fallback.try_allocate(whole).unwrap();
+        assert!(fallback.reserve(HEAP_SIZE).is_ok());
+        let second = fallback.try_allocate(whole).unwrap();
+        fallback.try_deallocate(second, whole).unwrap();
+        fallback.try_deallocate(first, whole).unwrap();
+    }
+}
+
+/// Allocates and frees zero sized objects of various alignments, which must
+/// not touch the heap.
+unsafe fn alloc_zsts<A: GlobalAlloc>(allocator: &A) {
+    for align in [1, 8, 64, 4096] {
+        let layout = Layout::from_size_align(0, align).unwrap();
+        let ptrs: Vec<_> = (0..100)
+            .map(|_| unsafe { allocator.alloc(layout) })
+            .collect();
+        for ptr in ptrs {
+            assert!(!ptr.is_null());
+            assert_eq!(ptr as usize % align, 0);
+            unsafe { allocator.dealloc(ptr, layout) };
+        }
+    }
+}
+
+#[test]
+fn allocators_zero_sized() {
+    const HEAP_SIZE: usize = 4096;
+    static mut BUMP_MEM: Heap8Byte<HEAP_SIZE> = Heap8Byte([MaybeUninit::uninit(); HEAP_SIZE]);
+    static mut LOCKLESS_MEM: Heap8Byte<HEAP_SIZE> = Heap8Byte([MaybeUninit::uninit(); HEAP_SIZE]);
+    static mut BUDDY_MEM: Heap4096Byte<HEAP_SIZE> =
+        Heap4096Byte([MaybeUninit::uninit(); HEAP_SIZE]);
+    static mut LIST_MEM: Heap8Byte<HEAP_SIZE> = Heap8Byte([MaybeUninit::uninit(); HEAP_SIZE]);
+    static mut TLSF_MEM: Heap8Byte<HEAP_SIZE> = Heap8Byte([MaybeUninit::uninit(); HEAP_SIZE]);
+
+    unsafe {
+        let bump = LockedBumpAlloc::new();
+        bump.init(&raw mut BUMP_MEM.0 as usize, HEAP_SIZE);
+        let lockless = LocklessBumpAlloc::new();
+        lockless.init(&raw mut LOCKLESS_MEM.0 as usize, HEAP_SIZE);
+        let bconst = ConstBumpAlloc::<HEAP_SIZE>::new();
+        let tlsf = LockedTlsfAlloc::new();
+        tlsf.init(&raw mut TLSF_MEM.0 as usize, HEAP_SIZE);
+
+        let remaining = tlsf.remaining();
+        alloc_zsts(&bump);
+        alloc_zsts(&lockless);
+        alloc_zsts(&bconst);
+        alloc_zsts(&tlsf);
+        assert_eq!(bump.remaining(), HEAP_SIZE);
+        assert_eq!(lockless.remaining(), HEAP_SIZE);
+        assert_eq!(bconst.remaining(), HEAP_SIZE);
+        assert_eq!(tlsf.remaining(), remaining);
+        assert_eq!(tlsf.allocations(), 0);
+
+        let buddy = LockedBuddyAlloc::new();
+        buddy.init(&raw mut BUDDY_MEM.0 as usize, HEAP_SIZE);
+        let free_counts = buddy.free_counts();
+        alloc_zsts(&buddy);
+        assert_eq!(buddy.free_counts(), free_counts);
+
+        let list = LockedLinkedListAlloc::new();
+        list.init(&raw mut LIST_MEM.0 as usize, HEAP_SIZE);
+        let free_chain = format!("{list:?}");
+        alloc_zsts(&list);
+        assert_eq!(format!("{list:?}"), free_chain);
+
+        assert!(bump.can_allocate(Layout::from_size_align(0, 8).unwrap()));
+        assert!(
+            bump.try_deallocate_lifo(dangling(Layout::new::<()>()), Layout::new::<()>())
+                .is_ok()
+        );
+        assert_eq!(bump.allocations(), 0);
+    }
+}
+
+#[test]
+fn error_conversions() {
+    let layout_error = Layout::from_size_align(8, 3).unwrap_err();
+    let error = BAllocatorError::from(layout_error.clone());
+    assert!(matches!(error, BAllocatorError::Layout(_)));
+    assert_eq!(
+        format!("{error:?}"),
+        format!("Layout Error: {layout_error:?}")
+    );
+    assert!(!error.is_oom());
+
+    assert!(BAllocatorError::Oom(None).is_oom());
+    assert!(BAllocatorError::Oom(Some(Layout::new::<u64>())).is_oom());
+    assert!(!BAllocatorError::Null.is_oom());
+
+    #[cfg(feature = "allocator_api")]
+    {
+        let _: core::alloc::AllocError = BAllocatorError::Oom(None).into();
+        let _: core::alloc::AllocError = BAllocatorError::Layout(layout_error).into();
+    }
+}
+
+#[test]
+fn pathological_layout_errors() {
+    const HEAP_SIZE: usize = 256;
+    static mut LIST_MEM: Heap8Byte<HEAP_SIZE> = Heap8Byte([MaybeUninit::uninit(); HEAP_SIZE]);
+    static mut GUARDED_MEM: Heap8Byte<HEAP_SIZE> = Heap8Byte([MaybeUninit::uninit(); HEAP_SIZE]);
+    static mut BUDDY_MEM: Heap4096Byte<HEAP_SIZE> =
+        Heap4096Byte([MaybeUninit::uninit(); HEAP_SIZE]);
+
+    let list = LockedLinkedListAlloc::new();
+    let guarded = LinkedListBuilder::new().guard_bytes(true).build();
+    let buddy = LockedBuddyAlloc::new();
+    unsafe {
+        list.init(&raw mut LIST_MEM.0 as usize, HEAP_SIZE);
+        guarded.init(&raw mut GUARDED_MEM.0 as usize, HEAP_SIZE);
+        buddy.init(&raw mut BUDDY_MEM.0 as usize, HEAP_SIZE);
+    }
+
+    // Valid on its own, but overflows once padded to the node alignment, or
+    // once guard bytes are added.
+    let layout = Layout::from_size_align(isize::MAX as usize, 1).unwrap();
+    for allocator in [&list as &dyn BAllocator, &guarded, &buddy] {
+        assert!(!allocator.can_allocate(layout));
+        assert!(matches!(
+            unsafe { allocator.try_allocate(layout) },
+            Err(BAllocatorError::Layout(_))
+        ));
+    }
+}
+
+#[cfg(feature = "buddy_track_live")]
+#[test]
+fn buddy_for_each_live() {
+    const HEAP_SIZE: usize = 4096;
+    static mut HEAP_MEM: Heap4096Byte<HEAP_SIZE> = Heap4096Byte([MaybeUninit::uninit(); HEAP_SIZE]);
+
+    let allocator = LockedBuddyAlloc::new();
+    unsafe { allocator.init(&raw mut HEAP_MEM.0 as usize, HEAP_SIZE) };
+
+    let small = Layout::from_size_align(8, 8).unwrap();
+    let large = Layout::from_size_align(64, 8).unwrap();
+    let (a, b, c) = unsafe {
+        (
+            allocator.alloc(small),
+            allocator.alloc(large),
+            allocator.alloc(small),
+        )
+    };
+    unsafe { allocator.dealloc(b, large) };
+
+    let mut live = Vec::new();
+    let untracked = allocator.for_each_live(|addr, order| live.push((addr, order)));
+    assert_eq!(untracked, 0);
+    live.sort();
+    let mut expected = [(a as usize, 0), (c as usize, 0)];
+    expected.sort();
+    assert_eq!(live, expected);
+
+    // Tiny slab slots show up as the block they are carved from, until the
+    // last of them is freed.
+    allocator.set_tiny_slab(true);
+    let tiny = Layout::new::<u8>();
+    let (first, second) = unsafe { (allocator.alloc(tiny), allocator.alloc(tiny)) };
+    let mut slab = Vec::new();
+    let untracked = allocator.for_each_live(|addr, order| {
+        if order == TINY_ORDER {
+            slab.push(addr);
+        }
+    });
+    assert_eq!(untracked, 0);
+    assert_eq!(slab, [first as usize]);
+    assert_eq!(second as usize, first as usize + TINY_SLOT_SIZE);
+    unsafe {
+        allocator.dealloc(first, tiny);
+        allocator.dealloc(second, tiny);
+    }
+    let mut count = 0;
+    assert_eq!(allocator.for_each_live(|_, _| count += 1), 0);
+    assert_eq!(count, 2);
+
+    // Past `LIVE_CAPACITY` the walk is short, and says by how much.
+    let extra: Vec<_> = (0..crate::buddy_alloc::LIVE_CAPACITY - 1)
+        .map(|_| unsafe { allocator.alloc(small) })
+        .collect();
+    let mut count = 0;
+    assert_eq!(allocator.for_each_live(|_, _| count += 1), 1);
+    assert_eq!(count, crate::buddy_alloc::LIVE_CAPACITY);
+    for ptr in extra {
+        unsafe { allocator.dealloc(ptr, small) };
+    }
+    assert_eq!(allocator.for_each_live(|_, _| {}), 0);
+}
+
+/// Runs with and without `compact_meta`, which must not change behaviour.
+#[test]
+fn compact_meta_small_heap() {
+    const HEAP_SIZE: usize = 512;
+    static mut BUDDY_MEM: Heap4096Byte<HEAP_SIZE> =
+        Heap4096Byte([MaybeUninit::uninit(); HEAP_SIZE]);
+    static mut LIST_MEM: Heap8Byte<HEAP_SIZE> = Heap8Byte([MaybeUninit::uninit(); HEAP_SIZE]);
+
+    let buddy = LockedBuddyAlloc::new();
+    let list = LinkedListBuilder::new().min_split(64).build();
+    let buddy_start = unsafe { &raw mut BUDDY_MEM.0 as usize };
+    let list_start = unsafe { &raw mut LIST_MEM.0 as usize };
+    unsafe {
+        buddy.init(buddy_start, HEAP_SIZE);
+        list.init(list_start, HEAP_SIZE);
+    }
+
+    let layouts = [8, 24, 64, 8, 200].map(|size| Layout::from_size_align(size, 8).unwrap());
+    unsafe {
+        let blocks = layouts.map(|layout| {
+            (
+                buddy.try_allocate(layout).unwrap(),
+                list.try_allocate(layout).unwrap(),
+            )
+        });
+        assert_eq!(&buddy.free_counts()[..7], [0, 1, 0, 0, 1, 0, 0]);
+        #[cfg(feature = "buddy_track_live")]
+        {
+            let mut live = Vec::new();
+            let untracked = buddy.for_each_live(|addr, order| live.push((addr, order)));
+            assert_eq!(untracked, 0);
+            live.sort();
+            let mut expected: Vec<_> = blocks
+                .iter()
+                .zip([0, 2, 3, 0, 5])
+                .map(|((ptr, _), order)| (ptr.as_ptr() as usize, order))
+                .collect();
+            expected.sort();
+            assert_eq!(live, expected);
+        }
+        // 160 of the 192 bytes left would leave a shard under 64 bytes, so
+        // the block takes all of them.
+        let tail_layout = Layout::from_size_align(160, 8).unwrap();
+        let tail = list.try_allocate(tail_layout).unwrap();
+        assert_eq!(list.remaining(), 0);
+        list.try_deallocate(tail, tail_layout).unwrap();
+
+        for ((buddy_ptr, list_ptr), layout) in blocks.into_iter().zip(layouts).rev() {
+            buddy.try_deallocate(buddy_ptr, layout).unwrap();
+            list.try_deallocate(list_ptr, layout).unwrap();
+        }
+    }
+    assert_eq!(buddy.free_counts()[6], 1);
+    assert_eq!(list.remaining(), HEAP_SIZE);
+    assert_eq!(list.free_region_count(), 1);
+}
+
+#[cfg(all(feature = "compact_meta", target_pointer_width = "64"))]
+#[test]
+#[should_panic(expected = "Heap too large for compact_meta")]
+fn compact_meta_rejects_large_heap() {
+    let _ = unsafe { LockedBuddyAlloc::new_with_static_heap(0x1000, 1 << 33) };
+}
+
+#[cfg(feature = "tagging")]
+#[test]
+fn usage_by_tag() {
+    const HEAP_SIZE: usize = 512;
+    static mut HEAP_MEM: Heap8Byte<HEAP_SIZE> = Heap8Byte([MaybeUninit::uninit(); HEAP_SIZE]);
+
+    const NET: u16 = 1;
+    const FS: u16 = 2;
+    let allocator = LockedLinkedListAlloc::new();
+    unsafe { allocator.init(&raw mut HEAP_MEM.0 as usize, HEAP_SIZE) };
+
+    let small = Layout::from_size_align(32, 8).unwrap();
+    let large = Layout::from_size_align(96, 8).unwrap();
+    unsafe {
+        let a = allocator.try_allocate_tagged(small, NET).unwrap();
+        let b = allocator.try_allocate_tagged(large, FS).unwrap();
+        let c = allocator.try_allocate_tagged(small, NET).unwrap();
+        let untagged = allocator.try_allocate(small).unwrap();
+        assert_eq!(allocator.usage_by_tag::<3>(), [(NET, 64), (FS, 96), (0, 0)]);
+
+        allocator.try_deallocate(a, small).unwrap();
+        let b = allocator.try_reallocate(b, large, 128).unwrap();
+        assert_eq!(allocator.usage_by_tag::<2>(), [(NET, 32), (FS, 128)]);
+
+        allocator
+            .try_deallocate(b, Layout::from_size_align(128, 8).unwrap())
+            .unwrap();
+        allocator.try_deallocate_bounded(c, small).unwrap();
+        allocator.try_deallocate(untagged, small).unwrap();
+    }
+    assert_eq!(allocator.usage_by_tag::<1>(), [(0, 0)]);
+}
+
+#[cfg(feature = "fill_on_free")]
+#[test]
+fn allocators_fill_on_free() {
+    use crate::FREE_FILL;
+
+    const HEAP_SIZE: usize = 4096;
+    // The first bytes of a freed block may hold free list metadata.
+    const SKIP: usize = 2 * size_of::<usize>();
+
+    fn assert_filled<A: GlobalAlloc>(allocator: &A) {
+        let layout = Layout::from_size_align(64, 8).unwrap();
+        unsafe {
+            let ptr = allocator.alloc(layout);
+            let _keep = allocator.alloc(layout);
+            ptr.write_bytes(0x11, layout.size());
+            allocator.dealloc(ptr, layout);
+
+            let freed = core::slice::from_raw_parts(ptr, layout.size());
+            assert!(freed[SKIP..].iter().all(|&byte| byte == FREE_FILL));
+        }
+    }
+
+    static mut BUMP_HEAP: Heap4096Byte<HEAP_SIZE> =
+        Heap4096Byte([MaybeUninit::uninit(); HEAP_SIZE]);
+    static mut LIST_HEAP: Heap4096Byte<HEAP_SIZE> =
+        Heap4096Byte([MaybeUninit::uninit(); HEAP_SIZE]);
+    static mut BUDDY_HEAP: Heap4096Byte<HEAP_SIZE> =
+        Heap4096Byte([MaybeUninit::uninit(); HEAP_SIZE]);
+    static mut TLSF_HEAP: Heap4096Byte<HEAP_SIZE> =
+        Heap4096Byte([MaybeUninit::uninit(); HEAP_SIZE]);
+
+    let bump = LockedBumpAlloc::new();
+    let linked_list = LockedLinkedListAlloc::new();
+    let buddy = LockedBuddyAlloc::new();
+    let tlsf = LockedTlsfAlloc::new();
+    unsafe {
+        bump.init(&raw mut BUMP_HEAP.0 as usize, HEAP_SIZE);
+        linked_list.init(&raw mut LIST_HEAP.0 as usize, HEAP_SIZE);
+        buddy.init(&raw mut BUDDY_HEAP.0 as usize, HEAP_SIZE);
+        tlsf.init(&raw mut TLSF_HEAP.0 as usize, HEAP_SIZE);
+    }
+
+    assert_filled(&bump);
+    assert_filled(&linked_list);
+    assert_filled(&buddy);
+    assert_filled(&tlsf);
+}
+
+#[cfg(feature = "oom_panic")]
+#[test]
+#[should_panic(expected = "Allocation of Layout { size: 128, align: 8")]
+fn global_alloc_oom_panics() {
+    const HEAP_SIZE: usize = 64;
+    static mut HEAP_MEM: Heap8Byte<HEAP_SIZE> = Heap8Byte([MaybeUninit::uninit(); HEAP_SIZE]);
+
+    let allocator = LockedBumpAlloc::new();
+    unsafe {
+        allocator.init(&raw mut HEAP_MEM.0 as usize, HEAP_SIZE);
+        allocator.alloc(Layout::from_size_align(128, 8).unwrap());
+    }
+}
+
+// The std critical section is a global lock, so the second thread could
+// not even get as far as trying the allocator's.
+#[cfg(not(feature = "critical-section"))]
+#[test]
+fn bounded_lock_would_block() {
+    const HEAP_SIZE: usize = 256;
+    static mut LOCKED_MEM: Heap8Byte<HEAP_SIZE> = Heap8Byte([MaybeUninit::uninit(); HEAP_SIZE]);
+    static mut RWLOCKED_MEM: Heap8Byte<HEAP_SIZE> = Heap8Byte([MaybeUninit::uninit(); HEAP_SIZE]);
+
+    let locked = LockedTlsfAlloc::new();
+    let rwlocked = RwLockedBumpAlloc::new();
+    unsafe {
+        locked.init(&raw mut LOCKED_MEM.0 as usize, HEAP_SIZE);
+        rwlocked.init(&raw mut RWLOCKED_MEM.0 as usize, HEAP_SIZE);
+    }
+    let layout = Layout::from_size_align(16, 8).unwrap();
+
+    let held = (locked.alloc.lock(), rwlocked.alloc.read());
+    std::thread::scope(|scope| {
+        scope.spawn(|| unsafe {
+            assert!(matches!(
+                locked.try_allocate_bounded(layout),
+                Err(BAllocatorError::WouldBlock)
+            ));
+            assert!(matches!(
+                rwlocked.try_allocate_bounded(layout),
+                Err(BAllocatorError::WouldBlock)
+            ));
+        });
+    });
+    drop(held);
+
+    unsafe {
+        let ptr = locked.try_allocate_bounded(layout).unwrap();
+        locked.try_deallocate_bounded(ptr, layout).unwrap();
+    }
+    // Giving up on the lock counts as a failed allocation.
+    assert!(matches!(
+        rwlocked.last_error(),
+        Some(BAllocatorError::WouldBlock)
+    ));
+    let metrics = locked.metrics();
+    assert_eq!(metrics.total_alloc_calls, 2);
+    assert_eq!(metrics.total_alloc_failures, 1);
+    assert_eq!(metrics.total_dealloc_calls, 1);
+}
+
+#[cfg(feature = "critical-section")]
+#[test]
+fn critical_section_locked_allocators() {
+    const HEAP_SIZE: usize = 4096;
+    static mut HEAP_MEM: Heap8Byte<HEAP_SIZE> = Heap8Byte([MaybeUninit::uninit(); HEAP_SIZE]);
+    static ALLOCATOR: LockedTlsfAlloc = LockedTlsfAlloc::new();
+
+    unsafe { ALLOCATOR.init(&raw mut HEAP_MEM.0 as usize, HEAP_SIZE) };
+    let initial = ALLOCATOR.remaining();
+
+    let workers: Vec<_> = (0..4)
+        .map(|_| {
+            std::thread::spawn(|| unsafe {
+                let layout = Layout::from_size_align(32, 8).unwrap();
+                for _ in 0..100 {
+                    let ptr = ALLOCATOR.alloc(layout);
+                    assert!(!ptr.is_null());
+                    ALLOCATOR.dealloc(ptr, layout);
+                }
+            })
+        })
+        .collect();
+    for worker in workers {
+        worker.join().unwrap();
+    }
+
+    assert_eq!(ALLOCATOR.allocations(), 0);
+    assert_eq!(ALLOCATOR.remaining(), initial);
+}
+
+/// Allocates layouts of mixed alignment, returning the bytes requested and
+/// the padding placed between them.
+unsafe fn alloc_mixed_alignment(allocator: &impl BAllocator) -> (usize, usize) {
+    let layouts = [(1, 1), (8, 8), (3, 1), (16, 16), (2, 2)];
+    let (mut requested, mut padding) = (0, 0);
+    let mut prev_end = None;
+
+    for (size, align) in layouts {
+        let layout = Layout::from_size_align(size, align).unwrap();
+        let addr = unsafe { allocator.try_allocate(layout).unwrap().as_ptr() as usize };
+        padding += prev_end.map_or(0, |end| addr - end);
+        requested += size;
+        prev_end = Some(addr + size);
+    }
+    return (requested, padding);
+}
+
+#[test]
+#[cfg(not(feature = "oom_panic"))]
+fn global_alloc_last_error() {
+    const HEAP_SIZE: usize = 64;
+    static mut HEAP_MEM: Heap8Byte<HEAP_SIZE> = Heap8Byte([MaybeUninit::uninit(); HEAP_SIZE]);
+
+    let allocator = LockedLinkedListAlloc::new();
+    unsafe { allocator.init(&raw mut HEAP_MEM.0 as usize, HEAP_SIZE) };
+    assert!(allocator.last_error().is_none());
+
+    let layout = Layout::from_size_align(HEAP_SIZE * 2, 8).unwrap();
+    unsafe {
+        assert!(allocator.alloc(layout).is_null());
+    }
+    assert!(matches!(
+        allocator.last_error(),
+        Some(BAllocatorError::Oom(Some(l))) if l == layout
+    ));
+
+    // Successes leave the last error in place.
+    unsafe {
+        let small = Layout::from_size_align(8, 8).unwrap();
+        let ptr = allocator.alloc(small);
+        assert!(!ptr.is_null());
+        allocator.dealloc(ptr, small);
+    }
+    assert!(allocator.last_error().unwrap().is_oom());
+}
+
+/// Counts how often interrupts were masked and panics if a disable nests or
+/// a restore comes without one.
+struct MockGuard {
+    masked: AtomicBool,
+    disables: AtomicUsize,
+    restores: AtomicUsize,
+}
+
+impl InterruptGuard for MockGuard {
+    fn disable(&self) -> usize {
+        assert!(!self.masked.swap(true, Ordering::SeqCst), "nested disable");
+        return self.disables.fetch_add(1, Ordering::SeqCst);
+    }
+
+    fn restore(&self, state: usize) {
+        assert!(
+            self.masked.swap(false, Ordering::SeqCst),
+            "restore without disable"
+        );
+        assert_eq!(state, self.restores.fetch_add(1, Ordering::SeqCst));
+    }
+}
+
+#[test]
+fn interrupt_guard_brackets_each_op() {
+    const HEAP_SIZE: usize = 256;
+    static mut BUMP_MEM: Heap8Byte<HEAP_SIZE> = Heap8Byte([MaybeUninit::uninit(); HEAP_SIZE]);
+    static mut BUDDY_MEM: Heap4096Byte<HEAP_SIZE> =
+        Heap4096Byte([MaybeUninit::uninit(); HEAP_SIZE]);
+    static GUARD: MockGuard = MockGuard {
+        masked: AtomicBool::new(false),
+        disables: AtomicUsize::new(0),
+        restores: AtomicUsize::new(0),
+    };
+
+    let bump = LockedBumpAlloc::new();
+    let buddy = LockedBuddyAlloc::new();
+    unsafe {
+        bump.init(&raw mut BUMP_MEM.0 as usize, HEAP_SIZE);
+        buddy.init(&raw mut BUDDY_MEM.0 as usize, HEAP_SIZE);
+    }
+    bump.set_interrupt_guard(&GUARD).unwrap();
+    buddy.set_interrupt_guard(&GUARD).unwrap();
+    assert!(matches!(
+        bump.set_interrupt_guard(&GUARD),
+        Err(BAllocatorError::InterruptGuardSet)
+    ));
+    let allocators: [&dyn BAllocator; 2] = [&bump, &buddy];
+
+    let layout = Layout::from_size_align(16, 8).unwrap();
+    for allocator in allocators {
+        unsafe {
+            let ptr = allocator.try_allocate(layout).unwrap();
+            let grown = allocator.try_reallocate(ptr, layout, 32).unwrap();
+            allocator
+                .try_deallocate(grown, Layout::from_size_align(32, 8).unwrap())
+                .unwrap();
+
+            // Zero sized requests never reach the allocator, so are not masked.
+            let empty = allocator.try_allocate(Layout::new::<()>()).unwrap();
+            allocator
+                .try_deallocate(empty, Layout::new::<()>())
+                .unwrap();
+        }
+    }
+    assert_eq!(GUARD.disables.load(Ordering::SeqCst), 6);
+
+    // Queries and inherent methods take the lock masked as well.
+    assert_eq!(bump.allocations(), 0);
+    assert!(bump.remaining() > 0);
+    assert!(buddy.can_allocate(layout));
+    assert_eq!(buddy.coalesce_all(), (0, 0));
+    assert_eq!(buddy.free_counts().iter().sum::<usize>(), 1);
+    assert_eq!(GUARD.disables.load(Ordering::SeqCst), 11);
+    assert_eq!(GUARD.restores.load(Ordering::SeqCst), 11);
+    assert!(!GUARD.masked.load(Ordering::SeqCst));
+}
+
+/// Allocates odd sizes with byte alignment until `allocator` runs out,
+/// checking each block meets its base alignment, and returns the lowest
+/// alignment seen.
+unsafe fn lowest_alignment<A: BAllocator>(allocator: &A) -> usize {
+    let base = allocator.base_alignment();
+    let mut lowest = usize::MAX;
+    let mut blocks = Vec::new();
+    for size in [1, 3, 5, 7, 9, 13].into_iter().cycle().take(32) {
+        let layout = Layout::from_size_align(size, 1).unwrap();
+        let Ok(ptr) = (unsafe { allocator.try_allocate(layout) }) else {
+            break;
+        };
+        let addr = ptr.as_ptr() as usize;
+        assert!(addr.is_multiple_of(base), "{addr:#X} not aligned to {base}");
+        lowest = lowest.min(1 << addr.trailing_zeros());
+        blocks.push((ptr, layout));
+    }
+    for (ptr, layout) in blocks.into_iter().rev() {
+        unsafe { allocator.try_deallocate(ptr, layout).unwrap() };
+    }
+    return lowest;
+}
+
+#[test]
+fn base_alignment_holds() {
+    const HEAP_SIZE: usize = 512;
+    static mut BUMP_MEM: Heap8Byte<HEAP_SIZE> = Heap8Byte([MaybeUninit::uninit(); HEAP_SIZE]);
+    static mut LIST_MEM: Heap4096Byte<HEAP_SIZE> = Heap4096Byte([MaybeUninit::uninit(); HEAP_SIZE]);
+    static mut BUDDY_MEM: Heap4096Byte<HEAP_SIZE> =
+        Heap4096Byte([MaybeUninit::uninit(); HEAP_SIZE]);
+    static mut TLSF_MEM: Heap4096Byte<HEAP_SIZE> = Heap4096Byte([MaybeUninit::uninit(); HEAP_SIZE]);
+    static ALIGNED_LIST: LockedLinkedListAlloc = LinkedListBuilder::new().min_align(32).build();
+
+    unsafe {
+        // Bump blocks start wherever the previous one ended.
+        let bump = LockedBumpAlloc::new();
+        bump.init(&raw mut BUMP_MEM.0 as usize, HEAP_SIZE);
+        assert_eq!(bump.base_alignment(), 1);
+        assert_eq!(lowest_alignment(&bump), 1);
+
+        // Heaps starting just off a 16 byte boundary show the guarantee is
+        // no stronger than reported.
+        let list = LockedLinkedListAlloc::new();
+        list.init(&raw mut LIST_MEM.0 as usize + 8, HEAP_SIZE - 8);
+        assert_eq!(list.base_alignment(), 8);
+        assert_eq!(lowest_alignment(&list), 8);
+        list.deinit().unwrap();
+        ALIGNED_LIST.init(&raw mut LIST_MEM.0 as usize + 8, HEAP_SIZE - 8);
+        assert_eq!(ALIGNED_LIST.base_alignment(), 32);
+        assert_eq!(lowest_alignment(&ALIGNED_LIST), 32);
+
+        let buddy = LockedBuddyAlloc::new();
+        buddy.init(&raw mut BUDDY_MEM.0 as usize, HEAP_SIZE);
+        assert_eq!(buddy.base_alignment(), PAGE_SIZE);
+        assert_eq!(lowest_alignment(&buddy), PAGE_SIZE);
+        buddy.set_tiny_slab(true);
+        assert_eq!(buddy.base_alignment(), TINY_SLOT_SIZE);
+        assert_eq!(lowest_alignment(&buddy), TINY_SLOT_SIZE);
+
+        let tlsf = LockedTlsfAlloc::new();
+        tlsf.init(&raw mut TLSF_MEM.0 as usize + 8, HEAP_SIZE - 8);
+        assert_eq!(tlsf.base_alignment(), 8);
+        assert_eq!(lowest_alignment(&tlsf), 8);
+
+        let fallback = FallbackAlloc::new(LockedTlsfAlloc::new(), LockedBumpAlloc::new());
+        assert_eq!(fallback.base_alignment(), 1);
+    }
+}
+
+#[test]
+fn alloc_metrics_count_calls() {
+    const HEAP_SIZE: usize = 64;
+    static mut HEAP_MEM: Heap8Byte<HEAP_SIZE> = Heap8Byte([MaybeUninit::uninit(); HEAP_SIZE]);
+
+    let allocator = LockedBumpAlloc::new();
+    unsafe { allocator.init(&raw mut HEAP_MEM.0 as usize, HEAP_SIZE) };
+    assert_eq!(allocator.metrics(), AllocMetrics::default());
+
+    let small = Layout::from_size_align(16, 8).unwrap();
+    let large = Layout::from_size_align(HEAP_SIZE * 2, 8).unwrap();
+    unsafe {
+        let first = allocator.try_allocate(small).unwrap();
+        let second = allocator.try_allocate(small).unwrap();
+        assert!(allocator.try_allocate(large).is_err());
+        assert!(allocator.try_allocate(large).is_err());
+        let empty = allocator.try_allocate(Layout::new::<()>()).unwrap();
+
+        allocator.try_deallocate(second, small).unwrap();
+        allocator.try_deallocate(first, small).unwrap();
+        allocator
+            .try_deallocate(empty, Layout::new::<()>())
+            .unwrap();
+    }
+    assert_eq!(
+        allocator.metrics(),
+        AllocMetrics {
+            total_alloc_calls: 5,
+            total_alloc_failures: 2,
+            total_dealloc_calls: 3,
+        }
+    );
+    assert_eq!(allocator.allocations(), 0);
+}
+
+#[test]
+#[cfg(not(feature = "oom_panic"))]
+fn global_alloc_ext() {
+    const HEAP_SIZE: usize = 128;
+    static mut HEAP_MEM: Heap8Byte<HEAP_SIZE> = Heap8Byte([MaybeUninit::new(0xFF); HEAP_SIZE]);
+
+    let bump = LockedBumpAlloc::new();
+    unsafe { bump.init(&raw mut HEAP_MEM.0 as usize, HEAP_SIZE) };
+    let allocator: &dyn GlobalAlloc = &bump;
+
+    let layout = Layout::from_size_align(16, 8).unwrap();
+    unsafe {
+        let ptr = allocator.alloc_zeroed_checked(layout).unwrap();
+        assert!(
+            core::slice::from_raw_parts(ptr.as_ptr(), 16)
+                .iter()
+                .all(|&b| b == 0)
+        );
+        ptr.write_bytes(0x42, 16);
+
+        let grown = allocator.realloc_checked(ptr, layout, 48).unwrap();
+        assert!(
+            core::slice::from_raw_parts(grown.as_ptr(), 16)
+                .iter()
+                .all(|&b| b == 0x42)
+        );
+        let layout = Layout::from_size_align(48, 8).unwrap();
+
+        assert!(matches!(
+            allocator.realloc_checked(grown, layout, HEAP_SIZE),
+            Err(BAllocatorError::Oom(Some(l))) if l.size() == HEAP_SIZE
+        ));
+        assert!(matches!(
+            allocator.realloc_checked(grown, layout, usize::MAX),
+            Err(BAllocatorError::Layout(_))
+        ));
+        assert!(
+            allocator
+                .alloc_zeroed_checked(Layout::from_size_align(HEAP_SIZE, 8).unwrap())
+                .unwrap_err()
+                .is_oom()
+        );
+    }
+}
+
+#[test]
+fn allocate_2d_row_alignment() {
+    const HEAP_SIZE: usize = 1024;
+    static mut HEAP_MEM: Heap8Byte<HEAP_SIZE> = Heap8Byte([MaybeUninit::uninit(); HEAP_SIZE]);
+
+    let allocator = LockedBumpAlloc::new();
+    let heap_start = unsafe { &raw mut HEAP_MEM.0 as usize };
+    unsafe { allocator.init(heap_start, HEAP_SIZE) };
+
+    let (rows, cols, row_align) = (5, 3, 16);
+    let elem = Layout::new::<[u8; 6]>();
+    unsafe {
+        // Pad the bump pointer off any 16 byte boundary.
+        allocator.try_allocate(Layout::new::<u8>()).unwrap();
+
+        let (ptr, stride) = allocator
+            .try_allocate_2d(rows, cols, elem, row_align)
+            .unwrap();
+        assert_eq!(stride, 32);
+        let start = ptr.as_ptr() as usize;
+        for row in 0..rows {
+            let row_start = start + row * stride;
+            assert!(row_start.is_multiple_of(row_align));
+            assert!(row_start + cols * elem.size() <= start + rows * stride);
+            assert!(allocator.contains(NonNull::new(row_start as *mut u8).unwrap()));
+        }
+        assert_eq!(allocator.used(), start - heap_start + rows * stride);
+
+        assert!(matches!(
+            allocator.try_allocate_2d(usize::MAX, 2, elem, row_align),
+            Err(BAllocatorError::Overflowed)
+        ));
+        assert!(matches!(
+            allocator.try_allocate_2d(1, 1, elem, 3),
+            Err(BAllocatorError::Layout(_))
+        ));
+    }
+}
+
+#[test]
+fn heap_box_frees_on_drop() {
+    const HEAP_SIZE: usize = 128;
+    static mut HEAP_MEM: Heap8Byte<HEAP_SIZE> = Heap8Byte([MaybeUninit::uninit(); HEAP_SIZE]);
+
+    let allocator = LockedBumpAlloc::new();
+    unsafe { allocator.init(&raw mut HEAP_MEM.0 as usize, HEAP_SIZE) };
+
+    {
+        let mut boxed = HeapBox::try_new([0u8; 32], &allocator).unwrap();
+        assert!(allocator.contains(NonNull::from(&*boxed).cast()));
+        boxed[0] = 1;
+        boxed[31] = 2;
+        assert_eq!((boxed[0], boxed[1], boxed[31]), (1, 0, 2));
+        assert_eq!(allocator.allocations(), 1);
+        assert_eq!(allocator.remaining(), HEAP_SIZE - 32);
+    }
+    assert_eq!(allocator.allocations(), 0);
+    assert_eq!(allocator.remaining(), HEAP_SIZE);
+
+    let boxed = HeapBox::try_new([7u8; 32], &allocator).unwrap();
+    assert_eq!(boxed.into_inner(), [7; 32]);
+    assert_eq!(allocator.allocations(), 0);
+
+    assert!(HeapBox::try_new([0u8; HEAP_SIZE + 1], &allocator).is_err());
+}
+
+#[test]
+fn migrate_bump_to_linked_list() {
+    const HEAP_SIZE: usize = 256;
+    static mut HEAP_MEM: Heap8Byte<HEAP_SIZE> = Heap8Byte([MaybeUninit::uninit(); HEAP_SIZE]);
+
+    #[derive(Debug, Clone, Copy, PartialEq)]
+    struct Packet {
+        id: u32,
+        flags: u32,
+        payload: [u8; 24],
+    }
+
+    let arena = ConstBumpAlloc::<HEAP_SIZE>::new();
+    let heap = LockedLinkedListAlloc::new();
+    unsafe { heap.init(&raw mut HEAP_MEM.0 as usize, HEAP_SIZE) };
+
+    let packet = Packet {
+        id: 0xDEAD_BEEF,
+        flags: 0x0102,
+        payload: core::array::from_fn(|i| i as u8),
+    };
+    unsafe {
+        let src = arena.try_allocate_typed::<Packet>().unwrap();
+        let src = NonNull::new_unchecked((*src.as_ptr()).write(packet));
+
+        let dst = heap.migrate(src).unwrap();
+        assert!(heap.contains(dst.cast()));
+        assert_eq!(*dst.as_ref(), packet);
+        assert_eq!(
+            core::slice::from_raw_parts(dst.as_ptr().cast::<u8>(), size_of::<Packet>()),
+            core::slice::from_raw_parts(src.as_ptr().cast::<u8>(), size_of::<Packet>())
+        );
+
+        arena
+            .try_deallocate_typed(src.cast::<MaybeUninit<Packet>>())
+            .unwrap();
+        assert_eq!(arena.remaining(), HEAP_SIZE);
+        assert_eq!(*dst.as_ref(), packet);
+    }
+}
+
+#[test]
+fn bump_used_bytes_exact() {
+    const HEAP_SIZE: usize = 256;
+    static mut LOCKED_MEM: Heap8Byte<HEAP_SIZE> = Heap8Byte([MaybeUninit::uninit(); HEAP_SIZE]);
+    static mut LOCKLESS_MEM: Heap8Byte<HEAP_SIZE> = Heap8Byte([MaybeUninit::uninit(); HEAP_SIZE]);
+
+    let locked = LockedBumpAlloc::new();
+    let lockless = LocklessBumpAlloc::new();
+    let bconst = ConstBumpAlloc::<HEAP_SIZE>::new();
+    unsafe {
+        locked.init(&raw mut LOCKED_MEM.0 as usize, HEAP_SIZE);
+        lockless.init(&raw mut LOCKLESS_MEM.0 as usize, HEAP_SIZE);
+
+        let (requested, padding) = alloc_mixed_alignment(&locked);
+        assert!(padding > 0);
+        assert_eq!(locked.used_bytes_exact(), requested);
+        assert_eq!(locked.used() - locked.used_bytes_exact(), padding);
+
+        let (requested, padding) = alloc_mixed_alignment(&lockless);
+        assert_eq!(lockless.used_bytes_exact(), requested);
+        assert_eq!(lockless.used() - lockless.used_bytes_exact(), padding);
+
+        let (requested, padding) = alloc_mixed_alignment(&bconst);
+        assert_eq!(bconst.used_bytes_exact(), requested);
+        assert_eq!(bconst.used() - bconst.used_bytes_exact(), padding);
+
+        let ptr = locked.try_allocate(Layout::new::<u64>()).unwrap();
+        let before = locked.used_bytes_exact();
+        locked.try_deallocate(ptr, Layout::new::<u64>()).unwrap();
+        assert_eq!(locked.used_bytes_exact(), before - 8);
+        assert!(locked.used_bytes_exact() <= locked.used());
+    }
+}
+
+#[test]
+fn lockless_bump_concurrent_allocations_never_overlap() {
+    const THREADS: usize = 8;
+    const PER_THREAD: usize = 128;
+    const BLOCK: usize = 16;
+    const HEAP_SIZE: usize = THREADS * PER_THREAD * BLOCK;
+    static mut HEAP_MEM: Heap8Byte<HEAP_SIZE> = Heap8Byte([MaybeUninit::uninit(); HEAP_SIZE]);
+    static ALLOCATOR: LocklessBumpAlloc = LocklessBumpAlloc::new();
+
+    unsafe { ALLOCATOR.init(&raw mut HEAP_MEM.0 as usize, HEAP_SIZE) };
+
+    let workers: Vec<_> = (0..THREADS)
+        .map(|_| {
+            std::thread::spawn(|| unsafe {
+                let layout = Layout::from_size_align(BLOCK, 8).unwrap();
+                (0..PER_THREAD)
+                    .map(|_| ALLOCATOR.try_allocate(layout).unwrap().as_ptr() as usize)
+                    .collect::<Vec<_>>()
+            })
+        })
+        .collect();
+    let mut addrs: Vec<usize> = workers
+        .into_iter()
+        .flat_map(|worker| worker.join().unwrap())
+        .collect();
+
+    // Every block is the same size, so sorted neighbours at least `BLOCK`
+    // apart means no two overlap.
+    addrs.sort_unstable();
+    assert_eq!(addrs.len(), THREADS * PER_THREAD);
+    assert!(addrs.windows(2).all(|pair| pair[1] - pair[0] >= BLOCK));
+    assert_eq!(ALLOCATOR.allocations(), THREADS * PER_THREAD);
+    assert_eq!(ALLOCATOR.remaining(), 0);
+}
+
+#[test]
+fn lockless_bump_concurrent_partial_allocations() {
+    const THREADS: usize = 4;
+    const HEAP_SIZE: usize = 1024;
+    static mut HEAP_MEM: Heap8Byte<HEAP_SIZE> = Heap8Byte([MaybeUninit::uninit(); HEAP_SIZE]);
+    static ALLOCATOR: LocklessBumpAlloc = LocklessBumpAlloc::new();
+
+    unsafe { ALLOCATOR.init(&raw mut HEAP_MEM.0 as usize, HEAP_SIZE) };
+
+    // Requests larger than what is left race to take the remainder, and
+    // between them are granted exactly the heap without overlapping.
+    let workers: Vec<_> = (0..THREADS)
+        .map(|_| {
+            std::thread::spawn(|| unsafe {
+                let layout = Layout::from_size_align(HEAP_SIZE / 3, 1).unwrap();
+                let mut blocks = Vec::new();
+                while let Ok((ptr, granted)) = ALLOCATOR.try_allocate_partial(layout) {
+                    blocks.push((ptr.as_ptr() as usize, granted));
+                }
+                blocks
+            })
+        })
+        .collect();
+    let mut blocks: Vec<(usize, usize)> = workers
+        .into_iter()
+        .flat_map(|worker| worker.join().unwrap())
+        .collect();
+
+    blocks.sort_unstable();
+    assert!(
+        blocks
+            .windows(2)
+            .all(|pair| pair[0].0 + pair[0].1 <= pair[1].0)
+    );
+    assert_eq!(
+        blocks.iter().map(|&(_, granted)| granted).sum::<usize>(),
+        HEAP_SIZE
+    );
+    assert_eq!(ALLOCATOR.used_bytes_exact(), HEAP_SIZE);
+    assert_eq!(ALLOCATOR.allocations(), blocks.len());
+}
+
+#[test]
+fn lockless_bump_reset_keeps_live_blocks() {
+    const THREADS: usize = 4;
+    const ROUNDS: usize = 2000;
+    const BLOCK: usize = 32;
+    const HEAP_SIZE: usize = THREADS * BLOCK * 4;
+    static mut HEAP_MEM: Heap8Byte<HEAP_SIZE> = Heap8Byte([MaybeUninit::uninit(); HEAP_SIZE]);
+    static ALLOCATOR: LocklessBumpAlloc = LocklessBumpAlloc::new();
+
+    unsafe { ALLOCATOR.init(&raw mut HEAP_MEM.0 as usize, HEAP_SIZE) };
+
+    // Freeing the last live block resets `next` while other threads
+    // allocate, which must never hand out a block still being written.
+    let workers: Vec<_> = (0..THREADS as u8)
+        .map(|id| {
+            std::thread::spawn(move || unsafe {
+                let layout = Layout::from_size_align(BLOCK, 8).unwrap();
+                for _ in 0..ROUNDS {
+                    let ptr = loop {
+                        if let Ok(ptr) = ALLOCATOR.try_allocate(layout) {
+                            break ptr;
+                        }
+                        std::thread::yield_now();
+                    };
+                    ptr.as_ptr().write_bytes(id, BLOCK);
+                    std::thread::yield_now();
+                    let block = core::slice::from_raw_parts(ptr.as_ptr(), BLOCK);
+                    assert!(block.iter().all(|&byte| byte == id));
+                    ALLOCATOR.try_deallocate(ptr, layout).unwrap();
+                }
+            })
+        })
+        .collect();
+    for worker in workers {
+        worker.join().unwrap();
+    }
+    assert_eq!(ALLOCATOR.allocations(), 0);
+    assert_eq!(ALLOCATOR.used_bytes_exact(), 0);
+}
+
+#[test]
+fn lockless_bump_last_free_races_allocations() {
+    const ROUNDS: usize = 5000;
+    const BLOCK: usize = 32;
+    const HEAP_SIZE: usize = BLOCK * 64;
+    static mut HEAP_MEM: Heap8Byte<HEAP_SIZE> = Heap8Byte([MaybeUninit::uninit(); HEAP_SIZE]);
+    static ALLOCATOR: LocklessBumpAlloc = LocklessBumpAlloc::new();
+
+    unsafe { ALLOCATOR.init(&raw mut HEAP_MEM.0 as usize, HEAP_SIZE) };
+    let layout = Layout::from_size_align(BLOCK, 8).unwrap();
+
+    // One thread keeps freeing what is usually the only live block, so
+    // every free tries to reset `next`, while the other allocates and checks
+    // its block is not handed out again before it is freed.
+    let churn = std::thread::spawn(move || unsafe {
+        for _ in 0..ROUNDS {
+            if let Ok(ptr) = ALLOCATOR.try_allocate(layout) {
+                ptr.as_ptr().write_bytes(u8::MAX, BLOCK);
+                ALLOCATOR.try_deallocate(ptr, layout).unwrap();
+            }
+        }
+    });
+    let checker = std::thread::spawn(move || unsafe {
+        for round in 0..ROUNDS {
+            let Ok(ptr) = ALLOCATOR.try_allocate(layout) else {
+                std::thread::yield_now();
+                continue;
+            };
+            let fill = (round % u8::MAX as usize) as u8;
+            ptr.as_ptr().write_bytes(fill, BLOCK);
+            std::thread::yield_now();
+            let block = core::slice::from_raw_parts(ptr.as_ptr(), BLOCK);
+            assert!(block.iter().all(|&byte| byte == fill));
+            ALLOCATOR.try_deallocate(ptr, layout).unwrap();
+        }
+    });
+    churn.join().unwrap();
+    checker.join().unwrap();
+    assert_eq!(ALLOCATOR.allocations(), 0);
+    assert_eq!(ALLOCATOR.debug_next(), unsafe {
+        &raw mut HEAP_MEM.0 as usize
+    });
+}
+
+#[test]
+fn te() {
+    const HEAP_SIZE: usize = 4096 * 8192;
+    static mut HEAP_MEM: Heap8Byte<HEAP_SIZE> = Heap8Byte([MaybeUninit::uninit(); HEAP_SIZE]);
+
+    loom::model(|| {
+        let allocator = LockedBuddyAlloc::new();
+        unsafe { allocator.init(&raw mut HEAP_MEM.0 as usize, HEAP_SIZE) };
+
+        let a = Arc::new(allocator);
+        let b = a.clone();
+        thread::spawn(move || unsafe {
+            let _ = b.alloc(Layout::from_size_align(8, 1).unwrap());
+        });
+        unsafe {
+            let _ = a.alloc(Layout::from_size_align(32, 1).unwrap());
+        }
+    });
+}
+
+#[test]
+fn buddy_min_order() {
+    const HEAP_SIZE: usize = 512;
+    static mut HEAP_MEM: Heap8Byte<HEAP_SIZE> = Heap8Byte([MaybeUninit::uninit(); HEAP_SIZE]);
+
+    let allocator = LockedBuddyAlloc::new();
+    unsafe { allocator.init(&raw mut HEAP_MEM.0 as usize, HEAP_SIZE) };
+    let top_order = (HEAP_SIZE / PAGE_SIZE).ilog2() as usize;
+    let mut whole = [0; NR_MAX_ORDER];
+    whole[top_order] = 1;
+
+    for size in [1, PAGE_SIZE << MIN_ORDER] {
+        let layout = Layout::from_size_align(size, 1).unwrap();
+        unsafe {
+            let ptr = allocator.try_allocate(layout).unwrap();
+
+            // Split from the top down to `MIN_ORDER` and no further, leaving
+            // one free buddy at each order in between.
+            let counts = allocator.free_counts();
+            assert!(counts[..MIN_ORDER].iter().all(|&count| count == 0));
+            assert!(counts[MIN_ORDER..top_order].iter().all(|&count| count == 1));
+            assert_eq!(counts[top_order], 0);
+
+            allocator.try_deallocate(ptr, layout).unwrap();
+        }
+        // And coalesce all the way back up from `MIN_ORDER`.
+        assert_eq!(allocator.free_counts(), whole);
+    }
+    assert_eq!(allocator.prewarm(MIN_ORDER, 2), 2);
+}
+
 #[test]
-fn te() {
-    const HEAP_SIZE: usize = 4096 * 8192;
+fn buddy_validate_after_churn() {
+    const HEAP_SIZE: usize = 4096;
     static mut HEAP_MEM: Heap8Byte<HEAP_SIZE> = Heap8Byte([MaybeUninit::uninit(); HEAP_SIZE]);
 
-    loom::model(|| {
-        let allocator = LockedBuddyAlloc::new();
-        unsafe { allocator.init(&raw mut HEAP_MEM.0 as usize, HEAP_SIZE) };
+    let allocator = LockedBuddyAlloc::new();
+    unsafe { allocator.init(&raw mut HEAP_MEM.0 as usize, HEAP_SIZE) };
+    assert_eq!(allocator.validate(), Ok(()));
 
-        let a = Arc::new(allocator);
-        let b = a.clone();
-        thread::spawn(move || unsafe {
-            let _ = b.alloc(Layout::from_size_align(8, 1).unwrap());
-        });
+    let mut live: [Option<(NonNull<u8>, Layout)>; 32] = [None; 32];
+    let mut seed: u32 = 0x1234_5678;
+    for _ in 0..2000 {
+        seed = seed.wrapping_mul(1_103_515_245).wrapping_add(12_345);
+        let slot = &mut live[(seed >> 16) as usize % 32];
         unsafe {
-            let _ = a.alloc(Layout::from_size_align(32, 1).unwrap());
+            match slot.take() {
+                Some((ptr, layout)) => allocator.try_deallocate(ptr, layout).unwrap(),
+                None => {
+                    let layout =
+                        Layout::from_size_align(1 + (seed >> 8) as usize % 200, 8).unwrap();
+                    *slot = allocator.try_allocate(layout).ok().map(|ptr| (ptr, layout));
+                }
+            }
+        }
+        assert_eq!(allocator.validate(), Ok(()));
+    }
+}
+
+#[test]
+fn buddy_realloc_in_place() {
+    const HEAP_SIZE: usize = 512;
+    static mut HEAP_MEM: Heap8Byte<HEAP_SIZE> = Heap8Byte([MaybeUninit::uninit(); HEAP_SIZE]);
+
+    let allocator = LockedBuddyAlloc::new();
+    let start = unsafe { &raw mut HEAP_MEM.0 as usize };
+    unsafe { allocator.init(start, HEAP_SIZE) };
+
+    let layout = Layout::from_size_align(20, 8).unwrap();
+    unsafe {
+        // 20 and 30 bytes both take a 32 byte block.
+        let ptr = allocator.alloc(layout);
+        ptr.write_bytes(0xA5, 20);
+        assert_eq!(allocator.realloc(ptr, layout, 30), ptr);
+        let layout = Layout::from_size_align(30, 8).unwrap();
+        allocator.dealloc(ptr, layout);
+
+        // The heap start is the lower buddy at every order, so once the
+        // rest of the heap is free it can grow to any size in place.
+        let blocks: [_; HEAP_SIZE / 32] = core::array::from_fn(|_| allocator.alloc(layout));
+        let low = start as *mut u8;
+        assert!(blocks.contains(&low));
+        for block in blocks.into_iter().filter(|&block| block != low) {
+            allocator.dealloc(block, layout);
+        }
+        low.write_bytes(0x5A, 30);
+
+        let grown = allocator.realloc(low, layout, 200);
+        assert_eq!(grown, low);
+        assert!(
+            core::slice::from_raw_parts(grown, 30)
+                .iter()
+                .all(|&b| b == 0x5A)
+        );
+        assert_eq!(allocator.validate(), Ok(()));
+        let mut expected = [0; NR_MAX_ORDER];
+        expected[5] = 1;
+        assert_eq!(allocator.free_counts()[..7], expected[..7]);
+
+        // Nothing free above a block at the top of the heap, so it moves.
+        let grown_layout = Layout::from_size_align(200, 8).unwrap();
+        let top = allocator.alloc(layout);
+        assert!(top as usize > start + HEAP_SIZE / 2);
+        top.write_bytes(0x3C, 30);
+        allocator.dealloc(grown, grown_layout);
+        let moved = allocator.realloc(top, layout, 100);
+        assert_ne!(moved, top);
+        assert!(
+            core::slice::from_raw_parts(moved, 30)
+                .iter()
+                .all(|&b| b == 0x3C)
+        );
+        assert_eq!(allocator.validate(), Ok(()));
+    }
+}
+
+#[test]
+fn buddy_shrink_in_place() {
+    const HEAP_SIZE: usize = 512;
+    static mut HEAP_MEM: Heap8Byte<HEAP_SIZE> = Heap8Byte([MaybeUninit::uninit(); HEAP_SIZE]);
+
+    let allocator = LockedBuddyAlloc::new();
+    unsafe { allocator.init(&raw mut HEAP_MEM.0 as usize, HEAP_SIZE) };
+
+    let large = Layout::from_size_align(128, 8).unwrap();
+    let small = Layout::from_size_align(32, 8).unwrap();
+    unsafe {
+        let ptr = allocator.try_allocate(large).unwrap();
+        let shrunk = allocator.shrink(ptr, large, small).unwrap();
+        assert_eq!(shrunk, ptr);
+
+        // The order 4 block is split down to order 2, leaving its upper
+        // halves free at orders 3 and 2.
+        let mut expected = [0; NR_MAX_ORDER];
+        expected[2..6].fill(1);
+        assert_eq!(allocator.free_counts(), expected);
+        assert_eq!(allocator.validate(), Ok(()));
+
+        let quarter = allocator.try_allocate(small).unwrap();
+        assert_eq!(quarter.as_ptr(), ptr.as_ptr().add(32));
+        let half_layout = Layout::from_size_align(64, 8).unwrap();
+        let half = allocator.try_allocate(half_layout).unwrap();
+        assert_eq!(half.as_ptr(), ptr.as_ptr().add(64));
+
+        assert!(allocator.shrink(ptr, small, large).is_err());
+
+        allocator.try_deallocate(half, half_layout).unwrap();
+        allocator.try_deallocate(quarter, small).unwrap();
+        allocator.try_deallocate(ptr, small).unwrap();
+    }
+    let mut expected = [0; NR_MAX_ORDER];
+    expected[6] = 1;
+    assert_eq!(allocator.free_counts(), expected);
+}
+
+#[test]
+fn buddy_free_counts() {
+    const HEAP_SIZE: usize = 512;
+    static mut HEAP_MEM: Heap8Byte<HEAP_SIZE> = Heap8Byte([MaybeUninit::uninit(); HEAP_SIZE]);
+
+    let allocator = LockedBuddyAlloc::new();
+    unsafe { allocator.init(&raw mut HEAP_MEM.0 as usize, HEAP_SIZE) };
+
+    let mut expected = [0; NR_MAX_ORDER];
+    expected[6] = 1;
+    assert_eq!(allocator.free_counts(), expected);
+
+    // Allocating a single page splits the order 6 block down to order 0,
+    // leaving one free buddy behind at each order on the way down.
+    let _ptr = unsafe { allocator.alloc(Layout::from_size_align(8, 1).unwrap()) };
+    let mut expected = [0; NR_MAX_ORDER];
+    expected[..6].fill(1);
+    assert_eq!(allocator.free_counts(), expected);
+}
+
+#[test]
+fn buddy_allocatable_counts() {
+    const HEAP_SIZE: usize = 512;
+    static mut HEAP_MEM: Heap8Byte<HEAP_SIZE> = Heap8Byte([MaybeUninit::uninit(); HEAP_SIZE]);
+
+    let allocator = LockedBuddyAlloc::new();
+    unsafe { allocator.init(&raw mut HEAP_MEM.0 as usize, HEAP_SIZE) };
+
+    // The single order 6 block splits into 2^(6 - k) blocks of order k.
+    let counts = allocator.allocatable_counts();
+    assert_eq!(counts[..7], [64, 32, 16, 8, 4, 2, 1]);
+    assert!(counts[7..].iter().all(|&count| count == 0));
+    assert_eq!(allocator.free_counts().iter().sum::<usize>(), 1);
+
+    // A 64 byte block leaves 32 + 16 + 8 + 4 + ... of what was there.
+    let layout = Layout::from_size_align(64, 8).unwrap();
+    let ptr = unsafe { allocator.try_allocate(layout).unwrap() };
+    let counts = allocator.allocatable_counts();
+    assert_eq!(counts[..7], [56, 28, 14, 7, 3, 1, 0]);
+    for (order, &count) in counts.iter().enumerate().take(7) {
+        let block = Layout::from_size_align(PAGE_SIZE << order, 8).unwrap();
+        assert_eq!(count > 0, allocator.can_allocate(block), "order: {order}");
+    }
+    unsafe { allocator.try_deallocate(ptr, layout).unwrap() };
+}
+
+#[test]
+fn buddy_tiny_slab() {
+    const HEAP_SIZE: usize = 1024;
+    const COUNT: usize = 64;
+    static mut HEAP_MEM: Heap4096Byte<HEAP_SIZE> = Heap4096Byte([MaybeUninit::uninit(); HEAP_SIZE]);
+
+    let allocator = LockedBuddyAlloc::new();
+    unsafe { allocator.init(&raw mut HEAP_MEM.0 as usize, HEAP_SIZE) };
+    allocator.set_tiny_slab(true);
+    let free_bytes = || {
+        allocator
+            .free_counts()
+            .iter()
+            .enumerate()
+            .map(|(order, &count)| count * (PAGE_SIZE << order))
+            .sum::<usize>()
+    };
+    let free_counts = allocator.free_counts();
+
+    let layout = Layout::new::<u8>();
+    let mut ptrs: Vec<_> = unsafe {
+        (0..COUNT)
+            .map(|_| allocator.try_allocate(layout).unwrap())
+            .collect()
+    };
+    for (i, ptr) in ptrs.iter().enumerate() {
+        unsafe { ptr.write(i as u8) };
+    }
+    // One byte each would take a whole page, the slots pack them into two
+    // slab blocks.
+    let used = HEAP_SIZE - free_bytes();
+    assert_eq!(used, 2 * (PAGE_SIZE << TINY_ORDER));
+    assert!(used < COUNT * PAGE_SIZE);
+    assert_eq!(COUNT * TINY_SLOT_SIZE, used);
+    for (i, ptr) in ptrs.iter().enumerate() {
+        assert_eq!(unsafe { ptr.read() }, i as u8);
+    }
+
+    unsafe {
+        // Still fits the slot, so stays put, and anything larger moves out.
+        assert_eq!(
+            allocator.try_reallocate(ptrs[0], layout, 2).unwrap(),
+            ptrs[0]
+        );
+        let moved = allocator
+            .try_reallocate(ptrs[0], Layout::from_size_align(2, 1).unwrap(), 16)
+            .unwrap();
+        assert_eq!(moved.read(), 0);
+        allocator
+            .try_deallocate(moved, Layout::from_size_align(16, 1).unwrap())
+            .unwrap();
+
+        allocator.try_deallocate(ptrs[1], layout).unwrap();
+        assert!(matches!(
+            allocator.try_deallocate(ptrs[1], layout),
+            Err(BAllocatorError::InvalidFree)
+        ));
+        for ptr in ptrs.drain(2..) {
+            allocator.try_deallocate(ptr, layout).unwrap();
+        }
+    }
+    assert_eq!(allocator.free_counts(), free_counts);
+}
+
+#[cfg(not(feature = "oom_panic"))]
+#[test]
+fn buddy_oom_returns_null() {
+    const HEAP_SIZE: usize = 64;
+    static mut HEAP_MEM: Heap8Byte<HEAP_SIZE> = Heap8Byte([MaybeUninit::uninit(); HEAP_SIZE]);
+    static LOGGER: AllocErrorLogger = AllocErrorLogger;
+
+    if log::set_logger(&LOGGER).is_ok() {
+        log::set_max_level(LevelFilter::Error);
+    }
+
+    let allocator = LockedBuddyAlloc::new();
+    unsafe { allocator.init(&raw mut HEAP_MEM.0 as usize, HEAP_SIZE) };
+
+    let ptr = unsafe { allocator.alloc(Layout::from_size_align(HEAP_SIZE * 2, 8).unwrap()) };
+    assert!(ptr.is_null());
+
+    #[cfg(debug_assertions)]
+    assert!(ALLOC_ERROR_LOGGED.load(Ordering::SeqCst));
+}
+
+#[test]
+fn linked_list_free_by_ptr() {
+    const HEAP_SIZE: usize = 256;
+    static mut HEAP_MEM: Heap8Byte<HEAP_SIZE> = Heap8Byte([MaybeUninit::uninit(); HEAP_SIZE]);
+
+    let allocator = LockedLinkedListAlloc::new();
+    unsafe { allocator.init(&raw mut HEAP_MEM.0 as usize, HEAP_SIZE) };
+
+    // Large enough that a second allocation only fits if the first was reclaimed.
+    let layout = Layout::from_size_align(160, 16).unwrap();
+
+    unsafe {
+        let ptr1 = allocator.try_allocate_tracked(layout).unwrap();
+        assert_eq!(ptr1.as_ptr() as usize % layout.align(), 0);
+        allocator.free_by_ptr(ptr1).unwrap();
+
+        let ptr2 = allocator.try_allocate_tracked(layout).unwrap();
+        assert_eq!(ptr1, ptr2);
+        allocator.free_by_ptr(ptr2).unwrap();
+
+        // Tracked calls are counted and recorded like any other.
+        let too_big = Layout::from_size_align(HEAP_SIZE, 16).unwrap();
+        assert!(allocator.try_allocate_tracked(too_big).is_err());
+        assert!(matches!(
+            allocator.last_error(),
+            Some(BAllocatorError::Oom(_))
+        ));
+    }
+    let metrics = allocator.metrics();
+    assert_eq!(metrics.total_alloc_calls, 3);
+    assert_eq!(metrics.total_alloc_failures, 1);
+    assert_eq!(metrics.total_dealloc_calls, 2);
+}
+
+#[test]
+fn bump_on_grow_at_page_boundaries() {
+    const HEAP_SIZE: usize = 64;
+    const PAGE: usize = 16;
+    static mut HEAP_MEM: Heap4096Byte<HEAP_SIZE> = Heap4096Byte([MaybeUninit::uninit(); HEAP_SIZE]);
+    static GROWS: std::sync::Mutex<Vec<usize>> = std::sync::Mutex::new(Vec::new());
+    fn on_grow(new_end: usize) {
+        GROWS.lock().unwrap().push(new_end);
+    }
+
+    let allocator = LockedBumpAlloc::new();
+    let start = unsafe { &raw mut HEAP_MEM.0 as usize };
+    unsafe { allocator.init(start, HEAP_SIZE) };
+    allocator.set_on_grow(PAGE, on_grow);
+
+    let grows_after = |size: usize| {
+        let layout = Layout::from_size_align(size, 1).unwrap();
+        let result = unsafe { allocator.try_allocate(layout) };
+        return (result.is_ok(), GROWS.lock().unwrap().clone());
+    };
+    // The first page is assumed mapped, and filling it exactly does not
+    // cross into the next.
+    assert_eq!(grows_after(8), (true, vec![]));
+    assert_eq!(grows_after(8), (true, vec![]));
+    assert_eq!(grows_after(4), (true, vec![start + 32]));
+    assert_eq!(grows_after(8), (true, vec![start + 32]));
+    // Reaching into two new pages at once is one call.
+    assert_eq!(grows_after(28), (true, vec![start + 32, start + 64]));
+    // `end` is still the limit, and failures do not grow.
+    assert_eq!(grows_after(16), (false, vec![start + 32, start + 64]));
+    assert_eq!(allocator.debug_next(), start + 56);
+}
+
+#[test]
+fn bump_on_grow_allocate_high() {
+    const HEAP_SIZE: usize = 64;
+    const PAGE: usize = 16;
+    static mut HEAP_MEM: Heap4096Byte<HEAP_SIZE> = Heap4096Byte([MaybeUninit::uninit(); HEAP_SIZE]);
+    static GROWS: std::sync::Mutex<Vec<usize>> = std::sync::Mutex::new(Vec::new());
+    fn on_grow(new_end: usize) {
+        GROWS.lock().unwrap().push(new_end);
+    }
+
+    let allocator = LockedBumpAlloc::new();
+    let start = unsafe { &raw mut HEAP_MEM.0 as usize };
+    unsafe { allocator.init(start, HEAP_SIZE) };
+    allocator.set_on_grow(PAGE, on_grow);
+
+    let layout = Layout::from_size_align(8, 8).unwrap();
+    unsafe {
+        // The top page is only reachable by mapping everything below it.
+        allocator.try_allocate_high(layout).unwrap();
+        assert_eq!(*GROWS.lock().unwrap(), vec![start + HEAP_SIZE]);
+        allocator.try_allocate_high(layout).unwrap();
+        allocator
+            .try_allocate(Layout::from_size_align(48, 8).unwrap())
+            .unwrap();
+        assert_eq!(*GROWS.lock().unwrap(), vec![start + HEAP_SIZE]);
+
+        assert!(allocator.try_allocate_high(layout).is_err());
+        assert!(allocator.last_error().unwrap().is_oom());
+        let metrics = allocator.metrics();
+        assert_eq!(metrics.total_alloc_calls, 4);
+        assert_eq!(metrics.total_alloc_failures, 1);
+    }
+}
+
+#[test]
+fn bump_tokens_invalidated_by_reset() {
+    const HEAP_SIZE: usize = 64;
+    static mut HEAP_MEM: Heap8Byte<HEAP_SIZE> = Heap8Byte([MaybeUninit::uninit(); HEAP_SIZE]);
+    static mut OTHER_MEM: Heap8Byte<HEAP_SIZE> = Heap8Byte([MaybeUninit::uninit(); HEAP_SIZE]);
+
+    let layout = Layout::from_size_align(8, 8).unwrap();
+    let locked = LockedBumpAlloc::new();
+    let constant = ConstBumpAlloc::<HEAP_SIZE>::new();
+    unsafe { locked.init(&raw mut HEAP_MEM.0 as usize, HEAP_SIZE) };
+
+    unsafe {
+        let (first, first_token) = locked.try_allocate_with_token(layout).unwrap();
+        let (second, second_token) = locked.try_allocate_with_token(layout).unwrap();
+        assert_eq!(first_token, second_token);
+
+        // Rewinding over the last block is not a reset.
+        locked.try_deallocate_lifo(second, layout).unwrap();
+        assert!(locked.validate_token(&first_token));
+        locked.try_deallocate(first, layout).unwrap();
+        assert!(!locked.validate_token(&first_token));
+
+        let (_, token) = locked.try_allocate_with_token(layout).unwrap();
+        assert!(locked.validate_token(&token));
+        locked.swap_heap(&raw mut OTHER_MEM.0 as usize, HEAP_SIZE);
+        assert!(!locked.validate_token(&token));
+
+        let (ptr, token) = constant.try_allocate_with_token(layout).unwrap();
+        assert!(constant.validate_token(&token));
+        constant.try_deallocate(ptr, layout).unwrap();
+        assert!(!constant.validate_token(&token));
+
+        let (_, reused) = constant.try_allocate_with_token(layout).unwrap();
+        assert!(constant.validate_token(&reused));
+    }
+}
+
+#[test]
+fn prefault_keeps_heap_state() {
+    const HEAP_SIZE: usize = 512;
+    static mut BUMP_MEM: Heap8Byte<HEAP_SIZE> = Heap8Byte([MaybeUninit::uninit(); HEAP_SIZE]);
+    static mut LIST_MEM: Heap8Byte<HEAP_SIZE> = Heap8Byte([MaybeUninit::uninit(); HEAP_SIZE]);
+    static mut BUDDY_MEM: Heap4096Byte<HEAP_SIZE> =
+        Heap4096Byte([MaybeUninit::uninit(); HEAP_SIZE]);
+
+    let layout = Layout::from_size_align(64, 8).unwrap();
+    unsafe {
+        let bump = LockedBumpAlloc::new();
+        bump.init(&raw mut BUMP_MEM.0 as usize, HEAP_SIZE);
+        bump.prefault(64);
+        assert_eq!(bump.remaining(), HEAP_SIZE);
+        assert!(bump.try_allocate(layout).is_ok());
+        assert_eq!(bump.remaining(), HEAP_SIZE - 64);
+
+        let list = LockedLinkedListAlloc::new();
+        list.init(&raw mut LIST_MEM.0 as usize, HEAP_SIZE);
+        let before = list.remaining();
+        list.prefault(64);
+        assert_eq!(list.remaining(), before);
+        assert!(list.self_test().is_ok());
+        let ptr = list.try_allocate(layout).unwrap();
+        list.try_deallocate(ptr, layout).unwrap();
+        assert_eq!(list.remaining(), before);
+
+        // Both before the heap is split and once the free lists hold blocks.
+        let buddy =
+            LockedBuddyAlloc::new_with_static_heap(&raw mut BUDDY_MEM.0 as usize, HEAP_SIZE);
+        buddy.prefault(4096);
+        assert_eq!(buddy.free_counts()[6], 1);
+        assert_eq!(buddy.prewarm(3, 1), 2);
+        let counts = buddy.free_counts();
+        buddy.prefault(64);
+        assert_eq!(buddy.free_counts(), counts);
+        assert!(buddy.validate().is_ok());
+        let ptr = buddy.try_allocate(layout).unwrap();
+        buddy.try_deallocate(ptr, layout).unwrap();
+        buddy.coalesce_all();
+        assert_eq!(buddy.free_counts()[6], 1);
+    }
+}
+
+#[test]
+fn bump_allocate_from_both_ends() {
+    const HEAP_SIZE: usize = 64;
+    static mut HEAP_MEM: Heap8Byte<HEAP_SIZE> = Heap8Byte([MaybeUninit::uninit(); HEAP_SIZE]);
+
+    let allocator = LockedBumpAlloc::new();
+    let start = unsafe { &raw mut HEAP_MEM.0 as usize };
+    unsafe { allocator.init(start, HEAP_SIZE) };
+    let end = start + HEAP_SIZE;
+
+    let block = |size| Layout::from_size_align(size, 8).unwrap();
+    unsafe {
+        let low = allocator.try_allocate(block(16)).unwrap();
+        let high = allocator.try_allocate_high(block(16)).unwrap();
+        assert_eq!(low.as_ptr() as usize, start);
+        assert_eq!(high.as_ptr() as usize, end - 16);
+
+        // Unaligned sizes still start each high block aligned.
+        let odd = allocator.try_allocate_high(block(5)).unwrap();
+        assert_eq!(odd.as_ptr() as usize, end - 24);
+        assert_eq!(allocator.remaining(), 24);
+        assert_eq!(allocator.used(), 40);
+
+        // The cursors meet exactly, after which neither end has room.
+        let last = allocator.try_allocate(block(24)).unwrap();
+        assert_eq!(last.as_ptr() as usize, start + 16);
+        assert_eq!(allocator.remaining(), 0);
+        assert!(matches!(
+            allocator.try_allocate(block(1)),
+            Err(BAllocatorError::Oom(_))
+        ));
+        assert!(matches!(
+            allocator.try_allocate_high(block(1)),
+            Err(BAllocatorError::Oom(_))
+        ));
+        assert!(allocator.self_test().is_ok());
+
+        allocator.try_deallocate(low, block(16)).unwrap();
+        allocator.try_deallocate(high, block(16)).unwrap();
+        allocator.try_deallocate(odd, block(5)).unwrap();
+        allocator.try_deallocate(last, block(24)).unwrap();
+    }
+    assert_eq!(allocator.remaining(), HEAP_SIZE);
+}
+
+#[test]
+fn bump_lockless_boundary_conditions() {
+    const HEAP_SIZE: usize = 100;
+    static mut HEAP_MEM: Heap8Byte<HEAP_SIZE> = Heap8Byte([MaybeUninit::uninit(); HEAP_SIZE]);
+
+    let allocator = LocklessBumpAlloc::new();
+    let start = unsafe { &raw mut HEAP_MEM.0 as usize };
+    unsafe { allocator.init(start, HEAP_SIZE) };
+    assert_eq!(allocator.debug_next(), start);
+
+    let layout = Layout::from_size_align(10, 1).unwrap();
+    let mut ptrs = Vec::new();
+    unsafe {
+        while let Ok(ptr) = allocator.try_allocate(layout) {
+            ptrs.push(ptr);
+            assert_eq!(allocator.debug_next(), start + ptrs.len() * 10);
+        }
+        assert_eq!(ptrs.len(), HEAP_SIZE / 10);
+        assert_eq!(allocator.debug_next(), start + HEAP_SIZE);
+
+        for ptr in ptrs {
+            allocator.try_deallocate(ptr, layout).unwrap();
+        }
+    }
+    // Freeing the last allocation resets the heap.
+    assert_eq!(allocator.debug_next(), start);
+}
+
+#[test]
+fn bump_const_boundary_conditions() {
+    const HEAP_SIZE: usize = 100;
+    let allocator = ConstBumpAlloc::<HEAP_SIZE>::new();
+    let start = allocator.debug_heap_start();
+
+    let layout = Layout::from_size_align(10, 1).unwrap();
+    let mut ptrs = Vec::new();
+    unsafe {
+        while let Ok(ptr) = allocator.try_allocate(layout) {
+            assert_eq!(ptr.as_ptr() as usize, start + ptrs.len() * 10);
+            ptrs.push(ptr);
+        }
+        assert_eq!(ptrs.len(), HEAP_SIZE / 10);
+        assert_eq!(allocator.debug_next(), start + HEAP_SIZE);
+
+        for ptr in ptrs {
+            allocator.try_deallocate(ptr, layout).unwrap();
+        }
+    }
+    assert_eq!(allocator.debug_next(), start);
+}
+
+#[test]
+fn linked_list_locked_combine_free_regions() {
+    const HEAP_SIZE: usize = 64;
+    static mut HEAP_MEM: Heap8Byte<HEAP_SIZE> = Heap8Byte([MaybeUninit::uninit(); HEAP_SIZE]);
+
+    let allocator = LockedLinkedListAlloc::new();
+    let start = unsafe { &raw mut HEAP_MEM.0 as usize };
+    unsafe { allocator.init(start, HEAP_SIZE) };
+
+    let free_list = || {
+        let mut regions = Vec::new();
+        allocator.debug_free_list(|addr, size| regions.push((addr, size)));
+        return regions;
+    };
+    assert_eq!(free_list(), [(start, HEAP_SIZE)]);
+
+    // Both are padded to a 16 byte node, so four of either fill the heap.
+    for layout in [Layout::new::<u32>(), Layout::new::<u64>()] {
+        unsafe {
+            let ptrs: Vec<_> = (0..4)
+                .map(|_| allocator.try_allocate(layout).unwrap())
+                .collect();
+            for (i, ptr) in ptrs.iter().enumerate() {
+                assert_eq!(ptr.as_ptr() as usize, start + i * 16);
+                ptr.write_bytes(0xFF, layout.size());
+            }
+            assert_eq!(free_list(), []);
+
+            // Each free lands in front of the region after it and merges.
+            for ptr in ptrs.into_iter().rev() {
+                allocator.try_deallocate(ptr, layout).unwrap();
+                assert_eq!(free_list().len(), 1);
+            }
+        }
+        assert_eq!(free_list(), [(start, HEAP_SIZE)]);
+    }
+}
+
+#[test]
+fn coalesce_all_reports_merges() {
+    const HEAP_SIZE: usize = 64;
+    static mut LIST_MEM: Heap8Byte<HEAP_SIZE> = Heap8Byte([MaybeUninit::uninit(); HEAP_SIZE]);
+    static mut BUDDY_MEM: Heap4096Byte<HEAP_SIZE> =
+        Heap4096Byte([MaybeUninit::uninit(); HEAP_SIZE]);
+
+    let list = LockedLinkedListAlloc::new();
+    let buddy = LockedBuddyAlloc::new();
+    unsafe {
+        list.init(&raw mut LIST_MEM.0 as usize, HEAP_SIZE);
+        buddy.init(&raw mut BUDDY_MEM.0 as usize, HEAP_SIZE);
+    }
+    list.set_coalesce_policy(CoalescePolicy::Deferred);
+    buddy.set_merge_threshold(usize::MAX);
+
+    // Four 16 byte nodes fill the heap, and freeing them out of order
+    // leaves four touching regions, three adjacencies.
+    let layout = Layout::new::<u64>();
+    unsafe {
+        let ptrs: Vec<_> = (0..4).map(|_| list.try_allocate(layout).unwrap()).collect();
+        for i in [2, 0, 3, 1] {
+            list.try_deallocate(ptrs[i], layout).unwrap();
+        }
+    }
+    assert_eq!(list.free_region_count(), 4);
+    assert_eq!(list.coalesce_all(), 3);
+    assert_eq!(list.free_region_count(), 1);
+    assert_eq!(list.coalesce_all(), 0);
+
+    // Eight order 0 blocks merge pairwise up to the order 3 heap.
+    let layout = Layout::from_size_align(PAGE_SIZE, 8).unwrap();
+    unsafe {
+        let ptrs: Vec<_> = (0..8)
+            .map(|_| buddy.try_allocate(layout).unwrap())
+            .collect();
+        for ptr in ptrs {
+            buddy.try_deallocate(ptr, layout).unwrap();
+        }
+    }
+    assert_eq!(buddy.free_counts()[0], 8);
+    assert_eq!(buddy.coalesce_all(), (7, 3));
+    assert_eq!(buddy.free_counts()[3], 1);
+    assert_eq!(buddy.coalesce_all(), (0, 0));
+}
+
+#[test]
+fn buddy_locked_split_and_merge() {
+    const HEAP_SIZE: usize = 512;
+    static mut HEAP_MEM: Heap4096Byte<HEAP_SIZE> = Heap4096Byte([MaybeUninit::uninit(); HEAP_SIZE]);
+
+    let allocator = LockedBuddyAlloc::new();
+    let start = unsafe { &raw mut HEAP_MEM.0 as usize };
+    unsafe { allocator.init(start, HEAP_SIZE) };
+
+    let free_lists = || {
+        let mut lists: [Vec<usize>; 7] = Default::default();
+        for (order, list) in lists.iter_mut().enumerate() {
+            allocator.debug_free_list(order, |addr| list.push(addr));
+        }
+        return lists;
+    };
+    assert_eq!(free_lists()[6], [start]);
+
+    // 11 bytes take an order 1 block. Splits hand out the upper half, so
+    // the block is at the top of the heap and the lower half of every split
+    // is left free.
+    let layout = Layout::new::<[u8; 11]>();
+    let ptr = unsafe { allocator.try_allocate(layout).unwrap() };
+    let end = start + HEAP_SIZE;
+    assert_eq!(ptr.as_ptr() as usize, end - (PAGE_SIZE << 1));
+    let lists = free_lists();
+    assert!(lists[0].is_empty() && lists[6].is_empty());
+    for (order, list) in lists.iter().enumerate().take(6).skip(1) {
+        assert_eq!(*list, [end - (PAGE_SIZE << (order + 1))]);
+    }
+
+    unsafe { allocator.try_deallocate(ptr, layout).unwrap() };
+    let lists = free_lists();
+    assert!(lists[..6].iter().all(Vec::is_empty));
+    assert_eq!(lists[6], [start]);
+}
+
+#[test]
+fn buddy_whole_heap_after_free() {
+    const HEAP_SIZE: usize = 512;
+    static mut HEAP_MEM: Heap4096Byte<HEAP_SIZE> = Heap4096Byte([MaybeUninit::uninit(); HEAP_SIZE]);
+
+    let half = Layout::from_size_align(HEAP_SIZE / 2, 8).unwrap();
+    let whole = Layout::from_size_align(HEAP_SIZE, 8).unwrap();
+    let start = unsafe { &raw mut HEAP_MEM.0 as usize };
+    let tiny = Layout::new::<u8>();
+    // Merges deferred by a threshold are caught up on when the whole heap
+    // is asked for, and an emptied tiny slab block is given back.
+    for (threshold, tiny_slab) in [(0, false), (1, false), (4, false), (0, true)] {
+        let allocator = LockedBuddyAlloc::new();
+        unsafe { allocator.init(start, HEAP_SIZE) };
+        allocator.set_merge_threshold(threshold);
+        allocator.set_tiny_slab(tiny_slab);
+
+        for _ in 0..2 {
+            unsafe {
+                let ptr = allocator.try_allocate(half).unwrap();
+                let small = allocator.try_allocate(tiny).unwrap();
+                allocator.try_deallocate(ptr, half).unwrap();
+                allocator.try_deallocate(small, tiny).unwrap();
+
+                let ptr = allocator.try_allocate(whole).unwrap();
+                assert_eq!(ptr.as_ptr() as usize, start);
+                assert!(allocator.try_allocate(half).is_err());
+                allocator.try_deallocate(ptr, whole).unwrap();
+            }
         }
+        assert!(allocator.validate().is_ok());
+    }
+}
+
+#[test]
+fn buddy_on_top_free_after_trim() {
+    const HEAP_SIZE: usize = PAGE_SIZE * 64;
+    static mut HEAP_MEM: Heap4096Byte<HEAP_SIZE> = Heap4096Byte([MaybeUninit::uninit(); HEAP_SIZE]);
+    static CALLS: AtomicUsize = AtomicUsize::new(0);
+
+    let allocator = LockedBuddyAlloc::new();
+    unsafe { allocator.init(&raw mut HEAP_MEM.0 as usize, HEAP_SIZE) };
+    allocator.set_on_top_free(|_| {
+        CALLS.fetch_add(1, Ordering::Relaxed);
+    });
+
+    let half = Layout::from_size_align(PAGE_SIZE * 32, 8).unwrap();
+    let quarter = Layout::from_size_align(PAGE_SIZE * 16, 8).unwrap();
+    let eighth = Layout::from_size_align(PAGE_SIZE * 8, 8).unwrap();
+    unsafe {
+        // Keeps pages 32..40 and trims the 24 above them, leaving a 40 page
+        // heap whose top order block is the lower 32 pages.
+        let tail = allocator.try_allocate(quarter).unwrap();
+        let upper = allocator.try_allocate(eighth).unwrap();
+        let kept = allocator.try_allocate(eighth).unwrap();
+        allocator.try_deallocate(tail, quarter).unwrap();
+        allocator.try_deallocate(upper, eighth).unwrap();
+        assert_eq!(allocator.trim(), PAGE_SIZE * 24);
+
+        // Freeing that block leaves `kept` allocated past it.
+        let lower = allocator.try_allocate(half).unwrap();
+        allocator.try_deallocate(lower, half).unwrap();
+        assert_eq!(CALLS.load(Ordering::Relaxed), 0);
+
+        // Nor is the heap one block once `kept` is back.
+        allocator.try_deallocate(kept, eighth).unwrap();
+        assert_eq!(allocator.coalesce_all().0, 0);
+        assert_eq!(CALLS.load(Ordering::Relaxed), 0);
+
+        // Trimmed back to a power of two, the heap is reported again.
+        assert_eq!(allocator.trim(), PAGE_SIZE * 8);
+        let lower = allocator.try_allocate(half).unwrap();
+        allocator.try_deallocate(lower, half).unwrap();
+    }
+    assert_eq!(CALLS.load(Ordering::Relaxed), 1);
+    assert!(allocator.validate().is_ok());
+}
+
+#[test]
+fn buddy_on_top_free() {
+    const HEAP_SIZE: usize = 512;
+    static mut HEAP_MEM: Heap4096Byte<HEAP_SIZE> = Heap4096Byte([MaybeUninit::uninit(); HEAP_SIZE]);
+    static FREED_AT: AtomicUsize = AtomicUsize::new(0);
+    static CALLS: AtomicUsize = AtomicUsize::new(0);
+
+    let start = unsafe { &raw mut HEAP_MEM.0 as usize };
+    let allocator = LockedBuddyAlloc::new();
+    unsafe { allocator.init(start, HEAP_SIZE) };
+    allocator.set_on_top_free(|addr| {
+        FREED_AT.store(addr, Ordering::Relaxed);
+        CALLS.fetch_add(1, Ordering::Relaxed);
     });
+
+    let layout = Layout::from_size_align(HEAP_SIZE / 4, 8).unwrap();
+    unsafe {
+        let ptrs = [0; 4].map(|_| allocator.try_allocate(layout).unwrap());
+        for ptr in &ptrs[..3] {
+            allocator.try_deallocate(*ptr, layout).unwrap();
+        }
+        assert_eq!(CALLS.load(Ordering::Relaxed), 0);
+        allocator.try_deallocate(ptrs[3], layout).unwrap();
+    }
+    assert_eq!(CALLS.load(Ordering::Relaxed), 1);
+    assert_eq!(FREED_AT.load(Ordering::Relaxed), start);
+
+    // Merges deferred by the threshold report once `coalesce_all` does them.
+    allocator.set_merge_threshold(4);
+    let half = Layout::from_size_align(HEAP_SIZE / 2, 8).unwrap();
+    unsafe {
+        let ptrs = [0; 2].map(|_| allocator.try_allocate(half).unwrap());
+        for ptr in ptrs {
+            allocator.try_deallocate(ptr, half).unwrap();
+        }
+    }
+    assert_eq!(CALLS.load(Ordering::Relaxed), 1);
+    assert_eq!(allocator.coalesce_all(), (1, 6));
+    assert_eq!(CALLS.load(Ordering::Relaxed), 2);
+    assert_eq!(FREED_AT.load(Ordering::Relaxed), start);
+
+    // A deferred merge caught up on to serve an allocation is handed out,
+    // not reported.
+    let whole = Layout::from_size_align(HEAP_SIZE, 8).unwrap();
+    unsafe {
+        let ptrs = [0; 2].map(|_| allocator.try_allocate(half).unwrap());
+        for ptr in ptrs {
+            allocator.try_deallocate(ptr, half).unwrap();
+        }
+        let ptr = allocator.try_allocate(whole).unwrap();
+        assert_eq!(CALLS.load(Ordering::Relaxed), 2);
+        allocator.try_deallocate(ptr, whole).unwrap();
+    }
+    assert_eq!(CALLS.load(Ordering::Relaxed), 3);
 }
 
-// #[test]
-// fn bump_spin_boundary_conditions() {
-//     const HEAP_SIZE: usize = 100;
-//     static mut HEAP_MEM: [MaybeUninit<u8>; HEAP_SIZE] = [MaybeUninit::uninit(); HEAP_SIZE];
+#[test]
+fn buddy_top_order_bounds() {
+    const HEAP_SIZE: usize = 512;
+    static mut HEAP_MEM: Heap4096Byte<HEAP_SIZE> = Heap4096Byte([MaybeUninit::uninit(); HEAP_SIZE]);
 
-//     let allocator = Locked::new(BumpAlloc_spin::new());
+    let allocator = LockedBuddyAlloc::new();
+    unsafe { allocator.init(&raw mut HEAP_MEM.0 as usize, HEAP_SIZE) };
+    let top = HEAP_SIZE.ilog2() as usize - PAGE_SIZE.ilog2() as usize;
 
-//     unsafe {
-//         allocator.lock().init(&raw mut HEAP_MEM as usize, HEAP_SIZE);
+    // Freeing the top order block has no buddy to look for above it.
+    let whole = Layout::from_size_align(HEAP_SIZE, 8).unwrap();
+    unsafe {
+        let ptr = allocator.try_allocate(whole).unwrap();
+        allocator.try_deallocate(ptr, whole).unwrap();
+    }
+    assert_eq!(allocator.coalesce_all(), (0, 0));
+    assert_eq!(allocator.free_counts()[top], 1);
 
-//         let layout = Layout::from_size_align(10, 1).unwrap();
-//         let mut ptrs = Vec::new();
+    assert_eq!(allocator.prewarm(MAX_ORDER, 1), 0);
+    assert_eq!(allocator.prewarm(NR_MAX_ORDER, 1), 0);
+    assert_eq!(allocator.prewarm(usize::MAX, 1), 0);
+    assert_eq!(allocator.free_counts()[top], 1);
+}
 
-//         loop {
-//             let ptr = allocator.alloc(layout);
-//             if ptr.is_null() {
-//                 break;
-//             }
-//             ptrs.push(ptr);
-//         }
+#[cfg(target_pointer_width = "64")]
+#[test]
+#[should_panic(expected = "Buddy Allocator heap larger than a MAX_ORDER block")]
+fn buddy_rejects_heap_past_max_order() {
+    let _ = unsafe { LockedBuddyAlloc::new_with_static_heap(0x1000, PAGE_SIZE << NR_MAX_ORDER) };
+}
 
-//         assert!(!ptrs.is_empty());
+#[test]
+fn buddy_block_size_for() {
+    const HEAP_SIZE: usize = 256;
+    static mut HEAP_MEM: Heap4096Byte<HEAP_SIZE> = Heap4096Byte([MaybeUninit::uninit(); HEAP_SIZE]);
 
-//         let ptr = allocator.alloc(layout);
-//         assert!(ptr.is_null());
-//     }
-// }
+    // 17 bytes pad to 24, three pages, rounded up to a four page block.
+    let layout = Layout::from_size_align(17, 1).unwrap();
+    assert_eq!(LockedBuddy::block_size_for(layout).unwrap(), 4 * PAGE_SIZE);
+    assert_eq!(
+        LockedBuddy::block_size_for(Layout::new::<u8>()).unwrap(),
+        PAGE_SIZE << MIN_ORDER
+    );
+    assert!(matches!(
+        LockedBuddy::block_size_for(
+            Layout::from_size_align((PAGE_SIZE << MAX_ORDER) + 1, 8).unwrap()
+        ),
+        Err(BAllocatorError::TooLarge(_))
+    ));
 
-// #[test]
-// fn bump_lockless_boundary_conditions() {
-//     const HEAP_SIZE: usize = 100;
-//     static mut HEAP_MEM: [MaybeUninit<u8>; HEAP_SIZE] = [MaybeUninit::uninit(); HEAP_SIZE];
+    let allocator = LockedBuddyAlloc::new();
+    unsafe {
+        allocator.init(&raw mut HEAP_MEM.0 as usize, HEAP_SIZE);
+        let (ptr, usable) = allocator.try_allocate_with_usable(layout).unwrap();
+        assert_eq!(usable, LockedBuddy::block_size_for(layout).unwrap());
+        allocator.try_deallocate(ptr, layout).unwrap();
+    }
+}
 
-//     let mut allocator = BumpAlloc_lockless::new();
+#[test]
+fn buddy_rounds_odd_page_counts_up() {
+    const HEAP_SIZE: usize = PAGE_SIZE * 8;
+    static mut HEAP_MEM: Heap4096Byte<HEAP_SIZE> = Heap4096Byte([MaybeUninit::uninit(); HEAP_SIZE]);
 
-//     unsafe {
-//         allocator.init(&raw mut HEAP_MEM as usize, HEAP_SIZE);
+    // Three pages need a four page block, a two page one would leave the
+    // last page overlapping the neighbouring block.
+    let three_pages = Layout::from_size_align(PAGE_SIZE * 3, 8).unwrap();
+    let allocator = LockedBuddyAlloc::new();
+    unsafe {
+        allocator.init(&raw mut HEAP_MEM.0 as usize, HEAP_SIZE);
+        let first = allocator.try_allocate(three_pages).unwrap();
+        assert_eq!(allocator.free_counts()[2], 1);
+        let second = allocator.try_allocate(three_pages).unwrap();
+        assert_eq!(
+            first.as_ptr().addr().abs_diff(second.as_ptr().addr()),
+            PAGE_SIZE * 4
+        );
+        assert!(
+            allocator
+                .try_allocate(three_pages)
+                .is_err_and(|e| e.is_oom())
+        );
 
-//         let layout = Layout::from_size_align(10, 1).unwrap();
-//         let mut ptrs = Vec::new();
+        first.write_bytes(1, three_pages.size());
+        second.write_bytes(2, three_pages.size());
+        assert!(
+            core::slice::from_raw_parts(first.as_ptr(), three_pages.size())
+                .iter()
+                .all(|&b| b == 1)
+        );
 
-//         loop {
-//             let ptr = allocator.alloc(layout);
-//             if ptr.is_null() {
-//                 break;
-//             }
-//             ptrs.push(ptr);
-//         }
+        allocator.try_deallocate(second, three_pages).unwrap();
+        allocator.try_deallocate(first, three_pages).unwrap();
+    }
+    assert_eq!(allocator.free_counts()[3], 1);
+}
 
-//         assert!(!ptrs.is_empty());
+#[test]
+fn buddy_drain_into() {
+    const HEAP_SIZE: usize = 512;
+    static mut SOURCE_MEM: Heap4096Byte<HEAP_SIZE> =
+        Heap4096Byte([MaybeUninit::uninit(); HEAP_SIZE]);
+    static mut PAIR_MEM: Heap4096Byte<HEAP_SIZE> = Heap4096Byte([MaybeUninit::uninit(); HEAP_SIZE]);
 
-//         let ptr = allocator.alloc(layout);
-//         assert!(ptr.is_null());
-//     }
-// }
+    // Into an uninitialized allocator, which takes the split blocks over
+    // as they are.
+    let source = LockedBuddyAlloc::new();
+    let dest = LockedBuddyAlloc::new();
+    unsafe { source.init(&raw mut SOURCE_MEM.0 as usize, HEAP_SIZE) };
+    assert_eq!(source.prewarm(2, 3), 4);
+    let counts = source.free_counts();
+    assert!(matches!(
+        source.drain_into(&source),
+        Err(BAllocatorError::Unmergeable)
+    ));
+    assert!(source.drain_into(&dest).is_ok());
+    assert!(!source.is_initialized());
+    assert_eq!(source.free_counts(), [0; NR_MAX_ORDER]);
+    assert_eq!(dest.free_counts(), counts);
+    assert_eq!(dest.coalesce_all().1, 6);
 
-// #[test]
-// fn bump_const_boundary_conditions() {
-//     const HEAP_SIZE: usize = 100;
-//     let allocator = BumpAlloc_const::<HEAP_SIZE>::new();
+    // Into the heap just below, which grows over both halves.
+    let start = unsafe { &raw mut PAIR_MEM.0 as usize };
+    let lower = LockedBuddyAlloc::new();
+    let upper = LockedBuddyAlloc::new();
+    unsafe {
+        lower.init(start, HEAP_SIZE / 2);
+        upper.init(start + HEAP_SIZE / 2, HEAP_SIZE / 2);
+    }
+    let layout = Layout::from_size_align(16, 8).unwrap();
+    let ptr = unsafe { upper.try_allocate(layout).unwrap() };
+    assert!(matches!(
+        upper.drain_into(&lower),
+        Err(BAllocatorError::Unmergeable)
+    ));
+    assert!(matches!(
+        lower.drain_into(&upper),
+        Err(BAllocatorError::Unmergeable)
+    ));
+    unsafe { upper.try_deallocate(ptr, layout).unwrap() };
 
-//     unsafe {
-//         let layout = Layout::from_size_align(10, 1).unwrap();
-//         let mut ptrs = Vec::new();
+    assert!(upper.drain_into(&lower).is_ok());
+    assert_eq!(lower.free_counts()[5], 2);
+    assert_eq!(lower.coalesce_all(), (1, 6));
+    let whole = Layout::from_size_align(HEAP_SIZE, 8).unwrap();
+    let ptr = unsafe { lower.try_allocate(whole).unwrap() };
+    assert_eq!(ptr.as_ptr() as usize, start);
+    unsafe { lower.try_deallocate(ptr, whole).unwrap() };
+}
 
-//         loop {
-//             let ptr = allocator.alloc(layout);
-//             if ptr.is_null() {
-//                 break;
-//             }
-//             ptrs.push(ptr);
-//         }
-
-//         assert!(!ptrs.is_empty());
-
-//         let ptr = allocator.alloc(layout);
-//         assert!(ptr.is_null());
-//     }
-// }
-
-// #[test]
-// fn linked_list_spin_combine_free_regions() {
-//     const HEAP_SIZE: usize = 64;
-//     static mut HEAP_MEM: LinkedListHeap<HEAP_SIZE> = LinkedListHeap::new();
+/// As `bump_merge_with_opposite_orders`, for `drain_into`.
+#[test]
+fn buddy_drain_into_opposite_orders() {
+    const HEAP_SIZE: usize = 512;
+    const ROUNDS: usize = 2000;
+    static mut HEAP_MEM: Heap4096Byte<HEAP_SIZE> = Heap4096Byte([MaybeUninit::uninit(); HEAP_SIZE]);
+    static FIRST: LockedBuddyAlloc = LockedBuddyAlloc::new();
+    static SECOND: LockedBuddyAlloc = LockedBuddyAlloc::new();
 
-//     let allocator = Locked::new(LinkedListAlloc_spin::new());
+    // Live blocks on both sides, so every drain fails.
+    let start = unsafe { &raw mut HEAP_MEM.0 as usize };
+    let layout = Layout::from_size_align(16, 8).unwrap();
+    unsafe {
+        FIRST.init(start, HEAP_SIZE / 2);
+        SECOND.init(start + HEAP_SIZE / 2, HEAP_SIZE / 2);
+        FIRST.try_allocate(layout).unwrap();
+        SECOND.try_allocate(layout).unwrap();
+    }
+    let forward = std::thread::spawn(|| {
+        for _ in 0..ROUNDS {
+            assert!(FIRST.drain_into(&SECOND).is_err());
+        }
+    });
+    for _ in 0..ROUNDS {
+        assert!(SECOND.drain_into(&FIRST).is_err());
+    }
+    forward.join().unwrap();
+}
 
-//     unsafe {
-//         allocator.lock().init(&raw mut HEAP_MEM);
-
-//         let layout_u32 = Layout::new::<u32>();
-
-//         // println!("Initialized empty heap");
-//         // print_heap_dump(&raw mut HEAP_MEM.0 as *mut u8, HEAP_SIZE);
-//         // println!();
-
-//         let ptr1 = allocator.alloc(layout_u32) as *mut u32;
-//         let ptr2 = allocator.alloc(layout_u32) as *mut u32;
-//         let ptr3 = allocator.alloc(layout_u32) as *mut u32;
-//         let ptr4 = allocator.alloc(layout_u32) as *mut u32;
-
-//         assert!(!ptr1.is_null());
-//         assert!(!ptr2.is_null());
-//         assert!(!ptr3.is_null());
-//         assert!(!ptr4.is_null());
-
-//         // println!("Heap allocated with 4 u32 numbers");
-//         // print_heap_dump(&raw mut HEAP_MEM.0 as *mut u8, HEAP_SIZE);
-//         // println!();
-
-//         *ptr1 = u32::MAX;
-//         *ptr2 = u32::MAX;
-//         *ptr3 = u32::MAX;
-//         *ptr4 = u32::MAX;
-
-//         // println!("u32 numbers are set to u32::max");
-//         // print_heap_dump(&raw mut HEAP_MEM.0 as *mut u8, HEAP_SIZE);
-//         // println!();
-
-//         assert_eq!(*ptr4, u32::MAX);
-//         assert_eq!(*ptr3, u32::MAX);
-//         assert_eq!(*ptr2, u32::MAX);
-//         assert_eq!(*ptr1, u32::MAX);
-
-//         allocator.dealloc(ptr4 as *mut u8, layout_u32);
-//         allocator.dealloc(ptr3 as *mut u8, layout_u32);
-//         allocator.dealloc(ptr2 as *mut u8, layout_u32);
-//         allocator.dealloc(ptr1 as *mut u8, layout_u32);
-
-//         // println!("u32 numbers are deallocated");
-//         // print_heap_dump(&raw mut HEAP_MEM.0 as *mut u8, HEAP_SIZE);
-//         // println!();
+#[test]
+fn buddy_allocate_exact() {
+    const HEAP_SIZE: usize = PAGE_SIZE << 5;
+    static mut HEAP_MEM: Heap4096Byte<HEAP_SIZE> = Heap4096Byte([MaybeUninit::uninit(); HEAP_SIZE]);
 
-//         let layout_u64 = Layout::new::<u64>();
-
-//         let ptr5 = allocator.alloc(layout_u64) as *mut u64;
-//         let ptr6 = allocator.alloc(layout_u64) as *mut u64;
-//         let ptr7 = allocator.alloc(layout_u64) as *mut u64;
-//         let ptr8 = allocator.alloc(layout_u64) as *mut u64;
-
-//         assert!(!ptr5.is_null());
-//         assert!(!ptr6.is_null());
-//         assert!(!ptr7.is_null());
-//         assert!(!ptr8.is_null());
-
-//         // println!("Heap allocated with 4 u64 numbers");
-//         // print_heap_dump(&raw mut HEAP_MEM.0 as *mut u8, HEAP_SIZE);
-//         // println!();
-
-//         *ptr5 = u64::MAX;
-//         *ptr6 = u64::MAX;
-//         *ptr7 = u64::MAX;
-//         *ptr8 = u64::MAX;
-
-//         // println!("u64 numbers are set to u64::max");
-//         // print_heap_dump(&raw mut HEAP_MEM.0 as *mut u8, HEAP_SIZE);
-//         // println!();
-
-//         assert_eq!(*ptr8, u64::MAX);
-//         assert_eq!(*ptr7, u64::MAX);
-//         assert_eq!(*ptr6, u64::MAX);
-//         assert_eq!(*ptr5, u64::MAX);
-
-//         allocator.dealloc(ptr8 as *mut u8, layout_u64);
-//         allocator.dealloc(ptr7 as *mut u8, layout_u64);
-//         allocator.dealloc(ptr6 as *mut u8, layout_u64);
-//         allocator.dealloc(ptr5 as *mut u8, layout_u64);
-
-//         // println!("u64 numbers are deallocated");
-//         // print_heap_dump(&raw mut HEAP_MEM.0 as *mut u8, HEAP_SIZE);
-//         // println!();
-//     }
-// }
-
-// #[test]
-// fn linked_list_spin_boundary_conditions() {
-//     const HEAP_SIZE: usize = 64;
-//     static mut HEAP_MEM: LinkedListHeap<HEAP_SIZE> = LinkedListHeap::new();
+    let allocator = LockedBuddyAlloc::new();
+    unsafe { allocator.init(&raw mut HEAP_MEM.0 as usize, HEAP_SIZE) };
+    assert_eq!(allocator.free_counts()[5], 1);
 
-//     let allocator = Locked::new(LinkedListAlloc_spin::new());
-
-//     unsafe {
-//         allocator.lock().init(&raw mut HEAP_MEM);
+    // Only the order 5 block is free, so an exact order 2 request has
+    // nothing to take and must not split it.
+    let layout = Layout::from_size_align(PAGE_SIZE << 2, 1).unwrap();
+    let result = unsafe { allocator.try_allocate_exact(layout) };
+    assert!(matches!(result, Err(BAllocatorError::Oom(_))));
+    assert_eq!(allocator.free_counts()[5], 1);
+    assert_eq!(allocator.splits(), 0);
 
-//         let layout = Layout::from_size_align(10, 1).unwrap();
-//         let mut ptrs = Vec::new();
+    let ptr = unsafe { allocator.try_allocate(layout).unwrap() };
+    assert_eq!(allocator.splits(), 3);
+    let counts = allocator.free_counts();
+    assert_eq!(counts[5], 0);
+    assert_eq!(&counts[2..5], [1, 1, 1]);
 
-//         loop {
-//             let ptr = allocator.alloc(layout);
-//             if ptr.is_null() {
-//                 break;
-//             }
-//             ptrs.push(ptr);
-//         }
-
-//         assert!(!ptrs.is_empty());
-
-//         let ptr = allocator.alloc(layout);
-//         assert!(ptr.is_null());
-//     }
-// }
-
-// #[test]
-// fn buddy_spin_alloc() {
-//     #[repr(align(8))]
-//     pub struct Heap<const HEAP_SIZE: usize>(pub [MaybeUninit<u8>; HEAP_SIZE]);
-
-//     const HEAP_SIZE: usize = 512;
-//     static mut HEAP_MEM: Heap<HEAP_SIZE> = Heap([MaybeUninit::uninit(); HEAP_SIZE]);
-
-//     let allocator = Locked::new(BuddyAlloc_spin::new());
-
-//     unsafe {
-//         allocator
-//             .lock()
-//             .init_with_ptr(&raw mut HEAP_MEM as usize, HEAP_SIZE);
-
-//         // println!("{:?}", *allocator.lock());
-//         // print_heap_dump(&raw mut HEAP_MEM.0 as *mut u8, HEAP_SIZE);
-//         // println!();
-
-//         let layout_u8_16 = Layout::new::<[u8; 11]>();
+    // The lower half left by the last split is now an exact fit.
+    let exact = unsafe { allocator.try_allocate_exact(layout).unwrap() };
+    assert_eq!(
+        exact.as_ptr() as usize,
+        ptr.as_ptr() as usize - (PAGE_SIZE << 2)
+    );
+    assert_eq!(allocator.free_counts()[2], 0);
 
-//         let ptr1_u8_16 = allocator.alloc(layout_u8_16) as *mut [u8; 11];
-//         ptr1_u8_16.write([0xFF_u8; 11]);
-
-//         // println!("{:?}", *allocator.lock());
-//         // print_heap_dump(&raw mut HEAP_MEM.0 as *mut u8, HEAP_SIZE);
-//         // println!();
-
-//         allocator.dealloc(ptr1_u8_16 as *mut u8, layout_u8_16);
+    unsafe {
+        allocator.try_deallocate(exact, layout).unwrap();
+        allocator.try_deallocate(ptr, layout).unwrap();
+    }
+    assert_eq!(allocator.free_counts()[5], 1);
 
-//         // println!("{:?}", *allocator.lock());
-//         // print_heap_dump(&raw mut HEAP_MEM.0 as *mut u8, HEAP_SIZE);
-//         // println!();
-//     }
-// }
+    // Zero sized requests never reach the free lists, and every call is
+    // counted like `try_allocate`.
+    let zst = Layout::new::<()>();
+    let ptr = unsafe { allocator.try_allocate_exact(zst).unwrap() };
+    assert_eq!(ptr, dangling(zst));
+    assert_eq!(allocator.free_counts()[5], 1);
+    assert!(matches!(
+        allocator.last_error(),
+        Some(BAllocatorError::Oom(_))
+    ));
+    assert_eq!(allocator.metrics().total_alloc_calls, 4);
+    assert_eq!(allocator.metrics().total_alloc_failures, 1);
+}
@@ -0,0 +1,15 @@
+use crate::{
+    common::{Alloc, RwLocked},
+    lock::Mutex,
+};
+
+mod bconst;
+mod locked;
+mod lockless;
+
+pub use crate::tlsf_alloc::locked::{
+    ALIGN_SIZE, FL_INDEX_COUNT, FL_INDEX_MAX, LockedTlsf, MAX_BLOCK_SIZE, SL_INDEX_COUNT,
+};
+
+pub type LockedTlsfAlloc = Alloc<Mutex<LockedTlsf>>;
+pub type RwLockedTlsfAlloc = RwLocked<LockedTlsf>;
@@ -0,0 +1,522 @@
+use core::{
+    alloc::Layout,
+    mem::{align_of, size_of},
+    ptr::{NonNull, null_mut},
+};
+
+#[cfg(debug_assertions)]
+use log::{debug, error, trace};
+use spin::RwLock;
+
+#[cfg(feature = "fill_on_free")]
+use crate::common::fill_freed;
+use crate::{
+    common::{
+        ALLOCATOR_UNINITIALIZED, Alloc, AllocBounds, AllocInit, AllocState, BAllocatorError,
+        HEAP_END_OVERFLOWED, HEAP_SIZE_ZERO, HEAP_START_NULL, LockedInner, OOM, align_up,
+        check_alloc, check_free, check_write,
+    },
+    lock::Mutex,
+};
+
+const ALIGN_SIZE_LOG2: usize = 3;
+pub const ALIGN_SIZE: usize = 1 << ALIGN_SIZE_LOG2;
+
+const SL_INDEX_COUNT_LOG2: usize = 4;
+pub const SL_INDEX_COUNT: usize = 1 << SL_INDEX_COUNT_LOG2;
+
+const FL_INDEX_SHIFT: usize = SL_INDEX_COUNT_LOG2 + ALIGN_SIZE_LOG2;
+pub const FL_INDEX_MAX: usize = if usize::BITS > 32 { 32 } else { 31 };
+pub const FL_INDEX_COUNT: usize = FL_INDEX_MAX - FL_INDEX_SHIFT + 1;
+
+const SMALL_BLOCK_SIZE: usize = 1 << FL_INDEX_SHIFT;
+pub const MAX_BLOCK_SIZE: usize = 1 << FL_INDEX_MAX;
+
+const BLOCK_FREE: usize = 0b1;
+
+/*
+ * Only `prev_phys` and `size` are always present, the free list links
+ * overlap the payload and are only valid while the block is free.
+ */
+const BLOCK_OVERHEAD: usize = 2 * size_of::<usize>();
+const BLOCK_MIN_SIZE: usize = 2 * size_of::<usize>();
+
+#[derive(Debug)]
+#[repr(C)]
+struct BlockHeader {
+    prev_phys: Option<NonNull<BlockHeader>>,
+    size: usize,
+    next_free: Option<NonNull<BlockHeader>>,
+    prev_free: Option<NonNull<BlockHeader>>,
+}
+
+impl BlockHeader {
+    fn size(&self) -> usize {
+        return self.size & !BLOCK_FREE;
+    }
+
+    fn set_size(&mut self, size: usize) {
+        self.size = size | (self.size & BLOCK_FREE);
+    }
+
+    fn is_free(&self) -> bool {
+        return self.size & BLOCK_FREE != 0;
+    }
+
+    fn set_free(&mut self, free: bool) {
+        if free {
+            self.size |= BLOCK_FREE;
+        } else {
+            self.size &= !BLOCK_FREE;
+        }
+    }
+
+    fn start_addr(&self) -> usize {
+        return self as *const Self as usize;
+    }
+
+    fn payload_addr(&self) -> usize {
+        return self.start_addr() + BLOCK_OVERHEAD;
+    }
+
+    fn next_phys(&self) -> NonNull<BlockHeader> {
+        return unsafe { NonNull::new_unchecked((self.payload_addr() + self.size()) as *mut _) };
+    }
+}
+
+/// Maps a block size to its first and second level list indices.
+fn mapping_insert(size: usize) -> (usize, usize) {
+    if size < SMALL_BLOCK_SIZE {
+        return (0, size / (SMALL_BLOCK_SIZE / SL_INDEX_COUNT));
+    }
+    let fl = size.ilog2() as usize;
+    let sl = (size >> (fl - SL_INDEX_COUNT_LOG2)) ^ SL_INDEX_COUNT;
+    return (fl - (FL_INDEX_SHIFT - 1), sl);
+}
+
+/// Rounds the size up to the next list so any block found there fits it.
+fn mapping_search(size: usize) -> (usize, usize) {
+    if size < SMALL_BLOCK_SIZE {
+        return mapping_insert(size);
+    }
+    let round = (1 << (size.ilog2() as usize - SL_INDEX_COUNT_LOG2)) - 1;
+    return mapping_insert(size + round);
+}
+
+pub struct LockedTlsf {
+    base: *mut u8,
+    size: usize,
+    fl_bitmap: u32,
+    sl_bitmap: [u32; FL_INDEX_COUNT],
+    blocks: [[Option<NonNull<BlockHeader>>; SL_INDEX_COUNT]; FL_INDEX_COUNT],
+    free_bytes: usize,
+    allocations: usize,
+}
+
+impl Default for LockedTlsf {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl LockedTlsf {
+    const fn new() -> Self {
+        LockedTlsf {
+            base: null_mut(),
+            size: 0,
+            fl_bitmap: 0,
+            sl_bitmap: [0; FL_INDEX_COUNT],
+            blocks: [[None; SL_INDEX_COUNT]; FL_INDEX_COUNT],
+            free_bytes: 0,
+            allocations: 0,
+        }
+    }
+
+    unsafe fn init(&mut self, start: usize, size: usize) {
+        debug_assert!(start != 0, "{}", HEAP_START_NULL);
+        debug_assert!(size > 0, "{}", HEAP_SIZE_ZERO);
+        start.checked_add(size).expect(HEAP_END_OVERFLOWED);
+        debug_assert_eq!(
+            align_up(start, align_of::<BlockHeader>()),
+            start,
+            "Given start is not 8 byte aligned"
+        );
+        assert!(
+            size >= 2 * BLOCK_OVERHEAD + BLOCK_MIN_SIZE,
+            "TLSF Allocator heap too small to hold a single block"
+        );
+
+        self.base = start as *mut u8;
+        self.size = size;
+
+        let payload =
+            ((size - 2 * BLOCK_OVERHEAD) & !(ALIGN_SIZE - 1)).min(MAX_BLOCK_SIZE - ALIGN_SIZE);
+
+        unsafe {
+            let block = start as *mut BlockHeader;
+            check_write(start, size_of::<BlockHeader>(), start, start + size);
+            block.write_volatile(BlockHeader {
+                prev_phys: None,
+                size: payload | BLOCK_FREE,
+                next_free: None,
+                prev_free: None,
+            });
+
+            // Zero sized, always used, block marking the end of the heap so
+            // merging never walks past it. Only `BLOCK_OVERHEAD` bytes are
+            // left for it, so the free list links must not be written.
+            let sentinel = (*block).next_phys().as_ptr();
+            check_write(sentinel as usize, BLOCK_OVERHEAD, start, start + size);
+            (&raw mut (*sentinel).prev_phys).write_volatile(NonNull::new(block));
+            (&raw mut (*sentinel).size).write_volatile(0);
+
+            self.insert_free(NonNull::new_unchecked(block));
+        }
+    }
+
+    fn is_initialized(&self) -> bool {
+        return !self.base.is_null();
+    }
+
+    fn deinit(&mut self) {
+        debug_assert_eq!(self.allocations, 0, "Deinitialized with live allocations");
+        *self = LockedTlsf::new();
+    }
+
+    fn find_suitable(&self, size: usize) -> Option<NonNull<BlockHeader>> {
+        if size >= MAX_BLOCK_SIZE {
+            return None;
+        }
+        let (mut fl, sl) = mapping_search(size);
+        if fl >= FL_INDEX_COUNT {
+            return None;
+        }
+
+        let mut sl_map = self.sl_bitmap[fl] & (u32::MAX << sl);
+        if sl_map == 0 {
+            let fl_map = self.fl_bitmap & (u32::MAX << (fl + 1));
+            if fl_map == 0 {
+                return None;
+            }
+            fl = fl_map.trailing_zeros() as usize;
+            sl_map = self.sl_bitmap[fl];
+        }
+        return self.blocks[fl][sl_map.trailing_zeros() as usize];
+    }
+
+    unsafe fn insert_free(&mut self, mut block: NonNull<BlockHeader>) {
+        let (fl, sl) = mapping_insert(unsafe { block.as_ref().size() });
+
+        unsafe {
+            let header = block.as_mut();
+            header.set_free(true);
+            header.prev_free = None;
+            header.next_free = self.blocks[fl][sl];
+            if let Some(mut head) = self.blocks[fl][sl] {
+                head.as_mut().prev_free = Some(block);
+            }
+            self.free_bytes += header.size();
+        }
+
+        self.blocks[fl][sl] = Some(block);
+        self.fl_bitmap |= 1 << fl;
+        self.sl_bitmap[fl] |= 1 << sl;
+    }
+
+    unsafe fn remove_free(&mut self, mut block: NonNull<BlockHeader>) {
+        let header = unsafe { block.as_mut() };
+        let (fl, sl) = mapping_insert(header.size());
+
+        unsafe {
+            if let Some(mut prev) = header.prev_free {
+                prev.as_mut().next_free = header.next_free;
+            } else {
+                self.blocks[fl][sl] = header.next_free;
+            }
+            if let Some(mut next) = header.next_free {
+                next.as_mut().prev_free = header.prev_free;
+            }
+        }
+        header.set_free(false);
+        header.next_free = None;
+        header.prev_free = None;
+        self.free_bytes -= header.size();
+
+        if self.blocks[fl][sl].is_none() {
+            self.sl_bitmap[fl] &= !(1 << sl);
+            if self.sl_bitmap[fl] == 0 {
+                self.fl_bitmap &= !(1 << fl);
+            }
+        }
+    }
+
+    /// Splits `block` at `size` bytes of payload, returning the tail block.
+    unsafe fn split(
+        &mut self,
+        mut block: NonNull<BlockHeader>,
+        size: usize,
+    ) -> NonNull<BlockHeader> {
+        unsafe {
+            let header = block.as_mut();
+            let tail_size = header.size() - size - BLOCK_OVERHEAD;
+            let tail = NonNull::new_unchecked((header.payload_addr() + size) as *mut BlockHeader);
+            let base = self.base as usize;
+            check_write(
+                tail.as_ptr() as usize,
+                size_of::<BlockHeader>(),
+                base,
+                base + self.size,
+            );
+
+            tail.as_ptr().write_volatile(BlockHeader {
+                prev_phys: Some(block),
+                size: tail_size,
+                next_free: None,
+                prev_free: None,
+            });
+            (*tail.as_ref().next_phys().as_ptr()).prev_phys = Some(tail);
+            header.set_size(size);
+
+            #[cfg(debug_assertions)]
+            trace!(
+                "Split block {:#X} at size {}, tail {:#X} size {}",
+                header.start_addr(),
+                size,
+                tail.as_ptr() as usize,
+                tail_size
+            );
+            return tail;
+        }
+    }
+
+    /// Merges `next` into `block`, `next` must directly follow `block`.
+    unsafe fn absorb(&mut self, mut block: NonNull<BlockHeader>, next: NonNull<BlockHeader>) {
+        unsafe {
+            let header = block.as_mut();
+            header.set_size(header.size() + BLOCK_OVERHEAD + next.as_ref().size());
+            (*header.next_phys().as_ptr()).prev_phys = Some(block);
+        }
+    }
+
+    fn adjust_size(size: usize) -> Option<usize> {
+        let size = size.checked_add(ALIGN_SIZE - 1)? & !(ALIGN_SIZE - 1);
+        return Some(size.max(BLOCK_MIN_SIZE));
+    }
+
+    /// Over-aligned requests search for enough slack to carve off a leading
+    /// free block in front of the aligned payload.
+    fn search_size(size: usize, align: usize) -> Option<usize> {
+        if align > ALIGN_SIZE {
+            return size.checked_add(align + BLOCK_OVERHEAD + BLOCK_MIN_SIZE);
+        }
+        return Some(size);
+    }
+}
+
+unsafe impl LockedInner for LockedTlsf {
+    unsafe fn allocate(&mut self, layout: Layout) -> Result<NonNull<u8>, BAllocatorError> {
+        if !self.is_initialized() {
+            #[cfg(debug_assertions)]
+            error!("{}", ALLOCATOR_UNINITIALIZED);
+            return Err(BAllocatorError::Uninitialized);
+        }
+        let size = LockedTlsf::adjust_size(layout.size()).ok_or(BAllocatorError::Overflowed)?;
+        let align = layout.align();
+
+        let search_size =
+            LockedTlsf::search_size(size, align).ok_or(BAllocatorError::Overflowed)?;
+
+        let mut block = match self.find_suitable(search_size) {
+            Some(block) => block,
+            None => {
+                #[cfg(debug_assertions)]
+                error!("{}", OOM);
+                return Err(BAllocatorError::Oom(Some(layout)));
+            }
+        };
+
+        unsafe {
+            self.remove_free(block);
+
+            if align > ALIGN_SIZE {
+                let payload = block.as_ref().payload_addr();
+                let mut gap = align_up(payload, align) - payload;
+                if gap != 0 && gap < BLOCK_OVERHEAD + BLOCK_MIN_SIZE {
+                    gap = align_up(payload + BLOCK_OVERHEAD + BLOCK_MIN_SIZE, align) - payload;
+                }
+                if gap != 0 {
+                    let lead = block;
+                    block = self.split(lead, gap - BLOCK_OVERHEAD);
+                    self.insert_free(lead);
+                }
+            }
+
+            if block.as_ref().size() >= size + BLOCK_OVERHEAD + BLOCK_MIN_SIZE {
+                let tail = self.split(block, size);
+                self.insert_free(tail);
+            }
+        }
+        self.allocations += 1;
+
+        let alloc_start = unsafe { block.as_ref().payload_addr() };
+
+        let base = self.base as usize;
+        check_alloc(alloc_start, layout, base, base + self.size);
+        #[cfg(debug_assertions)]
+        debug!("Allocated object \"{:X}\"; layout: {layout:?}", alloc_start);
+        return NonNull::new(alloc_start as *mut u8).ok_or(BAllocatorError::Null);
+    }
+
+    unsafe fn deallocate(
+        &mut self,
+        ptr: NonNull<u8>,
+        _layout: Layout,
+    ) -> Result<(), BAllocatorError> {
+        let base = self.base as usize;
+        check_free(
+            (ptr.as_ptr() as usize).wrapping_sub(BLOCK_OVERHEAD),
+            BLOCK_OVERHEAD,
+            base,
+            base + self.size,
+        )?;
+        let mut block = unsafe { ptr.cast::<BlockHeader>().byte_sub(BLOCK_OVERHEAD) };
+
+        unsafe {
+            debug_assert!(!block.as_ref().is_free(), "Block is already free");
+
+            #[cfg(feature = "fill_on_free")]
+            fill_freed(ptr, _layout.size());
+
+            if let Some(prev) = block.as_ref().prev_phys
+                && prev.as_ref().is_free()
+            {
+                self.remove_free(prev);
+                self.absorb(prev, block);
+                block = prev;
+            }
+
+            let next = block.as_ref().next_phys();
+            if next.as_ref().is_free() {
+                self.remove_free(next);
+                self.absorb(block, next);
+            }
+
+            self.insert_free(block);
+        }
+        self.allocations -= 1;
+
+        #[cfg(debug_assertions)]
+        debug!(
+            "Deallocated object \"{:X}\"; layout: {_layout:?}",
+            ptr.as_ptr() as usize
+        );
+        return Ok(());
+    }
+
+    fn can_allocate(&self, layout: Layout) -> bool {
+        let Some(size) = LockedTlsf::adjust_size(layout.size()) else {
+            return false;
+        };
+        let search_size = LockedTlsf::search_size(size, layout.align());
+        return search_size.is_some_and(|size| self.find_suitable(size).is_some());
+    }
+
+    fn base_alignment(&self) -> usize {
+        return ALIGN_SIZE;
+    }
+}
+
+impl AllocState for LockedTlsf {
+    fn remaining(&self) -> usize {
+        return self.free_bytes;
+    }
+    fn allocations(&self) -> usize {
+        return self.allocations;
+    }
+}
+
+impl AllocBounds for LockedTlsf {
+    fn contains(&self, ptr: NonNull<u8>) -> bool {
+        let base = self.base as usize;
+        return (base..base + self.size).contains(&(ptr.as_ptr() as usize));
+    }
+}
+
+unsafe impl Sync for Alloc<Mutex<LockedTlsf>> {}
+unsafe impl Send for Alloc<Mutex<LockedTlsf>> {}
+
+impl Alloc<Mutex<LockedTlsf>> {
+    pub const fn new() -> Self {
+        Alloc::wrap(Mutex::new(LockedTlsf::new()))
+    }
+}
+
+#[cfg(test)]
+impl Alloc<Mutex<LockedTlsf>> {
+    pub(crate) fn corrupt_base(&self, base: usize) {
+        self.lock().base = base as *mut u8;
+    }
+}
+
+impl Default for Alloc<Mutex<LockedTlsf>> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl AllocInit for Mutex<LockedTlsf> {
+    unsafe fn init(&self, start: usize, size: usize) {
+        unsafe {
+            #[cfg(debug_assertions)]
+            debug!("Initialized locked tlsf alloc; start: {start:#X}, size: {size}");
+            self.lock().init(start, size);
+        }
+    }
+
+    unsafe fn deinit(&self) -> Result<(), BAllocatorError> {
+        #[cfg(debug_assertions)]
+        debug!("Deinitialized locked tlsf alloc");
+        self.lock().deinit();
+        return Ok(());
+    }
+
+    fn is_initialized(&self) -> bool {
+        return self.lock().is_initialized();
+    }
+}
+
+unsafe impl Sync for Alloc<RwLock<LockedTlsf>> {}
+unsafe impl Send for Alloc<RwLock<LockedTlsf>> {}
+
+impl Alloc<RwLock<LockedTlsf>> {
+    pub const fn new() -> Self {
+        Alloc::wrap(RwLock::new(LockedTlsf::new()))
+    }
+}
+
+impl Default for Alloc<RwLock<LockedTlsf>> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl AllocInit for RwLock<LockedTlsf> {
+    unsafe fn init(&self, start: usize, size: usize) {
+        unsafe {
+            #[cfg(debug_assertions)]
+            debug!("Initialized rwlocked tlsf alloc; start: {start:#X}, size: {size}");
+            self.write().init(start, size);
+        }
+    }
+
+    unsafe fn deinit(&self) -> Result<(), BAllocatorError> {
+        #[cfg(debug_assertions)]
+        debug!("Deinitialized rwlocked tlsf alloc");
+        self.write().deinit();
+        return Ok(());
+    }
+
+    fn is_initialized(&self) -> bool {
+        return self.read().is_initialized();
+    }
+}
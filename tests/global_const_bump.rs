@@ -0,0 +1,22 @@
+//! `#![no_main]` keeps the setup identical to the runtime initialized
+//! allocators, see `global_locked_bump.rs`.
+#![no_main]
+#![allow(clippy::needless_return)]
+
+use std::{boxed::Box, ffi::c_int, vec::Vec};
+
+use simple_alloc::bump_alloc::ConstBumpAlloc;
+
+const HEAP_SIZE: usize = 64 * 1024;
+
+#[global_allocator]
+static ALLOCATOR: ConstBumpAlloc<HEAP_SIZE> = ConstBumpAlloc::new();
+
+#[unsafe(no_mangle)]
+extern "C" fn main(_argc: c_int, _argv: *const *const u8) -> c_int {
+    let boxed = Box::new(5u64);
+    let values: Vec<u32> = (0..100).collect();
+    assert_eq!(*boxed, 5);
+    assert_eq!(values.iter().sum::<u32>(), 4950);
+    return 0;
+}
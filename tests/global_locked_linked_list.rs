@@ -0,0 +1,29 @@
+//! `#![no_main]` skips the std runtime setup, which allocates before `main`
+//! and would otherwise hit the allocator before its heap is initialized.
+#![no_main]
+#![allow(clippy::needless_return)]
+
+use std::{boxed::Box, ffi::c_int, mem::MaybeUninit, vec::Vec};
+
+use simple_alloc::{AllocInit, linked_list_alloc::LockedLinkedListAlloc};
+
+const HEAP_SIZE: usize = 64 * 1024;
+
+#[repr(align(8))]
+struct Heap([MaybeUninit<u8>; HEAP_SIZE]);
+
+static mut HEAP_MEM: Heap = Heap([MaybeUninit::uninit(); HEAP_SIZE]);
+
+#[global_allocator]
+static ALLOCATOR: LockedLinkedListAlloc = LockedLinkedListAlloc::new();
+
+#[unsafe(no_mangle)]
+extern "C" fn main(_argc: c_int, _argv: *const *const u8) -> c_int {
+    unsafe { ALLOCATOR.init(&raw mut HEAP_MEM.0 as usize, HEAP_SIZE) };
+
+    let boxed = Box::new(5u64);
+    let values: Vec<u32> = (0..100).collect();
+    assert_eq!(*boxed, 5);
+    assert_eq!(values.iter().sum::<u32>(), 4950);
+    return 0;
+}